@@ -4,6 +4,8 @@ await init();
 
 const client = new Client();
 window.client = client;
+// The games that are tables of a duplicate set.
+const duplicateTables = new Set();
 
 function getCookie(name) {
     const prefix = name + "=";
@@ -127,7 +129,7 @@ function updateWarGame(gameId, game, gameElem) {
 
         const finishTrickElem = gameElem.querySelector(".finish-trick");
         finishTrickElem.disabled = (!game.phase.finished && game.phase.currTrick.winner === undefined)
-            || (game.phase.currTrick.endMask & (1 << index)) === 0;
+            || game.phase.currTrick.ended[index];
 
         const playsElem = gameElem.querySelector(".my-plays");
         const newPlaysElem = document.createDocumentFragment();
@@ -326,6 +328,7 @@ function unstartedGamePlayerElement(gameId, userId) {
         ],
     });
     element.classList.toggle("online", user.online);
+    element.classList.toggle("bot", user.bot);
     element.classList.toggle("self", userId === window.userId);
     return element;
 }
@@ -338,6 +341,7 @@ function unstartedGameAddPlayerElement(userId) {
         textContent: user.name
     });
     element.classList.toggle("online", user.online);
+    element.classList.toggle("bot", user.bot);
     element.classList.toggle("self", userId === window.userId);
     return element;
 }
@@ -384,6 +388,7 @@ function nameElement(userId) {
         textContent: user.name
     });
     element.classList.toggle("online", user.online);
+    element.classList.toggle("bot", user.bot);
     element.classList.toggle("self", userId === window.userId);
     return element;
 }
@@ -501,7 +506,8 @@ function rummyGamePlayerInfoElement(userId) {
         children: [
             nameElement(userId),
             createElement("p", {classList: ["other-hand"]}),
-            createElement("p", {classList: ["last-play"]})
+            createElement("p", {classList: ["last-play"]}),
+            createElement("p", {classList: ["goat-meter"]})
         ]
     });
 }
@@ -633,7 +639,7 @@ function updateTrickElements(playElements, sloughElements, trick) {
         return;
     }
     for (let i = 0; i < playElements.length; i++) {
-        const next = trick.winner === undefined ? i === trick.next : (trick.endMask & (1 << i)) !== 0;
+        const next = trick.winner === undefined ? i === trick.next : !trick.ended[i];
         playElements[i].parentElement.classList.toggle("next", next);
     }
     for (const play of trick.plays) {
@@ -675,6 +681,17 @@ function pretty(card) {
     });
 }
 
+export function updateGoatMeter(gameId, goatChances) {
+    const gameElem = document.querySelector(`[data-gameId="${gameId}"]`);
+    if (!gameElem || gameElem.dataset.phase !== "rummy") {
+        return;
+    }
+    const meterElems = gameElem.querySelectorAll(".goat-meter");
+    for (let i = 0; i < meterElems.length; i++) {
+        meterElems[i].textContent = `Goat: ${goatChances[i]}%`;
+    }
+}
+
 export function forgetGame(gameId) {
     const gameNodes = document.querySelectorAll(`[data-gameId="${gameId}"]`);
     for (const gameNode of gameNodes) {
@@ -694,6 +711,7 @@ export function updateUser(userId, user) {
     }
     for (const userNode of userNodes) {
         userNode.classList.toggle("online", user.online);
+        userNode.classList.toggle("bot", user.bot);
         userNode.classList.toggle("self", userId === window.userId);
         const textNode = userNode.querySelector("span") ?? userNode;
         textNode.textContent = user.name;
@@ -714,7 +732,17 @@ export function forgetUser(userId) {
 }
 
 export function joinGame(gameId, userId) {
-    applyAction(gameId, `{"type":"join","userId":"${userId}"}`);
+    const joined = applyAction(gameId, `{"type":"join","userId":"${userId}"}`);
+    // Duplicate tables are all dealt the same cards, so the server refuses entropy for them.
+    if (userId === window.userId && !duplicateTables.has(gameId)) {
+        // Mix some of our own randomness into the shuffle, so the server can't pick the deal.
+        joined.then(() => applyAction(gameId, `{"type":"addEntropy","entropy":"${randomId()}"}`));
+    }
+}
+
+function randomId() {
+    const alphabet = "./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    return Array.from(crypto.getRandomValues(new Uint8Array(16)), (b) => alphabet[b % 64]).join("");
 }
 
 export function leaveGame(gameId, userId) {
@@ -778,7 +806,7 @@ function disableButtons(gameId) {
 }
 
 function applyAction(gameId, action) {
-    fetch(`./apply_action?game_id=${gameId}`, {
+    return fetch(`./apply_action?game_id=${gameId}`, {
         method: "POST",
         headers: { "Content-Type": "application/json" },
         body: action,
@@ -820,7 +848,7 @@ if (getCookie("USER_NAME") === null) {
     document.cookie = "USER_NAME=Anonymous";
 }
 
-new EventSource("./subscribe").onmessage = function(event) {
+new EventSource("./subscribe?version=4&capabilities=snapshots").onmessage = function(event) {
     if (!window.userId) {
         window.userId = getCookie("USER_ID");
     }
@@ -832,9 +860,19 @@ new EventSource("./subscribe").onmessage = function(event) {
             signalUpdate();
             break;
         case "replay":
+        case "snapshot":
             updateGame(response.gameId, true);
             break;
+        case "goatMeter":
+            updateGoatMeter(response.gameId, response.goatChances);
+            break;
+        case "duplicateSet":
+            for (const gameId of response.gameIds) {
+                duplicateTables.add(gameId);
+            }
+            break;
         case "forgetGame":
+            duplicateTables.delete(response.gameId);
             forgetGame(response.gameId);
             break;
         case "user":
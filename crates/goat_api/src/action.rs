@@ -1,8 +1,10 @@
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
-use crate::{Card, PlayerIdx, UserId};
+use crate::{Card, PlayerIdx, RandId, UserId};
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, JsonSchema, PartialEq, Serialize, TS)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 pub enum Action {
@@ -11,20 +13,26 @@ pub enum Action {
     #[serde(rename_all = "camelCase")]
     Leave { player: PlayerIdx },
     #[serde(rename_all = "camelCase")]
+    Invite { user_id: UserId },
+    /// Mixes randomness chosen by a player into the shuffle, so that not even the server knows
+    /// the deal before the game starts.
+    #[serde(rename_all = "camelCase")]
+    AddEntropy { entropy: RandId },
+    #[serde(rename_all = "camelCase")]
     Start { num_decks: u8 },
     #[serde(rename_all = "camelCase")]
     PlayCard { card: Card },
-    #[serde(rename_all = "camelCase")]
+    /// Plays the top card of the deck.
     PlayTop,
     #[serde(rename_all = "camelCase")]
     Slough { card: Card },
-    #[serde(rename_all = "camelCase")]
+    /// Draws a card from the deck into the player's hand.
     Draw,
-    #[serde(rename_all = "camelCase")]
+    /// Ends a trick that has been won, or that nobody can play on.
     FinishTrick,
     #[serde(rename_all = "camelCase")]
     PlayRun { lo: Card, hi: Card },
-    #[serde(rename_all = "camelCase")]
+    /// Picks up the top of the trick instead of playing on it.
     PickUp,
     #[serde(rename_all = "camelCase")]
     Goat { noise: usize },
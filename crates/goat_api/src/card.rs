@@ -1,16 +1,20 @@
-use std::convert::{Infallible, TryFrom};
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::fmt::{Debug, Display, Write as _};
 use std::ops::Add;
 use std::str::FromStr;
 use std::{fmt, mem};
 
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ts_rs::TS;
 
-use crate::{Cards, Rank, Suit};
+use crate::{Cards, GoatError, Rank, Suit};
 
 #[repr(u8)]
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, TS)]
+#[ts(type = "string")]
 pub enum Card {
     TwoClubs = 0,
     ThreeClubs,
@@ -109,19 +113,26 @@ impl Debug for Card {
 }
 
 impl FromStr for Card {
-    type Err = Infallible;
+    type Err = GoatError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut chars = s.chars();
-        let rank = Rank::try_from(chars.next().unwrap()).unwrap();
-        let suit = Suit::try_from(chars.next().unwrap()).unwrap();
-        Ok(Card::new(rank, suit))
+        if let (Some(rank), Some(suit), None) = (chars.next(), chars.next(), chars.next()) {
+            if let (Ok(rank), Ok(suit)) = (Rank::try_from(rank), Suit::try_from(suit)) {
+                return Ok(Card::new(rank, suit));
+            }
+        }
+        Err(GoatError::InvalidCard {
+            card: s.to_string(),
+        })
     }
 }
 
-impl From<String> for Card {
-    fn from(s: String) -> Self {
-        Card::from_str(&s).unwrap()
+impl TryFrom<String> for Card {
+    type Error = GoatError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Card::from_str(&s)
     }
 }
 
@@ -149,6 +160,19 @@ impl Serialize for Card {
     }
 }
 
+impl JsonSchema for Card {
+    fn schema_name() -> Cow<'static, str> {
+        "Card".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "pattern": "^[2-9TJQKA][CDHS]$",
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for Card {
     fn deserialize<D>(des: D) -> Result<Self, D::Error>
     where
@@ -166,7 +190,7 @@ impl<'de> Deserialize<'de> for Card {
             where
                 E: Error,
             {
-                Ok(Card::from_str(v).unwrap())
+                Card::from_str(v).map_err(E::custom)
             }
         }
         des.deserialize_str(Visitor)
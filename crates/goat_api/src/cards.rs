@@ -1,19 +1,54 @@
-use std::convert::{Infallible, TryFrom};
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::fmt;
 use std::fmt::{Debug, Display, Write};
 use std::iter::FromIterator;
 use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 use std::str::FromStr;
 
-use serde::de::{SeqAccess, Visitor};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::{Error as _, SeqAccess, Visitor};
 use serde::ser::SerializeSeq;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ts_rs::TS;
 
-use crate::{Card, Rank, Suit};
+use crate::{invalid_notation, Card, GoatError, Rank, Suit};
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+/// A multiset of cards. Each suit gets its own lane, with four bits per rank counting the copies
+/// of that card, so the bit for a card in its lane lines up with its `u8` representation. Counts
+/// are kept to at most [`Cards::MAX_COPIES`] so that the top bit of every rank is free, which lets
+/// comparisons run on a whole suit at once.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, TS)]
+#[ts(as = "Vec<Card>")]
 pub struct Cards {
-    pub bits: u128,
+    pub suits: [u64; 4],
+}
+
+/// The lowest bit of every rank in a suit.
+const RANKS: u64 = 0x0001_1111_1111_1111;
+
+/// The highest bit of every rank in a suit, which is never set in a valid lane.
+const GUARDS: u64 = RANKS << 3;
+
+/// The lowest bit of every rank with at least one copy in `lane`.
+fn presence(lane: u64) -> u64 {
+    (lane | (lane >> 1) | (lane >> 2) | (lane >> 3)) & RANKS
+}
+
+/// Fills all four bits of each rank that has its lowest bit set in `ranks`.
+fn spread(ranks: u64) -> u64 {
+    ranks * 0xf
+}
+
+fn lane_len(lane: u64) -> u32 {
+    (0..3)
+        .map(|bit| ((lane >> bit) & RANKS).count_ones() << bit)
+        .sum()
+}
+
+fn lane_and_shift(card: Card) -> (usize, u32) {
+    let card = card as u8;
+    ((card / 16) as usize, 4 * (card % 16) as u32)
 }
 
 impl Serialize for Cards {
@@ -29,6 +64,19 @@ impl Serialize for Cards {
     }
 }
 
+impl JsonSchema for Cards {
+    fn schema_name() -> Cow<'static, str> {
+        "Cards".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "array",
+            "items": generator.subschema_for::<Card>(),
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for Cards {
     fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
     where
@@ -52,33 +100,51 @@ impl<'de> Visitor<'de> for CardsVisitor {
         A: SeqAccess<'de>,
     {
         while let Some(card) = seq.next_element::<Card>()? {
-            self.0 += card;
+            self.0 = self.0.checked_add(card).ok_or_else(|| {
+                A::Error::custom(format!(
+                    "more than {} copies of {}",
+                    Cards::MAX_COPIES,
+                    card
+                ))
+            })?;
         }
         Ok(self.0)
     }
 }
 
 impl Cards {
-    pub const NONE: Cards = Cards {
-        bits: 0x0000_0000_0000_0000_0000_0000_0000_0000,
-    };
+    /// The most copies of any one card that can be held.
+    pub const MAX_COPIES: usize = 7;
+
+    pub const NONE: Cards = Cards { suits: [0; 4] };
     pub const CLUBS: Cards = Cards {
-        bits: 0x0000_0000_0000_0000_0000_0000_0155_5555,
-    };
-    pub const ONE_DECK: Cards = Cards {
-        bits: 0x0155_5555_0155_5555_0155_5555_0155_5555,
-    };
-    pub const COMMON_DRECK: Cards = Cards {
-        bits: 0x0000_0055_0000_0055_0000_0055_0000_0055,
+        suits: [RANKS, 0, 0, 0],
     };
+    pub const ONE_DECK: Cards = Cards { suits: [RANKS; 4] };
+    pub const COMMON_DRECK: Cards = Cards { suits: [0x1111; 4] };
 
     pub fn range(lo: Card, hi: Card) -> Cards {
-        let lo_bits = Cards::from(lo).bits;
-        let hi_bits = Cards::from(hi).bits;
-        let range_bits = 2 * hi_bits - lo_bits;
-        Cards {
-            bits: range_bits & Self::ONE_DECK.bits,
+        let (lo_lane, lo_shift) = lane_and_shift(lo);
+        let (hi_lane, hi_shift) = lane_and_shift(hi);
+        let mut cards = Cards::NONE;
+        for lane in lo_lane..=hi_lane {
+            let lo_bit = if lane == lo_lane { 1 << lo_shift } else { 1 };
+            let hi_bit = if lane == hi_lane {
+                1 << hi_shift
+            } else {
+                1 << 48
+            };
+            cards.suits[lane] = (2 * hi_bit - lo_bit) & RANKS;
         }
+        cards
+    }
+
+    /// The cards with another copy of `card`, or `None` if there are already
+    /// [`Cards::MAX_COPIES`] of it.
+    pub fn checked_add(self, card: Card) -> Option<Cards> {
+        let (lane, shift) = lane_and_shift(card);
+        let copies = (self.suits[lane] >> shift) & 0xf;
+        (copies < Cards::MAX_COPIES as u64).then(|| self + card)
     }
 
     pub fn is_empty(self) -> bool {
@@ -86,68 +152,71 @@ impl Cards {
     }
 
     pub fn len(self) -> usize {
-        let hi = (self.bits & (Self::ONE_DECK.bits << 1)).count_ones();
-        let lo = (self.bits & Self::ONE_DECK.bits).count_ones();
-        (2 * hi + lo) as usize
+        self.suits.iter().map(|lane| lane_len(*lane)).sum::<u32>() as usize
     }
 
     pub fn max(self) -> Card {
         debug_assert!(!self.is_empty());
-        Card::from((63 - self.bits.leading_zeros() / 2) as u8)
+        let lane = self.suits.iter().rposition(|lane| *lane != 0).unwrap_or(0);
+        let rank = (63 - self.suits[lane].leading_zeros()) / 4;
+        Card::from((16 * lane as u32 + rank) as u8)
     }
 
     pub fn min(self) -> Card {
         debug_assert!(!self.is_empty());
-        Card::from((self.bits.trailing_zeros() / 2) as u8)
+        let lane = self.suits.iter().position(|lane| *lane != 0).unwrap_or(0);
+        let rank = self.suits[lane].trailing_zeros() / 4;
+        Card::from((16 * lane as u32 + rank) as u8)
     }
 
     pub fn in_suit(self, suit: Suit) -> Cards {
-        let mask = 0x03ff_ffff << (32 * suit.idx());
-        Cards {
-            bits: self.bits & mask,
-        }
+        let mut cards = Cards::NONE;
+        cards.suits[suit.idx()] = self.suits[suit.idx()];
+        cards
     }
 
+    /// One copy of each card in the same suit as `card` with a higher rank.
     pub fn above(self, card: Card) -> Cards {
-        let suit_mask = 0x0155_5555 << (32 * card.suit().idx());
-        let rank_mask = !(2 * Cards::from(card).bits - 1);
-        Cards {
-            bits: self.bits & suit_mask & rank_mask,
-        }
+        let (lane, shift) = lane_and_shift(card);
+        let mut cards = Cards::NONE;
+        cards.suits[lane] = presence(self.suits[lane]) & !((1 << (shift + 4)) - 1);
+        cards
     }
 
+    /// One copy of each card in the same suit as `card` with a lower rank.
     pub fn below(self, card: Card) -> Cards {
-        let suit_mask = 0x0155_5555 << (32 * card.suit().idx());
-        let rank_mask = Cards::from(card).bits - 1;
-        Cards {
-            bits: self.bits & suit_mask & rank_mask,
-        }
+        let (lane, shift) = lane_and_shift(card);
+        let mut cards = Cards::NONE;
+        cards.suits[lane] = presence(self.suits[lane]) & ((1 << shift) - 1);
+        cards
     }
 
     pub fn contains(self, other: Card) -> bool {
-        let mask = 0b11_u128 << (other as u8 * 2);
-        (self.bits & mask) != 0
+        let (lane, shift) = lane_and_shift(other);
+        (self.suits[lane] >> shift) & 0xf != 0
     }
 
     pub fn contains_any(self, other: Cards) -> bool {
-        let mut mask = (other.bits | (other.bits >> 1)) & Self::ONE_DECK.bits;
-        mask |= mask << 1;
-        (self.bits & mask) != 0
+        self.suits
+            .iter()
+            .zip(other.suits)
+            .any(|(lane, other)| presence(*lane) & presence(other) != 0)
     }
 
     pub fn contains_all(self, other: Cards) -> bool {
-        let diff = Cards {
-            bits: self.bits.wrapping_sub(other.bits),
-        };
-        self.len().wrapping_sub(other.len()) == diff.len()
+        self.suits
+            .iter()
+            .zip(other.suits)
+            .all(|(lane, other)| ((lane | GUARDS) - other) & GUARDS == GUARDS)
     }
 
     pub fn remove_all(&mut self, cards: Cards) -> Cards {
-        let mut mask = (cards.bits | (cards.bits >> 1)) & Self::ONE_DECK.bits;
-        mask |= mask << 1;
-        let removed = self.bits & mask;
-        self.bits -= removed;
-        Cards { bits: removed }
+        let mut removed = Cards::NONE;
+        for (lane, (bits, cards)) in self.suits.iter_mut().zip(cards.suits).enumerate() {
+            removed.suits[lane] = *bits & spread(presence(cards));
+            *bits -= removed.suits[lane];
+        }
+        removed
     }
 
     pub fn min_run(self) -> (Card, Card) {
@@ -156,15 +225,12 @@ impl Cards {
     }
 
     pub fn top_of_run(self, card: Card) -> Card {
-        let mut bits = self.bits;
-        bits |= bits >> 1;
-        bits &= Self::ONE_DECK.bits;
-        bits |= bits << 1;
-        bits += Cards::from(card).bits;
-        bits >>= 2;
-        bits ^= bits >> 1;
-        bits &= Self::ONE_DECK.bits;
-        Cards { bits }.min()
+        let (lane, shift) = lane_and_shift(card);
+        let held = spread(presence(self.suits[lane]));
+        // Adding one to the bottom of a run of full ranks carries into the first missing rank.
+        let changed = (held + (1 << shift)) ^ held;
+        let missing = (63 - changed.leading_zeros()) / 4;
+        Card::from((16 * lane as u32 + missing - 1) as u8)
     }
 
     pub fn cards(self) -> CardsIter {
@@ -207,7 +273,7 @@ impl Debug for Cards {
 }
 
 impl FromStr for Cards {
-    type Err = Infallible;
+    type Err = GoatError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut cards = Cards::NONE;
@@ -215,7 +281,9 @@ impl FromStr for Cards {
         let mut curr_suit = Suit::Clubs;
         while let Some(c) = chars.next_back() {
             if let Ok(rank) = Rank::try_from(c) {
-                cards += Card::new(rank, curr_suit);
+                cards = cards
+                    .checked_add(Card::new(rank, curr_suit))
+                    .ok_or_else(|| invalid_notation(s))?;
             } else if let Ok(suit) = Suit::try_from(c) {
                 curr_suit = suit;
             }
@@ -226,9 +294,10 @@ impl FromStr for Cards {
 
 impl From<Card> for Cards {
     fn from(card: Card) -> Self {
-        Cards {
-            bits: 1 << (2 * (card as u8)),
-        }
+        let (lane, shift) = lane_and_shift(card);
+        let mut cards = Cards::NONE;
+        cards.suits[lane] = 1 << shift;
+        cards
     }
 }
 
@@ -241,12 +310,12 @@ impl From<(Card, Card)> for Cards {
 impl Add<Cards> for Cards {
     type Output = Self;
 
-    fn add(self, rhs: Cards) -> Self::Output {
-        let sum = Cards {
-            bits: self.bits + rhs.bits,
-        };
-        debug_assert_eq!(sum.len(), self.len() + rhs.len());
-        sum
+    fn add(mut self, rhs: Cards) -> Self::Output {
+        for (lane, rhs) in self.suits.iter_mut().zip(rhs.suits) {
+            *lane += rhs;
+            debug_assert_eq!(*lane & GUARDS, 0, "more than {} copies", Cards::MAX_COPIES);
+        }
+        self
     }
 }
 
@@ -273,11 +342,12 @@ impl AddAssign<Card> for Cards {
 impl Sub<Cards> for Cards {
     type Output = Self;
 
-    fn sub(self, rhs: Cards) -> Self::Output {
+    fn sub(mut self, rhs: Cards) -> Self::Output {
         debug_assert!(self.contains_all(rhs));
-        Cards {
-            bits: self.bits - rhs.bits,
+        for (lane, rhs) in self.suits.iter_mut().zip(rhs.suits) {
+            *lane -= rhs;
         }
+        self
     }
 }
 
@@ -305,7 +375,7 @@ impl Mul<usize> for Cards {
     type Output = Cards;
 
     fn mul(self, rhs: usize) -> Self::Output {
-        debug_assert!(rhs <= 3);
+        debug_assert!(rhs <= Cards::MAX_COPIES);
         (0..rhs).fold(Cards::NONE, |c, _| c + self)
     }
 }
@@ -336,14 +406,15 @@ impl Iterator for CardsIter {
         Self: Sized,
         F: FnMut(B, Self::Item) -> B,
     {
-        let mut bits = self.0.bits;
-        while bits != 0 {
-            let tz = bits.trailing_zeros();
-            init = f(init, Card::from(tz as u8 / 2));
-            if tz & 1 == 1 {
-                init = f(init, Card::from(tz as u8 / 2));
+        for (lane, mut bits) in self.0.suits.into_iter().enumerate() {
+            while bits != 0 {
+                let shift = bits.trailing_zeros() & !3;
+                let card = Card::from((16 * lane as u32 + shift / 4) as u8);
+                for _ in 0..(bits >> shift) & 0xf {
+                    init = f(init, card);
+                }
+                bits &= !(0xf << shift);
             }
-            bits ^= 1 << tz;
         }
         init
     }
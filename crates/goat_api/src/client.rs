@@ -19,7 +19,11 @@ impl<Users: UserDb, PrevTrick: PreviousTrick, History: RummyHistory>
 
     pub fn apply(&mut self, response: Response) -> Result<(), GoatError> {
         match response {
-            Response::Ping => {}
+            Response::Hello { .. }
+            | Response::Ping
+            | Response::GoatMeter { .. }
+            | Response::DuplicateSet { .. }
+            | Response::Unknown => {}
             Response::Replay { game_id, events } => {
                 let mut game = ClientGame::default();
                 for event in events {
@@ -27,6 +31,9 @@ impl<Users: UserDb, PrevTrick: PreviousTrick, History: RummyHistory>
                 }
                 self.games.insert(game_id, game);
             }
+            Response::Snapshot { game_id, game } => {
+                self.games.insert(game_id, ClientGame::from_snapshot(*game));
+            }
             Response::Game { game_id, event } => match self.games.get_mut(&game_id) {
                 Some(game) => game.apply(event)?,
                 None => return Err(GoatError::InvalidGame { game_id }),
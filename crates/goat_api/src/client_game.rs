@@ -1,17 +1,29 @@
 use std::fmt::Debug;
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
 use crate::{
     Cards, ClientDeck, ClientRummyHand, ClientWarHand, Event, GoatError, GoatPhase, PlayerIdx,
-    PreviousTrick, Rank, RummyHistory, RummyPhase, UserId, WarHand, WarPhase, WarTrick,
+    PreviousTrick, Rank, RummyHistory, RummyPhase, UserId, WarHand, WarPhase, WarTrick, MAX_DECKS,
+    MAX_PLAYERS, MIN_PLAYERS,
 };
 
-#[derive(Clone, Debug)]
+/// A game as one player sees it, as sent in [`crate::Response::Snapshot`]. Besides the current
+/// state it only keeps the previous war trick and the cards played in the rummy phase. Other
+/// players' hands are only counted.
+pub type Snapshot = ClientGame<Option<WarTrick>, Cards>;
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
 pub struct ClientGame<PrevTrick, History> {
     pub phase: ClientPhase<PrevTrick, History>,
     pub players: Vec<UserId>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
 pub enum ClientPhase<PrevTrick, History> {
     Unstarted,
     War(WarPhase<ClientDeck, ClientWarHand, PrevTrick>),
@@ -29,16 +41,62 @@ impl<PrevTrick, History> Default for ClientGame<PrevTrick, History> {
 }
 
 impl<PrevTrick: PreviousTrick, History: RummyHistory> ClientGame<PrevTrick, History> {
+    /// Carries on from `snapshot` instead of replaying the game from the start.
+    pub fn from_snapshot(snapshot: Snapshot) -> Self {
+        let phase = match snapshot.phase {
+            ClientPhase::Unstarted => ClientPhase::Unstarted,
+            ClientPhase::War(war) => {
+                let mut prev_trick = PrevTrick::empty();
+                if let Some(trick) = war.prev_trick {
+                    prev_trick.set(trick);
+                }
+                ClientPhase::War(WarPhase {
+                    deck: war.deck,
+                    hands: war.hands,
+                    won: war.won,
+                    trick: war.trick,
+                    prev_trick,
+                })
+            }
+            ClientPhase::Rummy(rummy) => ClientPhase::Rummy(RummyPhase {
+                history: History::from_played(rummy.hands.len(), rummy.history),
+                hands: rummy.hands,
+                trick: rummy.trick,
+                next: rummy.next,
+                trump: rummy.trump,
+                pick_ups: rummy.pick_ups,
+            }),
+            ClientPhase::Goat(goat) => ClientPhase::Goat(goat),
+        };
+        Self {
+            phase,
+            players: snapshot.players,
+        }
+    }
+
     pub fn apply(&mut self, event: Event) -> Result<(), GoatError> {
         match event {
             Event::Join { user_id } => {
                 self.players.push(user_id);
             }
             Event::Leave { player } => {
+                if player.idx() >= self.players.len() {
+                    return Err(GoatError::NoSuchPlayer { player });
+                }
                 self.players.swap_remove(player.idx());
             }
+            Event::Invite { .. }
+            | Event::Commit { .. }
+            | Event::AddEntropy { .. }
+            | Event::RevealSeed { .. } => {}
             Event::Start { num_decks } => {
                 let num_players = self.players.len();
+                if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&num_players) {
+                    return Err(GoatError::InvalidNumberOfPlayers);
+                }
+                if !(1..=MAX_DECKS).contains(&(num_decks as usize)) {
+                    return Err(GoatError::InvalidNumberOfDecks);
+                }
                 self.phase = ClientPhase::War(WarPhase {
                     deck: ClientDeck::new(num_decks),
                     hands: vec![ClientWarHand::new(); num_players].into_boxed_slice(),
@@ -50,7 +108,7 @@ impl<PrevTrick: PreviousTrick, History: RummyHistory> ClientGame<PrevTrick, Hist
             Event::PlayCard { card } => {
                 let war = self.war()?;
                 let player = war.trick.next_player().unwrap();
-                war.play_from_hand(player, card);
+                war.play_from_hand(player, card)?;
             }
             Event::PlayTop { card } => {
                 let war = self.war()?;
@@ -59,7 +117,7 @@ impl<PrevTrick: PreviousTrick, History: RummyHistory> ClientGame<PrevTrick, Hist
             }
             Event::Slough { player, card } => {
                 let war = self.war()?;
-                war.slough(player, card);
+                war.slough(player, card)?;
             }
             Event::Draw { player, card } => {
                 let war = self.war()?;
@@ -118,18 +176,21 @@ impl<PrevTrick: PreviousTrick, History: RummyHistory> ClientGame<PrevTrick, Hist
                     let cards = hand
                         .known
                         .remove_all(Cards::COMMON_DRECK + rummy.trump.with_rank(Rank::Six));
-                    hand.unknown -= dreck - cards.len() as u8;
+                    hand.unknown -= dreck as u16 - cards.len() as u16;
                 }
             }
             Event::RedactedReceiveDreck { player, dreck } => {
                 let rummy = self.rummy()?;
                 let hand = &mut rummy.hands[player.idx()];
-                hand.unknown += dreck;
+                hand.unknown += dreck as u16;
                 if rummy.finished_receiving_dreck() {
                     rummy.reset_trick();
                     rummy.advance_leader();
                 }
             }
+            // Events from newer versions of the protocol don't change anything this version
+            // knows about.
+            Event::Unknown => {}
         }
         Ok(())
     }
@@ -1,6 +1,10 @@
 use std::fmt;
 use std::fmt::Debug;
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
 use crate::Card;
 
 pub trait Deck: Debug {
@@ -13,12 +17,20 @@ impl Deck for Vec<Card> {
     }
 }
 
-#[derive(Clone)]
-pub struct ClientDeck(u8);
+/// The number of cards left in the deck that can still be drawn or played, which is all clients
+/// know about it.
+#[derive(Clone, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(transparent)]
+pub struct ClientDeck(u16);
 
 impl ClientDeck {
     pub fn new(num_decks: u8) -> Self {
-        Self(52 * num_decks - 1)
+        Self(52 * num_decks as u16 - 1)
+    }
+
+    /// A deck with `remaining` cards left to draw or play, besides the trump.
+    pub fn with_remaining(remaining: usize) -> Self {
+        Self(remaining as u16)
     }
 
     pub fn draw(&mut self) {
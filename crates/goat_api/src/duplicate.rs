@@ -0,0 +1,118 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::{ClientPhase, Event, GoatError, PlayerIdx, RummyHand, ServerGame, UserId};
+
+type ClientGame = crate::ClientGame<(), ()>;
+
+/// The order to join `players` to table `table` of a duplicate set in for each table to seat
+/// them one seat further round than the table before, so that over as many tables as there are
+/// players everybody holds every seat once. The server leaves seating to whoever fills the tables.
+pub fn seating<T: Copy>(players: &[T], table: usize) -> Vec<T> {
+    let mut seats = players.to_vec();
+    if !seats.is_empty() {
+        seats.rotate_left(table % players.len());
+    }
+    seats
+}
+
+/// Where each seat of a finished game placed: 0 for the first player to run out of cards, and
+/// one more for every player who ran out before them. Players who ran out at the same time tie,
+/// and the goat always places last.
+pub fn finishing_places(events: &[Event]) -> Result<Vec<usize>, GoatError> {
+    let mut game = ClientGame::default();
+    let mut places: Vec<Option<usize>> = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        game.apply(event.clone())?;
+        // Hands that are empty before the dreck has been given out may still receive some.
+        let giving_dreck = matches!(
+            events.get(index + 1),
+            Some(
+                Event::OfferDreck { .. }
+                    | Event::ReceiveDreck { .. }
+                    | Event::RedactedOfferDreck { .. }
+                    | Event::RedactedReceiveDreck { .. }
+            )
+        );
+        match &game.phase {
+            ClientPhase::Rummy(rummy) if !giving_dreck => {
+                places.resize(rummy.hands.len(), None);
+                let place = places.iter().flatten().count();
+                for (hand, finished) in rummy.hands.iter().zip(&mut places) {
+                    if hand.is_empty() && finished.is_none() {
+                        *finished = Some(place);
+                    }
+                }
+            }
+            ClientPhase::Goat(goat) => {
+                places.resize(game.players.len(), None);
+                let place = places.iter().flatten().count();
+                places[goat.goat.idx()] = Some(game.players.len() - 1);
+                return Ok(places.into_iter().map(|p| p.unwrap_or(place)).collect());
+            }
+            _ => {}
+        }
+    }
+    Err(GoatError::GameNotFinished)
+}
+
+/// How the player who held one seat at one table of a duplicate set did against the players who
+/// held the same seat at the other tables.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SeatScore {
+    pub table: usize,
+    pub seat: PlayerIdx,
+    pub user_id: UserId,
+    pub place: usize,
+    /// One for every table where the seat placed worse, and a half for every table where it
+    /// placed the same.
+    pub matchpoints: f64,
+}
+
+/// Scores the finished tables of a duplicate set seat by seat. The tables must all have been
+/// dealt from the same seed with the same number of players and decks.
+pub fn score_duplicate(tables: &[&ServerGame]) -> Result<Vec<SeatScore>, GoatError> {
+    let deal = |game: &ServerGame| {
+        let num_decks = game.events.iter().find_map(|event| match event {
+            Event::Start { num_decks } => Some(*num_decks),
+            _ => None,
+        });
+        (game.deal_seed(), game.players.len(), num_decks)
+    };
+    if let Some(first) = tables.first() {
+        if tables.iter().any(|game| deal(game) != deal(first)) {
+            return Err(GoatError::InvalidState {
+                what: "duplicate set".into(),
+            });
+        }
+    }
+    let places = tables
+        .iter()
+        .map(|game| finishing_places(&game.events))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut scores = Vec::new();
+    for (table, (game, table_places)) in tables.iter().zip(&places).enumerate() {
+        for (seat, (&user_id, &place)) in game.players.iter().zip(table_places).enumerate() {
+            let matchpoints = places
+                .iter()
+                .map(|other| match other[seat] {
+                    p if p > place => 1.0,
+                    p if p == place => 0.5,
+                    _ => 0.0,
+                })
+                .sum::<f64>()
+                // Don't count the seat's own table.
+                - 0.5;
+            scores.push(SeatScore {
+                table,
+                seat: PlayerIdx(seat as u8),
+                user_id,
+                place,
+                matchpoints,
+            });
+        }
+    }
+    Ok(scores)
+}
@@ -1,8 +1,17 @@
+use std::borrow::Cow;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use ts_rs::TS;
 
-use crate::{Card, GameId, PlayerIdx, Rank, UserId};
+use crate::{Card, GameId, PlayerIdx, Rank, UserId, MAX_DECKS, MAX_PLAYERS, MIN_PLAYERS};
 
-#[derive(Debug, Error)]
+/// An action or message that breaks the rules. Errors are serialized with a stable `code` naming
+/// the variant, alongside the variant's fields.
+#[derive(Clone, Debug, Deserialize, Eq, Error, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(tag = "code")]
+#[serde(rename_all = "camelCase")]
 pub enum GoatError {
     #[error("Drawing from the deck is not possible when the deck is empty")]
     CannotDrawFromEmptyDeck,
@@ -23,20 +32,41 @@ pub enum GoatError {
     CannotPlayRange { lo: Card },
     #[error("Players cannot slough on a trick after they have finished sloughing")]
     CannotSloughOnEndedTrick,
+    #[error("The game has not finished yet")]
+    GameNotFinished,
     #[error("Card {card} cannot be sloughed")]
     IllegalSlough { card: Card },
     #[error("This action cannot be taken at this point in the game")]
     InvalidAction,
+    #[error("{card:?} is not a valid card")]
+    InvalidCard { card: String },
+    #[error("{hex:?} is not valid hex of the right length")]
+    InvalidHex { hex: String },
     #[error("{game_id} is not a valid game id")]
+    #[serde(rename_all = "camelCase")]
     InvalidGame { game_id: GameId },
-    #[error("Games require at least one deck and can be played with at most three decks")]
+    #[error("{id:?} is not a valid id")]
+    InvalidId { id: String },
+    #[error("{text:?} is not valid notation")]
+    InvalidNotation { text: String },
+    #[error(
+        "Games require at least one deck and can be played with at most {} decks",
+        MAX_DECKS
+    )]
     InvalidNumberOfDecks,
-    #[error("Games require at least 3 players and can be played with at most 16 players")]
+    #[error(
+        "Games require at least {} players and can be played with at most {} players",
+        MIN_PLAYERS,
+        MAX_PLAYERS
+    )]
     InvalidNumberOfPlayers,
     #[error("User {user_id} is not a real player in the game")]
+    #[serde(rename_all = "camelCase")]
     InvalidPlayer { user_id: UserId },
     #[error("The cards {lo} to {hi} do not form a valid range")]
     InvalidRange { lo: Card, hi: Card },
+    #[error("The {what} is not in a valid state")]
+    InvalidState { what: Cow<'static, str> },
     #[error(
         "Players must play a card with the same rank, {rank}, as the \
         highest card played so far in this round of the current trick"
@@ -48,4 +78,6 @@ pub enum GoatError {
     NotYourCard { card: Card },
     #[error("It is not player {player}'s turn")]
     NotYourTurn { player: PlayerIdx },
+    #[error("There is no player {player}")]
+    NoSuchPlayer { player: PlayerIdx },
 }
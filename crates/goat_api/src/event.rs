@@ -1,16 +1,25 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
-use crate::{Card, Cards, PlayerIdx, UserId};
+use crate::{Card, Cards, Commitment, Nonce, PlayerIdx, RandId, Seed, UserId};
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 pub enum Event {
+    /// The first event of every game, committing the server to the seed it will shuffle with.
+    #[serde(rename_all = "camelCase")]
+    Commit { commitment: Commitment },
     #[serde(rename_all = "camelCase")]
     Join { user_id: UserId },
     #[serde(rename_all = "camelCase")]
     Leave { player: PlayerIdx },
     #[serde(rename_all = "camelCase")]
+    Invite { user_id: UserId },
+    #[serde(rename_all = "camelCase")]
+    AddEntropy { entropy: RandId },
+    #[serde(rename_all = "camelCase")]
     Start { num_decks: u8 },
     #[serde(rename_all = "camelCase")]
     PlayCard { card: Card },
@@ -30,16 +39,25 @@ pub enum Event {
     ReceiveDreck { player: PlayerIdx, dreck: Cards },
     #[serde(rename_all = "camelCase")]
     PlayRun { lo: Card, hi: Card },
-    #[serde(rename_all = "camelCase")]
+    /// The next player picks up the top of the trick.
     PickUp,
     #[serde(rename_all = "camelCase")]
     Goat { noise: usize },
+    /// Reveals the seed and the nonce it was committed with once the game is over, so that the
+    /// deal can be verified.
+    #[serde(rename_all = "camelCase")]
+    RevealSeed { seed: Seed, nonce: Nonce },
     #[serde(rename_all = "camelCase")]
     RedactedDraw { player: PlayerIdx },
     #[serde(rename_all = "camelCase")]
     RedactedOfferDreck { player: PlayerIdx, dreck: u8 },
     #[serde(rename_all = "camelCase")]
     RedactedReceiveDreck { player: PlayerIdx, dreck: u8 },
+    /// An event from a newer version of the protocol.
+    #[serde(other)]
+    #[schemars(skip)]
+    #[ts(skip)]
+    Unknown,
 }
 
 impl Event {
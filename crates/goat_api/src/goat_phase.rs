@@ -1,6 +1,11 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
 use crate::PlayerIdx;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[ts(optional_fields = nullable)]
 pub struct GoatPhase {
     pub goat: PlayerIdx,
     pub noise: Option<usize>,
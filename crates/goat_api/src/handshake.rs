@@ -0,0 +1,120 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::{Event, Response};
+
+/// The version of the protocol spoken by this build. Bump it whenever a variant is added to
+/// [`Event`] or [`Response`], and say in [`Response::min_version`] or [`Event::min_version`]
+/// which version introduced it. Version 2 added the events that make shuffles verifiable,
+/// version 3 added the goat meter, and version 4 added duplicate sets.
+pub const PROTOCOL_VERSION: u32 = 4;
+
+/// An optional part of the protocol, used only when both sides understand it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+    /// Games in progress are sent as snapshots rather than replays when subscribing.
+    Snapshots,
+    /// A capability this build doesn't know about.
+    #[serde(other)]
+    #[schemars(skip)]
+    #[ts(skip)]
+    Unknown,
+}
+
+/// What one side of a subscription speaks.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Protocol {
+    pub version: u32,
+    pub capabilities: Vec<Capability>,
+}
+
+impl Protocol {
+    /// What this build speaks.
+    pub fn current() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            capabilities: vec![Capability::Snapshots],
+        }
+    }
+
+    /// What a client that subscribes without a handshake speaks.
+    pub fn legacy() -> Self {
+        Self {
+            version: 0,
+            capabilities: Vec::new(),
+        }
+    }
+
+    /// Agrees on the newest version and the capabilities both `self` and a client speaking
+    /// `client` understand. Every version is still spoken, back to version 0 for clients that
+    /// subscribe without declaring one.
+    pub fn negotiate(&self, client: &Protocol) -> Protocol {
+        Protocol {
+            version: self.version.min(client.version),
+            capabilities: self
+                .capabilities
+                .iter()
+                .copied()
+                .filter(|capability| client.supports(*capability))
+                .collect(),
+        }
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        capability != Capability::Unknown && self.capabilities.contains(&capability)
+    }
+
+    /// Whether a client speaking this protocol can make sense of `response`.
+    pub fn understands(&self, response: &Response) -> bool {
+        response.min_version() <= self.version
+            && match response {
+                Response::Snapshot { .. } => self.supports(Capability::Snapshots),
+                _ => true,
+            }
+    }
+
+    /// `response` in a form a client speaking this protocol can make sense of. Replays leave out
+    /// the events the client doesn't know about, which must be ones that don't change the state
+    /// of the game, and other responses it doesn't understand aren't sent at all.
+    pub fn downgrade(&self, response: Response) -> Option<Response> {
+        match response {
+            Response::Replay {
+                game_id,
+                mut events,
+            } => {
+                events.retain(|event| event.min_version() <= self.version);
+                Some(Response::Replay { game_id, events })
+            }
+            response if self.understands(&response) => Some(response),
+            _ => None,
+        }
+    }
+}
+
+impl Response {
+    /// The first version of the protocol with this response.
+    pub fn min_version(&self) -> u32 {
+        match self {
+            Response::Hello { .. } => 1,
+            Response::GoatMeter { .. } => 3,
+            Response::DuplicateSet { .. } => 4,
+            Response::Game { event, .. } => event.min_version(),
+            Response::Replay { events, .. } => {
+                events.iter().map(Event::min_version).max().unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+}
+
+impl Event {
+    /// The first version of the protocol with this event.
+    pub fn min_version(&self) -> u32 {
+        match self {
+            Event::Commit { .. } | Event::AddEntropy { .. } | Event::RevealSeed { .. } => 2,
+            _ => 0,
+        }
+    }
+}
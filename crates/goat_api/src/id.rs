@@ -1,13 +1,16 @@
 use std::fmt;
 use std::fmt::{Debug, Display};
 
-use crate::RandId;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::RandId;
 
 macro_rules! declare_id {
     ($name:ident, $inner:ty) => {
         #[repr(transparent)]
-        #[derive(Clone, Copy, Deserialize, Eq, Hash, PartialEq, Serialize)]
+        #[derive(Clone, Copy, Deserialize, Eq, Hash, JsonSchema, PartialEq, Serialize, TS)]
         pub struct $name(pub $inner);
 
         impl From<$inner> for $name {
@@ -0,0 +1,416 @@
+use std::fmt;
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::{
+    verify_shuffle, Cards, ClientPhase, ClientRummyHand, ClientWarHand, Deck, Event, GoatError,
+    PlayerIdx, RummyHand, ServerGame, ServerPhase, ShuffleError, WarHand, WarTrick,
+};
+
+type ClientGame = crate::ClientGame<(), Cards>;
+
+/// A rule of the game that a [`ServerGame`] or the views built from its events has broken.
+#[derive(Debug, Error)]
+pub enum InvariantError {
+    #[error("Expected {expected} to be in play, found {found}")]
+    CardsNotConserved { expected: Cards, found: Cards },
+    #[error("Player {player} holds {len} cards during the war phase")]
+    WarHandTooLarge { player: PlayerIdx, len: usize },
+    #[error("Player {player} is next but has no cards")]
+    NextPlayerHasNoCards { player: PlayerIdx },
+    #[error("Replaying {event:?} for {view} failed: {error}")]
+    ReplayFailed {
+        view: View,
+        event: Event,
+        error: GoatError,
+    },
+    #[error("The view of {view} disagrees with the server about {what}")]
+    ViewMismatch { view: View, what: &'static str },
+    #[error("{event:?} reveals a card that {view} should not see")]
+    HiddenCardLeaked { view: View, event: Event },
+    #[error("The view of {view} disagrees with the unredacted view about {what}")]
+    ProjectionMismatch { view: View, what: &'static str },
+    #[error("After event {idx}, {view} can't tell whether the dreck has all been handed out")]
+    DreckMiscounted { view: View, idx: usize },
+    #[error("The deal doesn't match the committed seed: {error}")]
+    ShuffleMismatch { error: ShuffleError },
+}
+
+/// Whose view of the game an [`InvariantError`] is about.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum View {
+    Everything,
+    Player(PlayerIdx),
+}
+
+impl Display for View {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            View::Everything => f.write_str("everything"),
+            View::Player(player) => write!(f, "player {}", player),
+        }
+    }
+}
+
+/// Checks the rules that must hold after every action applied to a [`ServerGame`]:
+///
+/// - every card dealt is in exactly one of the deck, a hand, a won pile, the war trick, the trump
+///   or the cards played in the rummy phase, which are counted from the events and the server's
+///   tricks rather than from a view,
+/// - every view can tell that the dreck has all been handed out exactly after its last event,
+/// - war hands never hold more than three cards and the next rummy player has cards,
+/// - the game rebuilt from the events, both unredacted and redacted for every seat, agrees with
+///   the server about everything that view is allowed to see,
+/// - after every single event, each seat's view is a faithful projection of the unredacted view,
+///   and no event redacted for a seat carries a card that seat should not see,
+/// - once the game is over, the deal verifies against the seed the game committed to.
+///
+/// Events are replayed incrementally, so the same checker should be used for every check of a
+/// game, and a game should be checked after every action.
+pub struct InvariantChecker {
+    everything: ClientGame,
+    seats: Vec<ClientGame>,
+    num_decks: usize,
+    applied: usize,
+    /// The cards played in the rummy phase and not picked up again.
+    played: Cards,
+    /// The cards in the server's rummy trick when it was last checked.
+    trick: Cards,
+}
+
+impl Default for InvariantChecker {
+    fn default() -> Self {
+        Self {
+            everything: ClientGame::default(),
+            seats: Vec::new(),
+            num_decks: 0,
+            applied: 0,
+            played: Cards::NONE,
+            trick: Cards::NONE,
+        }
+    }
+}
+
+impl InvariantChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn check(&mut self, game: &ServerGame) -> Result<(), InvariantError> {
+        let start = self.applied.min(game.events.len());
+        for (idx, event) in game.events.iter().enumerate().skip(start) {
+            self.replay(event)?;
+            if matches!(event, Event::ReceiveDreck { .. }) {
+                let last = !game.events[idx + 1..].iter().any(|event| {
+                    matches!(event, Event::OfferDreck { .. } | Event::ReceiveDreck { .. })
+                });
+                self.check_dreck(idx, last)?;
+            }
+        }
+        self.count_played(game, start)?;
+        self.applied = game.events.len();
+        self.check_cards(game)?;
+        check_view(game, &self.everything, View::Everything)?;
+        for (seat, view) in self.seats.iter().enumerate() {
+            check_view(game, view, View::Player(PlayerIdx(seat as u8)))?;
+        }
+        if let ServerPhase::Goat(_) = game.phase {
+            verify_shuffle(&game.events)
+                .map_err(|error| InvariantError::ShuffleMismatch { error })?;
+        }
+        Ok(())
+    }
+
+    fn replay(&mut self, event: &Event) -> Result<(), InvariantError> {
+        let failed = |view, error| InvariantError::ReplayFailed {
+            view,
+            event: event.clone(),
+            error,
+        };
+        if let Event::Start { num_decks } = event {
+            // Nothing is hidden before the game starts, so every seat starts from the same view.
+            self.num_decks = *num_decks as usize;
+            self.seats = vec![self.everything.clone(); self.everything.players.len()];
+        }
+        self.everything
+            .apply(event.redact(None))
+            .map_err(|e| failed(View::Everything, e))?;
+        for (seat, view) in self.seats.iter_mut().enumerate() {
+            let seat = PlayerIdx(seat as u8);
+            let redacted = event.redact(Some(seat));
+            if reveals_hidden_card(&redacted, seat) {
+                return Err(InvariantError::HiddenCardLeaked {
+                    view: View::Player(seat),
+                    event: redacted,
+                });
+            }
+            view.apply(redacted)
+                .map_err(|e| failed(View::Player(seat), e))?;
+            check_projection(&self.everything, view, seat)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every view agrees that the dreck has all been handed out exactly when the
+    /// event at `idx` was the `last` one to hand it out.
+    fn check_dreck(&self, idx: usize, last: bool) -> Result<(), InvariantError> {
+        let views = std::iter::once((View::Everything, &self.everything)).chain(
+            self.seats
+                .iter()
+                .enumerate()
+                .map(|(seat, view)| (View::Player(PlayerIdx(seat as u8)), view)),
+        );
+        for (view, game) in views {
+            let finished = match &game.phase {
+                ClientPhase::Rummy(rummy) => rummy.finished_receiving_dreck(),
+                _ => false,
+            };
+            if finished != last {
+                return Err(InvariantError::DreckMiscounted { view, idx });
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts the cards played in the rummy phase from the events since `start`, taking whatever
+    /// left the server's trick on a pick up out again.
+    fn count_played(&mut self, game: &ServerGame, start: usize) -> Result<(), InvariantError> {
+        let ServerPhase::Rummy(rummy) = &game.phase else {
+            self.trick = Cards::NONE;
+            return Ok(());
+        };
+        let trick: Cards = rummy
+            .trick
+            .plays()
+            .iter()
+            .map(|(lo, hi)| Cards::range(*lo, *hi))
+            .collect();
+        for event in &game.events[start..] {
+            match *event {
+                Event::PlayRun { lo, hi } => self.played += Cards::range(lo, hi),
+                Event::PickUp => {
+                    // Picking up only ever takes cards out of the trick.
+                    if !self.trick.contains_all(trick) {
+                        return Err(InvariantError::CardsNotConserved {
+                            expected: self.trick,
+                            found: trick,
+                        });
+                    }
+                    let picked_up = self.trick - trick;
+                    if !self.played.contains_all(picked_up) {
+                        return Err(InvariantError::CardsNotConserved {
+                            expected: picked_up,
+                            found: self.played,
+                        });
+                    }
+                    self.played -= picked_up;
+                }
+                _ => {}
+            }
+        }
+        self.trick = trick;
+        Ok(())
+    }
+
+    fn check_cards(&self, game: &ServerGame) -> Result<(), InvariantError> {
+        let found = match &game.phase {
+            ServerPhase::War(war) => {
+                for (player, hand) in war.hands.iter().enumerate() {
+                    if hand.len() > 3 {
+                        return Err(InvariantError::WarHandTooLarge {
+                            player: PlayerIdx(player as u8),
+                            len: hand.len(),
+                        });
+                    }
+                }
+                let hands = war.hands.iter().flat_map(|hand| hand.cards());
+                let mut found: Cards = war.deck.iter().copied().chain(hands).collect();
+                found += war.won.iter().copied().collect::<Cards>();
+                found + war.trick.cards().collect::<Cards>()
+            }
+            ServerPhase::Rummy(rummy) => {
+                if rummy.hands[rummy.next.idx()].is_empty() {
+                    return Err(InvariantError::NextPlayerHasNoCards { player: rummy.next });
+                }
+                // The cards played include those still in the trick.
+                rummy.hands.iter().copied().collect::<Cards>() + rummy.trump + self.played
+            }
+            ServerPhase::Unstarted | ServerPhase::Goat(_) => return Ok(()),
+        };
+        let expected = Cards::ONE_DECK * self.num_decks;
+        if found != expected {
+            return Err(InvariantError::CardsNotConserved { expected, found });
+        }
+        Ok(())
+    }
+}
+
+fn check_view(game: &ServerGame, view: &ClientGame, of: View) -> Result<(), InvariantError> {
+    let mismatch = |what| Err(InvariantError::ViewMismatch { view: of, what });
+    let sees = |player: usize| match of {
+        View::Everything => true,
+        View::Player(seat) => seat.idx() == player,
+    };
+    if view.players != game.players {
+        return mismatch("players");
+    }
+    match (&game.phase, &view.phase) {
+        (ServerPhase::Unstarted, ClientPhase::Unstarted) => {}
+        (ServerPhase::War(war), ClientPhase::War(client)) => {
+            if war.deck.cards_remaining() != client.deck.cards_remaining() {
+                return mismatch("cards remaining in the deck");
+            }
+            if war.won != client.won {
+                return mismatch("cards won");
+            }
+            if !same_trick(&war.trick, &client.trick) {
+                return mismatch("the war trick");
+            }
+            for (player, (hand, client)) in war.hands.iter().zip(client.hands.iter()).enumerate() {
+                let same = match client {
+                    ClientWarHand::Visible(client) if sees(player) => {
+                        hand.cards().collect::<Cards>() == client.cards().collect::<Cards>()
+                    }
+                    ClientWarHand::Hidden(len) if !sees(player) => hand.len() == *len as usize,
+                    // Hands are only hidden once something has been drawn into them.
+                    ClientWarHand::Visible(client) => hand.is_empty() && client.is_empty(),
+                    ClientWarHand::Hidden(_) => false,
+                };
+                if !same {
+                    return mismatch("a war hand");
+                }
+            }
+        }
+        (ServerPhase::Rummy(rummy), ClientPhase::Rummy(client)) => {
+            if rummy.next != client.next
+                || rummy.trump != client.trump
+                || rummy.pick_ups != client.pick_ups
+            {
+                return mismatch("whose turn it is");
+            }
+            if rummy.trick.plays() != client.trick.plays()
+                || rummy.trick.num_players() != client.trick.num_players()
+            {
+                return mismatch("the rummy trick");
+            }
+            for (player, (hand, client)) in rummy.hands.iter().zip(client.hands.iter()).enumerate()
+            {
+                let ClientRummyHand { known, unknown } = *client;
+                let same = if sees(player) {
+                    *hand == known && unknown == 0
+                } else {
+                    hand.contains_all(known) && hand.len() == known.len() + unknown as usize
+                };
+                if !same {
+                    return mismatch("a rummy hand");
+                }
+            }
+        }
+        (ServerPhase::Goat(goat), ClientPhase::Goat(client)) => {
+            if goat.goat != client.goat || goat.noise != client.noise {
+                return mismatch("the goat");
+            }
+        }
+        _ => return mismatch("the phase"),
+    }
+    Ok(())
+}
+
+fn same_trick(a: &WarTrick, b: &WarTrick) -> bool {
+    a.next_player() == b.next_player()
+        && a.rank() == b.rank()
+        && a.winner() == b.winner()
+        && a.end_mask() == b.end_mask()
+        && a.plays().len() == b.plays().len()
+        && a.plays()
+            .iter()
+            .zip(b.plays())
+            .all(|(a, b)| a.player() == b.player() && a.kind() == b.kind() && a.card == b.card)
+}
+
+fn reveals_hidden_card(event: &Event, seat: PlayerIdx) -> bool {
+    match event {
+        Event::Draw { player, .. }
+        | Event::OfferDreck { player, .. }
+        | Event::ReceiveDreck { player, .. } => *player != seat,
+        _ => false,
+    }
+}
+
+/// Checks that the view redacted for `seat` matches the unredacted view `truth`, except for the
+/// cards in other players' hands that `seat` hasn't seen.
+fn check_projection(
+    truth: &ClientGame,
+    view: &ClientGame,
+    seat: PlayerIdx,
+) -> Result<(), InvariantError> {
+    let mismatch = |what| {
+        Err(InvariantError::ProjectionMismatch {
+            view: View::Player(seat),
+            what,
+        })
+    };
+    if view.players != truth.players {
+        return mismatch("players");
+    }
+    match (&truth.phase, &view.phase) {
+        (ClientPhase::Unstarted, ClientPhase::Unstarted) => {}
+        (ClientPhase::War(truth), ClientPhase::War(view)) => {
+            if truth.deck.cards_remaining() != view.deck.cards_remaining() {
+                return mismatch("cards remaining in the deck");
+            }
+            if truth.won != view.won {
+                return mismatch("cards won");
+            }
+            if !same_trick(&truth.trick, &view.trick) {
+                return mismatch("the war trick");
+            }
+            for (player, (truth, view)) in truth.hands.iter().zip(view.hands.iter()).enumerate() {
+                let same = match (truth, view) {
+                    (ClientWarHand::Visible(truth), ClientWarHand::Visible(view))
+                        if player == seat.idx() =>
+                    {
+                        truth.cards().collect::<Cards>() == view.cards().collect::<Cards>()
+                    }
+                    (_, ClientWarHand::Visible(view)) => player != seat.idx() && view.is_empty(),
+                    (_, ClientWarHand::Hidden(_)) => player != seat.idx(),
+                };
+                if !same || truth.len() != view.len() {
+                    return mismatch("a war hand");
+                }
+            }
+        }
+        (ClientPhase::Rummy(truth), ClientPhase::Rummy(view)) => {
+            if truth.next != view.next
+                || truth.trump != view.trump
+                || truth.pick_ups != view.pick_ups
+                || truth.history != view.history
+            {
+                return mismatch("whose turn it is");
+            }
+            if truth.trick.plays() != view.trick.plays()
+                || truth.trick.num_players() != view.trick.num_players()
+            {
+                return mismatch("the rummy trick");
+            }
+            for (player, (truth, view)) in truth.hands.iter().zip(view.hands.iter()).enumerate() {
+                let same = if player == seat.idx() {
+                    truth.known == view.known && view.unknown == 0
+                } else {
+                    truth.known.contains_all(view.known)
+                };
+                if !same || truth.len() != view.len() {
+                    return mismatch("a rummy hand");
+                }
+            }
+        }
+        (ClientPhase::Goat(truth), ClientPhase::Goat(view)) => {
+            if truth.goat != view.goat || truth.noise != view.noise {
+                return mismatch("the goat");
+            }
+        }
+        _ => return mismatch("the phase"),
+    }
+    Ok(())
+}
@@ -4,11 +4,17 @@ pub use cards::*;
 pub use client::*;
 pub use client_game::*;
 pub use deck::*;
+pub use duplicate::*;
 pub use error::*;
 pub use event::*;
 pub use goat_phase::*;
+pub use handshake::*;
 pub use id::*;
+pub use invariants::*;
+pub use notation::*;
+pub use position::*;
 pub use prev_trick::*;
+pub use protocol::*;
 pub use rand_id::*;
 pub use rank::*;
 pub use response::*;
@@ -17,6 +23,7 @@ pub use rummy_history::*;
 pub use rummy_phase::*;
 pub use rummy_trick::*;
 pub use server_game::*;
+pub use shuffle::*;
 pub use suit::*;
 pub use user::*;
 pub use user_db::*;
@@ -31,11 +38,17 @@ mod cards;
 mod client;
 mod client_game;
 mod deck;
+mod duplicate;
 mod error;
 mod event;
 mod goat_phase;
+mod handshake;
 mod id;
+mod invariants;
+mod notation;
+mod position;
 mod prev_trick;
+mod protocol;
 mod rand_id;
 mod rank;
 mod response;
@@ -44,6 +57,7 @@ mod rummy_history;
 mod rummy_phase;
 mod rummy_trick;
 mod server_game;
+mod shuffle;
 mod suit;
 mod user;
 mod user_db;
@@ -0,0 +1,414 @@
+use std::fmt;
+use std::fmt::{Display, Write as _};
+use std::str::FromStr;
+
+use crate::position::{invalid_notation, parse_cards, parse_player};
+use crate::{ClientGame, ClientPhase, Event, GoatError, PlayerIdx, Run, UserId};
+
+/// The events of a whole game, written compactly enough to paste into a chat or a bug report.
+///
+/// A game starts with a header of tags: the `Commit`ment, the `Players` who joined first, the
+/// number of `Decks` and the `Seed` once it is revealed. The moves follow, starting a new line
+/// for each phase, after players finish a war trick and after a pick up in the rummy phase. Moves
+/// by a player start with the player, written as in a [`crate::Position`], and cards are written
+/// as in [`crate::Card`] and [`crate::Cards`]:
+///
+/// ```text
+/// [Commit "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"]
+/// [Players "............/ ..........0. ..........1."]
+/// [Decks "1"]
+/// [Seed "0123456789abcdef"]
+///
+/// P0 D 7H P1 D 2S P2 D QC P0 PC 7H P1 PT 9H P2 SL QC P0 FT P1 FT P2 FT
+/// TRUMP 9H P0 OFFER [2C] P2 GETS [2C] RUN 5D-8D PU
+/// RUN 2C
+/// NOISE 3
+/// ```
+///
+/// Events that fit in the header are only written as moves when they aren't where the header puts
+/// them, so that any list of events is written and read back unchanged.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GameRecord(pub Vec<Event>);
+
+/// Whether `event` sets up a game rather than playing it.
+fn is_setup(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Join { .. } | Event::Leave { .. } | Event::Invite { .. } | Event::AddEntropy { .. }
+    )
+}
+
+/// Where the header's `Start` goes: after the events that set up the game.
+fn start_index(events: &[Event]) -> usize {
+    events
+        .iter()
+        .position(|event| !is_setup(event))
+        .unwrap_or(events.len())
+}
+
+/// Where the header's `RevealSeed` goes: before the goat noises that end the game.
+fn seed_index(events: &[Event]) -> usize {
+    let noises = events
+        .iter()
+        .rev()
+        .take_while(|event| matches!(event, Event::Goat { .. }))
+        .count();
+    events.len() - noises
+}
+
+/// Removes the first event that `tag` matches and that the header would put back where it was,
+/// along with the player who made it.
+fn take_tag<T>(
+    events: &mut Vec<Event>,
+    players: &mut Vec<Option<PlayerIdx>>,
+    tag: impl Fn(&Event) -> Option<T>,
+    index: impl Fn(&[Event]) -> usize,
+) -> Option<T> {
+    for i in 0..events.len() {
+        if let Some(value) = tag(&events[i]) {
+            let event = events.remove(i);
+            if index(events) == i {
+                players.remove(i);
+                return Some(value);
+            }
+            events.insert(i, event);
+        }
+    }
+    None
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The players who play cards in the war phase are only known from the state of the game.
+        let mut game = ClientGame::<(), ()>::default();
+        let mut players = Vec::with_capacity(self.0.len());
+        for event in &self.0 {
+            players.push(match &game.phase {
+                ClientPhase::War(war) => war.trick.next_player(),
+                _ => None,
+            });
+            if game.apply(event.clone()).is_err() {
+                players.resize(self.0.len(), None);
+                break;
+            }
+        }
+
+        let mut events = self.0.clone();
+        let commit = match events.first() {
+            Some(Event::Commit { commitment }) => Some(*commitment),
+            _ => None,
+        };
+        if commit.is_some() {
+            events.remove(0);
+            players.remove(0);
+        }
+        let seed = take_tag(
+            &mut events,
+            &mut players,
+            |event| match event {
+                Event::RevealSeed { seed, nonce } => Some((*seed, *nonce)),
+                _ => None,
+            },
+            seed_index,
+        );
+        let decks = take_tag(
+            &mut events,
+            &mut players,
+            |event| match event {
+                Event::Start { num_decks } => Some(*num_decks),
+                _ => None,
+            },
+            start_index,
+        );
+        let joined: Vec<_> = events
+            .iter()
+            .map_while(|event| match event {
+                Event::Join { user_id } => Some(user_id.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        if let Some(commitment) = commit {
+            writeln!(f, "[Commit \"{}\"]", commitment)?;
+        }
+        if !joined.is_empty() {
+            writeln!(f, "[Players \"{}\"]", joined.join(" "))?;
+        }
+        if let Some(num_decks) = decks {
+            writeln!(f, "[Decks \"{}\"]", num_decks)?;
+        }
+        if let Some((seed, nonce)) = seed {
+            writeln!(f, "[Seed \"{}\"]", seed)?;
+            writeln!(f, "[Nonce \"{}\"]", nonce)?;
+        }
+        f.write_char('\n')?;
+
+        let mut line = String::new();
+        let moves = events.iter().zip(players).skip(joined.len());
+        let mut prev = None;
+        for (event, player) in moves {
+            if prev.is_some_and(|prev| breaks_line(prev, event)) {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            } else if prev.is_some() {
+                line.push(' ');
+            }
+            write_move(&mut line, event, player)?;
+            prev = Some(event);
+        }
+        if !line.is_empty() {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a new line starts between `prev` and `next`.
+fn breaks_line(prev: &Event, next: &Event) -> bool {
+    let phase = |event: &Event| match event {
+        Event::Join { .. }
+        | Event::Leave { .. }
+        | Event::Invite { .. }
+        | Event::AddEntropy { .. }
+        | Event::Commit { .. }
+        | Event::Start { .. } => 0,
+        Event::PlayCard { .. }
+        | Event::PlayTop { .. }
+        | Event::Slough { .. }
+        | Event::Draw { .. }
+        | Event::RedactedDraw { .. }
+        | Event::FinishTrick { .. } => 1,
+        Event::RevealTrump { .. }
+        | Event::OfferDreck { .. }
+        | Event::RedactedOfferDreck { .. }
+        | Event::ReceiveDreck { .. }
+        | Event::RedactedReceiveDreck { .. }
+        | Event::PlayRun { .. }
+        | Event::PickUp => 2,
+        Event::Goat { .. } | Event::RevealSeed { .. } | Event::Unknown => 3,
+    };
+    phase(prev) != phase(next)
+        || matches!(prev, Event::PickUp)
+        || (matches!(prev, Event::FinishTrick { .. }) && !matches!(next, Event::FinishTrick { .. }))
+}
+
+fn write_move(out: &mut String, event: &Event, player: Option<PlayerIdx>) -> fmt::Result {
+    match event {
+        Event::Commit { commitment } => write!(out, "COMMIT {}", commitment),
+        Event::Join { user_id } => write!(out, "JOIN {}", user_id),
+        Event::Leave { player } => write!(out, "P{} LEAVE", player),
+        Event::Invite { user_id } => write!(out, "INVITE {}", user_id),
+        Event::AddEntropy { entropy } => write!(out, "ENTROPY {}", entropy),
+        Event::Start { num_decks } => write!(out, "START {}", num_decks),
+        Event::PlayCard { card } | Event::PlayTop { card } => {
+            if let Some(player) = player {
+                write!(out, "P{} ", player)?;
+            }
+            let verb = if matches!(event, Event::PlayCard { .. }) {
+                "PC"
+            } else {
+                "PT"
+            };
+            write!(out, "{} {}", verb, card)
+        }
+        Event::Slough { player, card } => write!(out, "P{} SL {}", player, card),
+        Event::Draw { player, card } => write!(out, "P{} D {}", player, card),
+        Event::RedactedDraw { player } => write!(out, "P{} D ?", player),
+        Event::FinishTrick { player } => write!(out, "P{} FT", player),
+        Event::RevealTrump { trump } => write!(out, "TRUMP {}", trump),
+        Event::OfferDreck { player, dreck } => write!(out, "P{} OFFER {}", player, dreck),
+        Event::RedactedOfferDreck { player, dreck } => write!(out, "P{} OFFER {}", player, dreck),
+        Event::ReceiveDreck { player, dreck } => write!(out, "P{} GETS {}", player, dreck),
+        Event::RedactedReceiveDreck { player, dreck } => {
+            write!(out, "P{} GETS {}", player, dreck)
+        }
+        Event::PlayRun { lo, hi } => write!(out, "RUN {}", Run(*lo, *hi)),
+        Event::PickUp => out.write_str("PU"),
+        Event::Goat { noise } => write!(out, "NOISE {}", noise),
+        Event::RevealSeed { seed, nonce } => write!(out, "SEED {} {}", seed, nonce),
+        Event::Unknown => out.write_str("UNKNOWN"),
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = GoatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut commit = None;
+        let mut players = Vec::new();
+        let mut decks = None;
+        let mut seed = None;
+        let mut nonce = None;
+        let mut moves = Vec::new();
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(tag) = line.strip_prefix('[') {
+                let (name, value) = tag
+                    .strip_suffix("\"]")
+                    .and_then(|tag| tag.split_once(" \""))
+                    .ok_or_else(|| invalid_notation(line))?;
+                let duplicate = match name {
+                    "Commit" => commit.replace(value.parse()?).is_some(),
+                    "Players" => {
+                        let joined = value
+                            .split_whitespace()
+                            .map(|user_id| {
+                                Ok(Event::Join {
+                                    user_id: UserId(user_id.parse()?),
+                                })
+                            })
+                            .collect::<Result<Vec<_>, GoatError>>()?;
+                        !std::mem::replace(&mut players, joined).is_empty()
+                    }
+                    "Decks" => decks
+                        .replace(value.parse().map_err(|_| invalid_notation(line))?)
+                        .is_some(),
+                    "Seed" => seed.replace(value.parse()?).is_some(),
+                    "Nonce" => nonce.replace(value.parse()?).is_some(),
+                    _ => true,
+                };
+                if duplicate || !moves.is_empty() {
+                    return Err(invalid_notation(line));
+                }
+            } else {
+                read_moves(line, &mut moves)?;
+            }
+        }
+
+        let mut events = players;
+        events.append(&mut moves);
+        if let Some(num_decks) = decks {
+            events.insert(start_index(&events), Event::Start { num_decks });
+        }
+        match (seed, nonce) {
+            (Some(seed), Some(nonce)) => {
+                events.insert(seed_index(&events), Event::RevealSeed { seed, nonce });
+            }
+            (None, None) => {}
+            _ => return Err(invalid_notation(s)),
+        }
+        if let Some(commitment) = commit {
+            events.insert(0, Event::Commit { commitment });
+        }
+        Ok(GameRecord(events))
+    }
+}
+
+/// Splits a line of moves into tokens, keeping lists of cards like `[Q9S JD]` together.
+fn tokens(line: &str) -> Result<Vec<&str>, GoatError> {
+    let mut tokens = Vec::new();
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let end = if rest.starts_with('[') {
+            rest.find(']').ok_or_else(|| invalid_notation(rest))? + 1
+        } else {
+            rest.find(char::is_whitespace).unwrap_or(rest.len())
+        };
+        tokens.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    Ok(tokens)
+}
+
+fn read_moves(line: &str, moves: &mut Vec<Event>) -> Result<(), GoatError> {
+    let tokens = tokens(line)?;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let player = parse_player(token).ok();
+        let verb = match player {
+            Some(_) => tokens.next().ok_or_else(|| invalid_notation(line))?,
+            None => token,
+        };
+        let mut arg = || tokens.next().ok_or_else(|| invalid_notation(line));
+        let needs_player = || player.ok_or_else(|| invalid_notation(line));
+        let no_player = |event: Event| match player {
+            Some(_) => Err(invalid_notation(line)),
+            None => Ok(event),
+        };
+        let event = match verb {
+            "COMMIT" => no_player(Event::Commit {
+                commitment: arg()?.parse()?,
+            })?,
+            "JOIN" => no_player(Event::Join {
+                user_id: UserId(arg()?.parse()?),
+            })?,
+            "LEAVE" => Event::Leave {
+                player: needs_player()?,
+            },
+            "INVITE" => no_player(Event::Invite {
+                user_id: UserId(arg()?.parse()?),
+            })?,
+            "ENTROPY" => no_player(Event::AddEntropy {
+                entropy: arg()?.parse()?,
+            })?,
+            "START" => no_player(Event::Start {
+                num_decks: arg()?.parse().map_err(|_| invalid_notation(line))?,
+            })?,
+            // The player who plays a card is only written for readers, since it follows from
+            // the state of the game.
+            "PC" => Event::PlayCard {
+                card: arg()?.parse()?,
+            },
+            "PT" => Event::PlayTop {
+                card: arg()?.parse()?,
+            },
+            "SL" => Event::Slough {
+                player: needs_player()?,
+                card: arg()?.parse()?,
+            },
+            "D" => {
+                let player = needs_player()?;
+                match arg()? {
+                    "?" => Event::RedactedDraw { player },
+                    card => Event::Draw {
+                        player,
+                        card: card.parse()?,
+                    },
+                }
+            }
+            "FT" => Event::FinishTrick {
+                player: needs_player()?,
+            },
+            "TRUMP" => no_player(Event::RevealTrump {
+                trump: arg()?.parse()?,
+            })?,
+            "OFFER" | "GETS" => {
+                let player = needs_player()?;
+                let dreck = arg()?;
+                match (verb, dreck.starts_with('[')) {
+                    ("OFFER", true) => Event::OfferDreck {
+                        player,
+                        dreck: parse_cards(dreck)?,
+                    },
+                    ("OFFER", false) => Event::RedactedOfferDreck {
+                        player,
+                        dreck: dreck.parse().map_err(|_| invalid_notation(dreck))?,
+                    },
+                    (_, true) => Event::ReceiveDreck {
+                        player,
+                        dreck: parse_cards(dreck)?,
+                    },
+                    (_, false) => Event::RedactedReceiveDreck {
+                        player,
+                        dreck: dreck.parse().map_err(|_| invalid_notation(dreck))?,
+                    },
+                }
+            }
+            "RUN" => {
+                let Run(lo, hi) = arg()?.parse()?;
+                no_player(Event::PlayRun { lo, hi })?
+            }
+            "PU" => no_player(Event::PickUp)?,
+            "NOISE" => no_player(Event::Goat {
+                noise: arg()?.parse().map_err(|_| invalid_notation(line))?,
+            })?,
+            "SEED" => no_player(Event::RevealSeed {
+                seed: arg()?.parse()?,
+                nonce: arg()?.parse()?,
+            })?,
+            "UNKNOWN" => no_player(Event::Unknown)?,
+            _ => return Err(invalid_notation(verb)),
+        };
+        moves.push(event);
+    }
+    Ok(())
+}
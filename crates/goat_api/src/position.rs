@@ -0,0 +1,415 @@
+use std::fmt;
+use std::fmt::{Display, Write as _};
+use std::str::FromStr;
+
+use crate::{
+    Card, Cards, ClientDeck, ClientGame, ClientPhase, ClientRummyHand, ClientWarHand, GoatError,
+    Nonce, PlayerIdx, PreviousTrick, Rank, RummyHistory, RummyPhase, RummyTrick, ServerGame,
+    ServerPhase, ServerWarHand, Suit, UserId, WarHand, WarPhase, WarTrick, MAX_DECKS, MAX_PLAYERS,
+    MIN_PLAYERS,
+};
+
+/// A game in progress, set up directly rather than reached by playing, for puzzles and tests.
+///
+/// Positions are written one line per field, each a key followed by its value. Hands and won
+/// cards are lists of [`Cards`], cards in the deck or a run are written like [`Card`], and
+/// players are written as `P0`, `P1` and so on:
+///
+/// ```text
+/// hands [Q9S JD] [] [AKH 2C]
+/// trump 9H
+/// trick 5D-8D 9D
+/// next P2
+/// played [T8C]
+/// ```
+///
+/// A position in the war phase has a `deck` instead of a `trump`, listed from the card that will
+/// be turned up as trump to the top card, and the `won` cards and trick `leader` instead of the
+/// `trick`, `next` player and `played` cards. Optional fields are left out when they are empty.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Position {
+    War(WarPosition),
+    Rummy(RummyPosition),
+}
+
+/// A war phase position at the start of a trick.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WarPosition {
+    /// The cards left in the deck. The first is turned up as trump and the last is on top.
+    pub deck: Vec<Card>,
+    pub hands: Vec<Cards>,
+    pub won: Vec<Cards>,
+    pub leader: PlayerIdx,
+}
+
+/// A rummy phase position after the dreck has been given out.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RummyPosition {
+    pub hands: Vec<Cards>,
+    pub trump: Card,
+    /// The runs played on the current trick so far.
+    pub trick: Vec<(Card, Card)>,
+    pub next: PlayerIdx,
+    /// The cards already killed, which players might remember.
+    pub played: Cards,
+}
+
+impl Position {
+    pub fn hands(&self) -> &[Cards] {
+        match self {
+            Position::War(war) => &war.hands,
+            Position::Rummy(rummy) => &rummy.hands,
+        }
+    }
+
+    /// Checks that the position could come up in a game.
+    pub fn check(&self) -> Result<(), GoatError> {
+        let invalid = || GoatError::InvalidState {
+            what: "position".into(),
+        };
+        let num_players = self.hands().len();
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&num_players) {
+            return Err(GoatError::InvalidNumberOfPlayers);
+        }
+        match self {
+            Position::War(war) => {
+                if war.deck.is_empty()
+                    || war.won.len() != num_players
+                    || war.leader.idx() >= num_players
+                    || war.hands.iter().any(|hand| hand.len() > 3)
+                {
+                    return Err(invalid());
+                }
+                // Every card has to be somewhere, or the dreck can't be given out.
+                let cards: usize = war.hands.iter().chain(&war.won).map(|c| c.len()).sum();
+                let held = war.hands.iter().chain(&war.won).flat_map(|c| c.cards());
+                if !(war.deck.len() + cards).is_multiple_of(52)
+                    || !fits_in_decks(war.deck.iter().copied().chain(held))
+                {
+                    return Err(invalid());
+                }
+            }
+            Position::Rummy(rummy) => {
+                let active = rummy.hands.iter().filter(|hand| !hand.is_empty()).count();
+                if active < 2
+                    || rummy.next.idx() >= num_players
+                    || rummy.hands[rummy.next.idx()].is_empty()
+                    || rummy.trick.len() >= active
+                {
+                    return Err(invalid());
+                }
+                let mut trick = RummyTrick::new(active);
+                for &(lo, hi) in &rummy.trick {
+                    if lo.suit() != hi.suit() || lo.rank() > hi.rank() {
+                        return Err(GoatError::InvalidRange { lo, hi });
+                    }
+                    if !trick.can_play(lo, rummy.trump.suit()) {
+                        return Err(GoatError::CannotPlayRange { lo });
+                    }
+                    trick.play(lo, hi);
+                }
+                let runs = rummy.trick.iter().flat_map(|&run| Cards::from(run).cards());
+                let held = rummy.hands.iter().chain([&rummy.played]);
+                if !fits_in_decks(held.flat_map(|c| c.cards()).chain(runs)) {
+                    return Err(invalid());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A game between `players`, one for each hand, set up at this position. Dreck is given out
+    /// with `seed` if the game gets that far. The game's events start from the position, so views
+    /// of it have to be set up with [`Position::client_game`] rather than by replaying them.
+    pub fn server_game(&self, players: Vec<UserId>, seed: u64) -> Result<ServerGame, GoatError> {
+        self.check()?;
+        if players.len() != self.hands().len() {
+            return Err(GoatError::InvalidNumberOfPlayers);
+        }
+        let phase = match self {
+            Position::War(war) => ServerPhase::War(WarPhase {
+                deck: war.deck.clone(),
+                hands: war
+                    .hands
+                    .iter()
+                    .map(|hand| ServerWarHand::try_from(hand.cards().collect::<Vec<_>>()))
+                    .collect::<Result<_, _>>()?,
+                won: war.won.clone().into_boxed_slice(),
+                trick: WarTrick::new(war.leader, players.len()),
+                prev_trick: None,
+            }),
+            Position::Rummy(rummy) => {
+                let hands = rummy.hands.clone().into_boxed_slice();
+                let mut phase = RummyPhase::new(hands, rummy.next, rummy.trump);
+                for &(lo, hi) in &rummy.trick {
+                    phase.trick.play(lo, hi);
+                }
+                ServerPhase::Rummy(phase)
+            }
+        };
+        Ok(ServerGame {
+            phase,
+            players,
+            events: Vec::new(),
+            seed,
+            nonce: Nonce(rand::random()),
+            withhold_seed: false,
+        })
+    }
+
+    /// The position as `viewer` sees it, or as a spectator sees it if there is no viewer.
+    pub fn client_game<PrevTrick: PreviousTrick, History: RummyHistory>(
+        &self,
+        players: Vec<UserId>,
+        viewer: Option<PlayerIdx>,
+    ) -> Result<ClientGame<PrevTrick, History>, GoatError> {
+        self.check()?;
+        if players.len() != self.hands().len() {
+            return Err(GoatError::InvalidNumberOfPlayers);
+        }
+        let visible = |idx: usize| viewer == Some(PlayerIdx(idx as u8));
+        let phase = match self {
+            Position::War(war) => ClientPhase::War(WarPhase {
+                deck: ClientDeck::with_remaining(war.deck.len() - 1),
+                hands: war
+                    .hands
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, hand)| {
+                        if visible(idx) {
+                            let mut visible = ClientWarHand::new();
+                            hand.cards().for_each(|card| visible += card);
+                            visible
+                        } else {
+                            ClientWarHand::Hidden(hand.len() as u8)
+                        }
+                    })
+                    .collect(),
+                won: war.won.clone().into_boxed_slice(),
+                trick: WarTrick::new(war.leader, players.len()),
+                prev_trick: PrevTrick::empty(),
+            }),
+            Position::Rummy(rummy) => {
+                let hands = rummy
+                    .hands
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, hand)| {
+                        if visible(idx) {
+                            ClientRummyHand {
+                                known: *hand,
+                                unknown: 0,
+                            }
+                        } else {
+                            ClientRummyHand {
+                                known: Cards::NONE,
+                                unknown: hand.len() as u16,
+                            }
+                        }
+                    })
+                    .collect();
+                let mut phase = RummyPhase::new(hands, rummy.next, rummy.trump);
+                for &(lo, hi) in &rummy.trick {
+                    phase.trick.play(lo, hi);
+                }
+                phase.history = History::from_played(players.len(), rummy.played);
+                ClientPhase::Rummy(phase)
+            }
+        };
+        Ok(ClientGame { phase, players })
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cards_list = |f: &mut fmt::Formatter<'_>, key: &str, list: &[Cards]| {
+            f.write_str(key)?;
+            list.iter().try_for_each(|cards| write!(f, " {}", cards))?;
+            f.write_char('\n')
+        };
+        match self {
+            Position::War(war) => {
+                f.write_str("deck")?;
+                war.deck
+                    .iter()
+                    .try_for_each(|card| write!(f, " {}", card))?;
+                f.write_char('\n')?;
+                cards_list(f, "hands", &war.hands)?;
+                if war.won.iter().any(|won| !won.is_empty()) {
+                    cards_list(f, "won", &war.won)?;
+                }
+                writeln!(f, "leader P{}", war.leader)
+            }
+            Position::Rummy(rummy) => {
+                cards_list(f, "hands", &rummy.hands)?;
+                writeln!(f, "trump {}", rummy.trump)?;
+                if !rummy.trick.is_empty() {
+                    f.write_str("trick")?;
+                    for &(lo, hi) in &rummy.trick {
+                        write!(f, " {}", Run(lo, hi))?;
+                    }
+                    f.write_char('\n')?;
+                }
+                writeln!(f, "next P{}", rummy.next)?;
+                if !rummy.played.is_empty() {
+                    writeln!(f, "played {}", rummy.played)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Position {
+    type Err = GoatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hands = None;
+        let mut deck = None;
+        let mut won = None;
+        let mut leader = None;
+        let mut trump = None;
+        let mut trick = None;
+        let mut next = None;
+        let mut played = None;
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            let duplicate = match key {
+                "hands" => hands.replace(parse_cards_list(value)?).is_some(),
+                "deck" => deck
+                    .replace(
+                        value
+                            .split_whitespace()
+                            .map(str::parse)
+                            .collect::<Result<_, _>>()?,
+                    )
+                    .is_some(),
+                "won" => won.replace(parse_cards_list(value)?).is_some(),
+                "leader" => leader.replace(parse_player(value)?).is_some(),
+                "trump" => trump.replace(value.parse()?).is_some(),
+                "trick" => trick
+                    .replace(
+                        value
+                            .split_whitespace()
+                            .map(|run| run.parse().map(|Run(lo, hi)| (lo, hi)))
+                            .collect::<Result<_, _>>()?,
+                    )
+                    .is_some(),
+                "next" => next.replace(parse_player(value)?).is_some(),
+                "played" => played.replace(parse_cards(value)?).is_some(),
+                _ => true,
+            };
+            if duplicate {
+                return Err(invalid_notation(line));
+            }
+        }
+        let hands: Vec<Cards> = hands.ok_or_else(|| invalid_notation(s))?;
+        let position = match (deck, trump) {
+            (Some(deck), None) if trick.is_none() && next.is_none() && played.is_none() => {
+                Position::War(WarPosition {
+                    won: won.unwrap_or_else(|| vec![Cards::NONE; hands.len()]),
+                    hands,
+                    deck,
+                    leader: leader.unwrap_or(PlayerIdx(0)),
+                })
+            }
+            (None, Some(trump)) if won.is_none() && leader.is_none() => {
+                Position::Rummy(RummyPosition {
+                    hands,
+                    trump,
+                    trick: trick.unwrap_or_default(),
+                    next: next.ok_or_else(|| invalid_notation(s))?,
+                    played: played.unwrap_or(Cards::NONE),
+                })
+            }
+            _ => return Err(invalid_notation(s)),
+        };
+        position.check()?;
+        Ok(position)
+    }
+}
+
+/// A run of cards in one suit, written as its lowest and highest cards, like `5D-8D`, or as a
+/// single card if it only has one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Run(pub Card, pub Card);
+
+impl Display for Run {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Run(lo, hi) = *self;
+        if lo == hi {
+            write!(f, "{}", lo)
+        } else {
+            write!(f, "{}-{}", lo, hi)
+        }
+    }
+}
+
+impl FromStr for Run {
+    type Err = GoatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lo, hi) = match s.split_once('-') {
+            Some((lo, hi)) => (lo.parse()?, hi.parse()?),
+            None => {
+                let card = s.parse()?;
+                (card, card)
+            }
+        };
+        Ok(Run(lo, hi))
+    }
+}
+
+pub(crate) fn invalid_notation(text: &str) -> GoatError {
+    GoatError::InvalidNotation {
+        text: text.to_string(),
+    }
+}
+
+/// Parses a player written as `P0`, `P1` and so on.
+pub(crate) fn parse_player(s: &str) -> Result<PlayerIdx, GoatError> {
+    s.strip_prefix('P')
+        .and_then(|idx| idx.parse().ok())
+        .filter(|idx| (*idx as usize) < MAX_PLAYERS)
+        .map(PlayerIdx)
+        .ok_or_else(|| invalid_notation(s))
+}
+
+/// Parses cards written like `[Q9S JD]`, which unlike [`Cards::from_str`] rejects anything that
+/// isn't a rank or a suit.
+pub(crate) fn parse_cards(s: &str) -> Result<Cards, GoatError> {
+    let inner = s
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .filter(|inner| {
+            inner
+                .chars()
+                .all(|c| c == ' ' || Rank::try_from(c).is_ok() || Suit::try_from(c).is_ok())
+        })
+        .ok_or_else(|| invalid_notation(s))?;
+    inner.parse()
+}
+
+/// Whether `cards` could all have been dealt from as many decks as it takes to hold them, and no
+/// more than a game can be played with.
+fn fits_in_decks(cards: impl Iterator<Item = Card>) -> bool {
+    let mut copies = [0; 64];
+    for card in cards {
+        copies[card as usize] += 1;
+    }
+    let num_decks = copies.iter().sum::<usize>().div_ceil(52).max(1);
+    num_decks <= MAX_DECKS && copies.iter().all(|&copies| copies <= num_decks)
+}
+
+/// Parses a list of cards written like `[Q9S JD] [] [AKH]`.
+pub(crate) fn parse_cards_list(s: &str) -> Result<Vec<Cards>, GoatError> {
+    let mut list = Vec::new();
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        let end = rest.find(']').ok_or_else(|| invalid_notation(rest))?;
+        list.push(parse_cards(&rest[..=end])?);
+        rest = rest[end + 1..].trim_start();
+    }
+    Ok(list)
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeSet;
+
+use schemars::{generate::SchemaSettings, JsonSchema, Schema};
+use ts_rs::TS;
+
+use crate::{
+    Action, Capability, Card, Cards, ClientDeck, ClientPhase, ClientRummyHand, ClientWarHand,
+    Commitment, Event, GameId, GoatError, GoatPhase, Nonce, PlayerIdx, RandId, Rank, Response,
+    RummyPhase, RummyTrick, SeatScore, Seed, ServerWarHand, Snapshot, User, UserId, WarPhase,
+    WarPlay, WarPlayKind, WarTrick,
+};
+
+/// The header of every generated TypeScript module.
+const HEADER: &str = "// Generated from the Rust types, do not edit. Regenerate with\n\
+                      // `GOAT_UPDATE_PROTOCOL=1 cargo test`.\n";
+
+/// A TypeScript module of type declarations. Types used by the declarations must be declared in
+/// the module or imported from another one.
+#[derive(Default)]
+pub struct TypeScript {
+    declared: BTreeSet<String>,
+    used: BTreeSet<String>,
+    decls: Vec<String>,
+    imports: Vec<(String, BTreeSet<String>)>,
+}
+
+impl TypeScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn declare<T: TS + 'static + ?Sized>(mut self) -> Self {
+        self.declared.insert(T::ident());
+        self.used
+            .extend(T::dependencies().into_iter().map(|dep| dep.ts_name));
+        self.decls.push(format!("export {}\n", T::decl()));
+        self
+    }
+
+    /// Lets the declarations use the types declared by `module`, importing them from `from`.
+    pub fn import(mut self, from: &str, module: &TypeScript) -> Self {
+        self.imports
+            .push((from.to_string(), module.declared.clone()));
+        self
+    }
+
+    /// The module's source.
+    ///
+    /// Panics if a declaration uses a type that is neither declared nor imported.
+    pub fn module(&self) -> String {
+        let mut module = HEADER.to_string();
+        let mut missing: BTreeSet<_> = self.used.difference(&self.declared).collect();
+        for (from, available) in &self.imports {
+            let imports: Vec<_> = missing
+                .iter()
+                .filter(|name| available.contains(**name))
+                .map(|name| name.as_str())
+                .collect();
+            if !imports.is_empty() {
+                module += &format!(
+                    "import type {{ {} }} from \"{}\";\n",
+                    imports.join(", "),
+                    from
+                );
+            }
+            missing.retain(|name| !available.contains(*name));
+        }
+        assert!(missing.is_empty(), "undeclared types: {:?}", missing);
+        for decl in &self.decls {
+            module.push('\n');
+            module += decl;
+        }
+        module
+    }
+}
+
+/// TypeScript declarations of everything sent to or received from the server.
+pub fn typescript() -> TypeScript {
+    TypeScript::new()
+        .declare::<Action>()
+        .declare::<Response>()
+        .declare::<Event>()
+        .declare::<User>()
+        .declare::<Capability>()
+        .declare::<SeatScore>()
+        .declare::<GoatError>()
+        .declare::<GameId>()
+        .declare::<UserId>()
+        .declare::<PlayerIdx>()
+        .declare::<RandId>()
+        .declare::<Card>()
+        .declare::<Cards>()
+        .declare::<Rank>()
+        .declare::<Snapshot>()
+        .declare::<ClientPhase<Option<WarTrick>, Cards>>()
+        .declare::<WarPhase<ClientDeck, ClientWarHand, Option<WarTrick>>>()
+        .declare::<ClientDeck>()
+        .declare::<ClientWarHand>()
+        .declare::<ServerWarHand>()
+        .declare::<WarTrick>()
+        .declare::<WarPlay>()
+        .declare::<WarPlayKind>()
+        .declare::<RummyPhase<ClientRummyHand, Cards>>()
+        .declare::<ClientRummyHand>()
+        .declare::<RummyTrick>()
+        .declare::<GoatPhase>()
+        .declare::<Seed>()
+        .declare::<Nonce>()
+        .declare::<Commitment>()
+}
+
+/// The JSON Schema of `T`, with the schemas of the types it uses under `$defs`.
+pub fn json_schema<T: JsonSchema>() -> Schema {
+    SchemaSettings::draft2020_12()
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// Checks that a file generated from the Rust types is up to date, or rewrites it if
+/// `GOAT_UPDATE_PROTOCOL` is set. `path` is relative to the root of the workspace.
+#[cfg(any(test, feature = "check_generated"))]
+pub fn check_generated(path: &str, generated: &str) {
+    use std::path::Path;
+    use std::{env, fs};
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .join(path);
+    if env::var_os("GOAT_UPDATE_PROTOCOL").is_some() {
+        fs::write(&path, generated).unwrap();
+    }
+    let committed = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "{} is out of date, regenerate it with `GOAT_UPDATE_PROTOCOL=1 cargo test`",
+        path.display()
+    );
+}
@@ -1,12 +1,18 @@
-use rand::distributions::{Distribution, Standard};
-use rand::Rng;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::convert::Infallible;
+use std::borrow::Cow;
 use std::fmt;
 use std::fmt::Display;
 use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ts_rs::TS;
+
+use crate::GoatError;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, TS)]
+#[ts(type = "string")]
 pub struct RandId(u128);
 
 impl RandId {
@@ -40,14 +46,19 @@ impl Display for RandId {
 }
 
 impl FromStr for RandId {
-    type Err = Infallible;
+    type Err = GoatError;
 
+    /// Parses the 16 character form produced by [`Display`], rejecting anything else.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || GoatError::InvalidId { id: s.to_string() };
+        if s.len() != 16 {
+            return Err(invalid());
+        }
         let mut val = 0;
-        s.bytes().for_each(|b| {
+        for b in s.bytes() {
             val <<= 6;
-            val += decode(b) as u128;
-        });
+            val += decode(b).ok_or_else(invalid)? as u128;
+        }
         Ok(RandId(val))
     }
 }
@@ -62,6 +73,19 @@ impl Serialize for RandId {
     }
 }
 
+impl JsonSchema for RandId {
+    fn schema_name() -> Cow<'static, str> {
+        "RandId".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "pattern": "^[./0-9A-Za-z]{16}$",
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for RandId {
     fn deserialize<D>(des: D) -> Result<Self, D::Error>
     where
@@ -79,7 +103,7 @@ impl<'de> Deserialize<'de> for RandId {
             where
                 E: serde::de::Error,
             {
-                Ok(v.parse().unwrap())
+                v.parse().map_err(E::custom)
             }
         }
         des.deserialize_str(Visitor)
@@ -94,11 +118,12 @@ fn encode(b: u8) -> u8 {
     }
 }
 
-fn decode(b: u8) -> u8 {
+fn decode(b: u8) -> Option<u8> {
     match b {
-        46..=57 => b - 46,
-        65..=90 => b - 53,
-        _ => b - 59,
+        46..=57 => Some(b - 46),
+        65..=90 => Some(b - 53),
+        97..=122 => Some(b - 59),
+        _ => None,
     }
 }
 
@@ -110,7 +135,20 @@ mod test {
     #[test]
     fn encode_decode() {
         for b in 0..64 {
-            assert_eq!(b, decode(encode(b)));
+            assert_eq!(Some(b), decode(encode(b)));
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for id in [
+            "",
+            "short",
+            "0123456789abcdefg",
+            "0123456789abcde!",
+            "0123456789abcdé",
+        ] {
+            assert!(id.parse::<RandId>().is_err(), "{:?}", id);
         }
     }
 
@@ -1,8 +1,12 @@
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::fmt::{Debug, Display, Write};
 use std::{fmt, mem};
 
-use serde::{Serialize, Serializer};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ts_rs::TS;
 
 use crate::{Card, Suit};
 
@@ -11,7 +15,8 @@ const RANKS: [char; 13] = [
 ];
 
 #[repr(u8)]
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, TS)]
+#[ts(type = "string")]
 pub enum Rank {
     Two,
     Three,
@@ -89,3 +94,26 @@ impl Serialize for Rank {
         serializer.serialize_char(self.char())
     }
 }
+
+impl JsonSchema for Rank {
+    fn schema_name() -> Cow<'static, str> {
+        "Rank".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "enum": RANKS.map(String::from),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Rank {
+    fn deserialize<D>(des: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let c = char::deserialize(des)?;
+        Rank::try_from(c).map_err(|c| D::Error::custom(format!("{:?} is not a valid rank", c)))
+    }
+}
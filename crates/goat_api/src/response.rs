@@ -1,21 +1,51 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
-use crate::{Event, GameId, User, UserId};
+use crate::{Capability, Event, GameId, Snapshot, User, UserId};
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 pub enum Response {
-    #[serde(rename_all = "camelCase")]
+    /// The first response to a client that declared its protocol when subscribing, saying which
+    /// version and capabilities the server will use.
+    Hello {
+        version: u32,
+        capabilities: Vec<Capability>,
+    },
+    /// Sent every so often to keep the connection open.
     Ping,
     #[serde(rename_all = "camelCase")]
     Replay { game_id: GameId, events: Vec<Event> },
     #[serde(rename_all = "camelCase")]
     Game { game_id: GameId, event: Event },
     #[serde(rename_all = "camelCase")]
+    Snapshot {
+        game_id: GameId,
+        game: Box<Snapshot>,
+    },
+    /// The chance, in percent, of each seat of a game in the rummy phase ending up goat, estimated
+    /// from how many cards each seat holds without looking at any hand. Only sent to users with no
+    /// cards left to play in the game.
+    #[serde(rename_all = "camelCase")]
+    GoatMeter {
+        game_id: GameId,
+        goat_chances: Vec<u8>,
+    },
+    /// The tables of a duplicate set, which are all dealt the same cards, so players can't add
+    /// entropy to them.
+    #[serde(rename_all = "camelCase")]
+    DuplicateSet { game_ids: Vec<GameId> },
+    #[serde(rename_all = "camelCase")]
     ForgetGame { game_id: GameId },
     #[serde(rename_all = "camelCase")]
     User { user_id: UserId, user: User },
     #[serde(rename_all = "camelCase")]
     ForgetUser { user_id: UserId },
+    /// A response from a newer version of the protocol.
+    #[serde(other)]
+    #[schemars(skip)]
+    #[ts(skip)]
+    Unknown,
 }
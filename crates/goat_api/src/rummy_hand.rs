@@ -2,6 +2,10 @@ use std::fmt;
 use std::fmt::Debug;
 use std::ops::{AddAssign, SubAssign};
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
 use crate::{Card, Cards, GoatError};
 
 pub trait RummyHand: AddAssign<Card> + AddAssign<Cards> + SubAssign<Cards> {
@@ -34,10 +38,10 @@ impl RummyHand for Cards {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
 pub struct ClientRummyHand {
     pub known: Cards,
-    pub unknown: u8,
+    pub unknown: u16,
 }
 
 impl RummyHand for ClientRummyHand {
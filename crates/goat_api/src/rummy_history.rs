@@ -2,6 +2,14 @@ use crate::{Card, Cards, PlayerIdx};
 
 pub trait RummyHistory {
     fn new(num_players: usize) -> Self;
+
+    /// Starts the history from a snapshot, which only records the cards played so far.
+    fn from_played(num_players: usize, _played: Cards) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(num_players)
+    }
     fn lead(&mut self, player: PlayerIdx, lo: Card, hi: Card);
     fn play(&mut self, player: PlayerIdx, lo: Card, hi: Card);
     fn kill(&mut self, player: PlayerIdx, lo: Card, hi: Card);
@@ -25,6 +33,10 @@ impl RummyHistory for Cards {
         Cards::NONE
     }
 
+    fn from_played(_: usize, played: Cards) -> Self {
+        played
+    }
+
     fn lead(&mut self, _: PlayerIdx, lo: Card, hi: Card) {
         *self += Cards::range(lo, hi);
     }
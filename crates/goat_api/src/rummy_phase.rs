@@ -1,19 +1,63 @@
-use rand::prelude::{SliceRandom, StdRng};
-use rand::SeedableRng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
+use ts_rs::TS;
 
-use crate::{Card, Cards, Event, GoatError, PlayerIdx, Rank, RummyHand, RummyHistory, RummyTrick};
+use crate::{
+    shuffle_dreck, Card, Cards, Event, GoatError, PlayerIdx, Rank, RummyHand, RummyHistory,
+    RummyTrick,
+};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[serde(try_from = "RummyPhaseRepr<Hand, History>")]
+#[serde(bound(deserialize = "Hand: Deserialize<'de>, History: Deserialize<'de>"))]
 pub struct RummyPhase<Hand, History> {
     pub hands: Box<[Hand]>,
     pub trick: RummyTrick,
     pub next: PlayerIdx,
     pub trump: Card,
-    pub pick_ups: u128,
+    /// How many times each player has picked up since a trick was last killed or a hand emptied.
+    #[ts(as = "Vec<u8>")]
+    pub pick_ups: SmallVec<[u8; 16]>,
     pub history: History,
 }
 
+// A `RummyPhase` as it is deserialized, before checking that there is a pick up count for every
+// hand and that the next player is one of them.
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "RummyPhase")]
+struct RummyPhaseRepr<Hand, History> {
+    hands: Box<[Hand]>,
+    trick: RummyTrick,
+    next: PlayerIdx,
+    trump: Card,
+    /// How many times each player has picked up since a trick was last killed or a hand emptied.
+    pick_ups: SmallVec<[u8; 16]>,
+    history: History,
+}
+
+impl<Hand, History> TryFrom<RummyPhaseRepr<Hand, History>> for RummyPhase<Hand, History> {
+    type Error = GoatError;
+
+    fn try_from(repr: RummyPhaseRepr<Hand, History>) -> Result<Self, Self::Error> {
+        if repr.pick_ups.len() != repr.hands.len() || repr.next.idx() >= repr.hands.len() {
+            return Err(GoatError::InvalidState {
+                what: "rummy phase".into(),
+            });
+        }
+        Ok(Self {
+            hands: repr.hands,
+            trick: repr.trick,
+            next: repr.next,
+            trump: repr.trump,
+            pick_ups: repr.pick_ups,
+            history: repr.history,
+        })
+    }
+}
+
 impl<Hand: RummyHand, History: RummyHistory> RummyPhase<Hand, History> {
     pub fn new(hands: Box<[Hand]>, next: PlayerIdx, trump: Card) -> Self {
         let num_players = hands.len();
@@ -22,7 +66,7 @@ impl<Hand: RummyHand, History: RummyHistory> RummyPhase<Hand, History> {
             trick: RummyTrick::new(0),
             next,
             trump,
-            pick_ups: 0,
+            pick_ups: SmallVec::from_elem(0, num_players),
             history: History::new(num_players),
         };
         phase.reset_trick();
@@ -43,13 +87,13 @@ impl<Hand: RummyHand, History: RummyHistory> RummyPhase<Hand, History> {
         }
         *hand -= Cards::range(lo, hi);
         if hand.is_empty() {
-            self.pick_ups = 0;
+            self.pick_ups.fill(0);
         }
         let killed = self.trick.play(lo, hi);
         if killed {
             self.history.kill(player, lo, hi);
             self.reset_trick();
-            self.pick_ups = 0;
+            self.pick_ups.fill(0);
         } else {
             self.next = PlayerIdx(self.next.0 + 1);
             if self.trick.len() == 1 {
@@ -105,17 +149,16 @@ impl<Hand: RummyHand, History: RummyHistory> RummyPhase<Hand, History> {
     }
 
     fn increment_pick_ups(&mut self, player: PlayerIdx) -> bool {
-        let pick_ups = (self.pick_ups >> (8 * player.0)) & 0xff;
+        let pick_ups = self.pick_ups[player.idx()];
         if pick_ups < 64 {
-            self.pick_ups += 1 << (8 * player.0);
+            self.pick_ups[player.idx()] += 1;
         }
         if pick_ups == 63 {
-            for i in 0..self.hands.len() {
-                if !self.hands[i].is_empty() && (self.pick_ups >> (8 * i)) & 0xff != 64 {
-                    return false;
-                }
-            }
-            return true;
+            return self
+                .hands
+                .iter()
+                .zip(&self.pick_ups)
+                .all(|(hand, pick_ups)| hand.is_empty() || *pick_ups == 64);
         }
         false
     }
@@ -125,9 +168,9 @@ impl<Hand: RummyHand, History: RummyHistory> RummyPhase<Hand, History> {
     }
 }
 
-impl RummyPhase<Cards, ()> {
+impl RummyPhase<Cards, Cards> {
     pub fn distribute_dreck(&mut self, events: &mut Vec<Event>, seed: u64) {
-        let mut dreck_players: SmallVec<[PlayerIdx; 16]> = self
+        let dreck_players: SmallVec<[PlayerIdx; 16]> = self
             .hands
             .iter()
             .enumerate()
@@ -157,26 +200,9 @@ impl RummyPhase<Cards, ()> {
                     });
                 }
             });
-        if dreck_players.len() == 1 {
-            let player = dreck_players[0];
-            self.hands[player.idx()] += all_dreck;
-            events.push(Event::ReceiveDreck {
-                player,
-                dreck: all_dreck,
-            });
-        } else {
-            let mut all_dreck: Vec<_> = all_dreck.cards().collect();
-            let mut rng = StdRng::seed_from_u64(seed ^ 1);
-            dreck_players.shuffle(&mut rng);
-            all_dreck.shuffle(&mut rng);
-            let mut dreck_players = dreck_players.into_iter();
-            let mut all_dreck = all_dreck.into_iter();
-            while let Some(player) = dreck_players.next() {
-                let len = all_dreck.len() / (1 + dreck_players.len());
-                let dreck = all_dreck.by_ref().take(len).collect();
-                self.hands[player.idx()] += dreck;
-                events.push(Event::ReceiveDreck { player, dreck });
-            }
+        for (player, dreck) in shuffle_dreck(dreck_players, all_dreck, seed) {
+            self.hands[player.idx()] += dreck;
+            events.push(Event::ReceiveDreck { player, dreck });
         }
         self.reset_trick();
         self.advance_leader();
@@ -1,12 +1,17 @@
 use std::fmt;
 use std::fmt::Debug;
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
+use ts_rs::TS;
 
 use crate::{Card, Cards, Suit};
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
 pub struct RummyTrick {
+    #[ts(as = "Vec<(Card, Card)>")]
     plays: SmallVec<[(Card, Card); 12]>,
     num_players: usize,
 }
@@ -33,7 +38,7 @@ impl RummyTrick {
     }
 
     pub fn can_play(&self, card: Card, trump: Suit) -> bool {
-        self.top_card().map_or(true, |c| {
+        self.top_card().is_none_or(|c| {
             (card.suit() == c.suit() && card.rank() > c.rank())
                 || (card.suit() == trump && c.suit() != trump)
         })
@@ -1,36 +1,74 @@
-use rand::prelude::{SeedableRng, SliceRandom, StdRng};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    Action, Card, Cards, Event, GoatError, GoatPhase, PlayerIdx, RummyPhase, ServerWarHand, UserId,
-    WarHand, WarPhase, WarTrick,
+    deal_seed, shuffle_deck, Action, Card, Cards, ClientDeck, ClientPhase, ClientRummyHand,
+    ClientWarHand, Commitment, Event, GoatError, GoatPhase, Nonce, PlayerIdx, RummyPhase, Seed,
+    ServerWarHand, Snapshot, UserId, WarHand, WarPhase, WarTrick,
 };
 
-#[derive(Debug)]
+/// The fewest players a game can be started with.
+pub const MIN_PLAYERS: usize = 3;
+
+/// The most players a game can be started with.
+pub const MAX_PLAYERS: usize = 64;
+
+/// The most decks a game can be played with.
+pub const MAX_DECKS: usize = Cards::MAX_COPIES;
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ServerGame {
     pub phase: ServerPhase,
     pub players: Vec<UserId>,
     pub events: Vec<Event>,
     pub seed: u64,
+    pub nonce: Nonce,
+    /// Whether finishing the game leaves its seed to be revealed by [`ServerGame::reveal_seed`],
+    /// for games that share their seed with others still being played.
+    pub withhold_seed: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
 pub enum ServerPhase {
     Unstarted,
-    War(WarPhase<Vec<Card>, ServerWarHand, ()>),
-    Rummy(RummyPhase<Cards, ()>),
+    War(WarPhase<Vec<Card>, ServerWarHand, Option<WarTrick>>),
+    Rummy(RummyPhase<Cards, Cards>),
     Goat(GoatPhase),
 }
 
 impl ServerGame {
+    /// A game that will be shuffled with `seed`, which it commits to in its first event along with
+    /// a random nonce.
     pub fn with_seed(seed: u64) -> Self {
+        let nonce = Nonce(rand::random());
+        let mut events = Vec::with_capacity(128);
+        events.push(Event::Commit {
+            commitment: Commitment::of(Seed(seed), nonce),
+        });
         Self {
             phase: ServerPhase::Unstarted,
             players: Vec::with_capacity(4),
-            events: Vec::with_capacity(128),
+            events,
             seed,
+            nonce,
+            withhold_seed: false,
         }
     }
 
+    /// The seed the game is dealt with, which mixes in the entropy players have added so far.
+    pub fn deal_seed(&self) -> u64 {
+        let entropy: Vec<_> = self
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                Event::AddEntropy { entropy } => Some(*entropy),
+                _ => None,
+            })
+            .collect();
+        deal_seed(Seed(self.seed), &entropy)
+    }
+
     pub fn player(&self, user_id: UserId) -> Result<PlayerIdx, GoatError> {
         match self.players.iter().position(|p| *p == user_id) {
             Some(idx) => Ok(PlayerIdx(idx as u8)),
@@ -38,6 +76,67 @@ impl ServerGame {
         }
     }
 
+    /// The game as `viewer` sees it, or as a spectator sees it if there is no viewer, for clients
+    /// that would rather start from the current state than replay every event. Other players'
+    /// hands are only counted, so anything the viewer could have learnt about them from earlier
+    /// events, like the cards they picked up, is left out.
+    pub fn snapshot(&self, viewer: Option<PlayerIdx>) -> Snapshot {
+        let sees = |idx: usize| viewer.is_none_or(|viewer| viewer.idx() == idx);
+        let phase = match &self.phase {
+            ServerPhase::Unstarted => ClientPhase::Unstarted,
+            ServerPhase::War(war) => ClientPhase::War(WarPhase {
+                deck: ClientDeck::with_remaining(war.deck.len().saturating_sub(1)),
+                hands: war
+                    .hands
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, hand)| {
+                        if sees(idx) {
+                            let mut visible = ClientWarHand::new();
+                            hand.cards().for_each(|card| visible += card);
+                            visible
+                        } else {
+                            ClientWarHand::Hidden(hand.len() as u8)
+                        }
+                    })
+                    .collect(),
+                won: war.won.clone(),
+                trick: war.trick.clone(),
+                prev_trick: war.prev_trick.clone(),
+            }),
+            ServerPhase::Rummy(rummy) => ClientPhase::Rummy(RummyPhase {
+                hands: rummy
+                    .hands
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, hand)| {
+                        if sees(idx) {
+                            ClientRummyHand {
+                                known: *hand,
+                                unknown: 0,
+                            }
+                        } else {
+                            ClientRummyHand {
+                                known: Cards::NONE,
+                                unknown: hand.len() as u16,
+                            }
+                        }
+                    })
+                    .collect(),
+                trick: rummy.trick.clone(),
+                next: rummy.next,
+                trump: rummy.trump,
+                pick_ups: rummy.pick_ups.clone(),
+                history: rummy.history,
+            }),
+            ServerPhase::Goat(goat) => ClientPhase::Goat(goat.clone()),
+        };
+        Snapshot {
+            phase,
+            players: self.players.clone(),
+        }
+    }
+
     pub fn active(&self) -> bool {
         matches!(self.phase, ServerPhase::War(_) | ServerPhase::Rummy(_))
     }
@@ -59,46 +158,64 @@ impl ServerGame {
                     ServerPhase::Unstarted => {}
                     _ => return Err(GoatError::InvalidAction),
                 }
+                if player.idx() >= self.players.len() {
+                    return Err(GoatError::NoSuchPlayer { player });
+                }
                 self.players.swap_remove(player.idx());
                 self.events.push(Event::Leave { player });
             }
+            Action::Invite { user_id } => {
+                match self.phase {
+                    ServerPhase::Unstarted => {}
+                    _ => return Err(GoatError::InvalidAction),
+                }
+                if self.player(user_id).is_err() {
+                    self.events.push(Event::Invite { user_id });
+                }
+            }
+            Action::AddEntropy { entropy } => {
+                match self.phase {
+                    ServerPhase::Unstarted => {}
+                    _ => return Err(GoatError::InvalidAction),
+                }
+                self.player(user_id)?;
+                self.events.push(Event::AddEntropy { entropy });
+            }
             Action::Start { num_decks } => {
                 match self.phase {
                     ServerPhase::Unstarted => {}
                     _ => return Err(GoatError::InvalidAction),
                 }
-                if !(3..=15).contains(&self.players.len()) {
+                if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&self.players.len()) {
                     return Err(GoatError::InvalidNumberOfPlayers);
                 }
-                if !(1..=3).contains(&num_decks) {
+                if !(1..=MAX_DECKS).contains(&(num_decks as usize)) {
                     return Err(GoatError::InvalidNumberOfDecks);
                 }
                 let num_players = self.players.len();
-                let mut deck: Vec<_> = (Cards::ONE_DECK * num_decks as usize).cards().collect();
-                deck.shuffle(&mut StdRng::seed_from_u64(self.seed));
                 self.phase = ServerPhase::War(WarPhase {
-                    deck,
+                    deck: shuffle_deck(num_decks, self.deal_seed()),
                     hands: vec![ServerWarHand::new(); num_players].into_boxed_slice(),
                     won: vec![Cards::NONE; num_players].into_boxed_slice(),
                     trick: WarTrick::new(PlayerIdx(0), num_players),
-                    prev_trick: (),
+                    prev_trick: None,
                 });
                 self.events.push(Event::Start { num_decks });
             }
             Action::PlayCard { card } => {
                 let player = self.player(user_id)?;
-                let (war, events, _) = self.war()?;
+                let (war, events) = self.war()?;
                 if war.is_finished() {
                     return Err(GoatError::CannotPlayOnFinishedTrick);
                 }
                 let hand = &war.hands[player.idx()];
                 war.trick.check_can_play(player, hand, card)?;
-                war.play_from_hand(player, card);
+                war.play_from_hand(player, card)?;
                 events.push(Event::PlayCard { card });
             }
             Action::PlayTop => {
                 let player = self.player(user_id)?;
-                let (war, events, _) = self.war()?;
+                let (war, events) = self.war()?;
                 if war.deck.len() <= 1 {
                     return Err(GoatError::CannotPlayFromEmptyDeck);
                 }
@@ -110,15 +227,15 @@ impl ServerGame {
             }
             Action::Slough { card } => {
                 let player = self.player(user_id)?;
-                let (war, events, _) = self.war()?;
+                let (war, events) = self.war()?;
                 let hand = &war.hands[player.idx()];
                 war.trick.check_can_slough(player, hand, card)?;
-                war.slough(player, card);
+                war.slough(player, card)?;
                 events.push(Event::Slough { player, card });
             }
             Action::Draw => {
                 let player = self.player(user_id)?;
-                let (war, events, _) = self.war()?;
+                let (war, events) = self.war()?;
                 let hand = &mut war.hands[player.idx()];
                 if hand.len() == 3 {
                     return Err(GoatError::CannotDrawMoreThanThreeCards);
@@ -132,7 +249,8 @@ impl ServerGame {
             }
             Action::FinishTrick => {
                 let player = self.player(user_id)?;
-                let (war, events, seed) = self.war()?;
+                let seed = self.deal_seed();
+                let (war, events) = self.war()?;
                 let complete = war.finish_trick(player)?;
                 events.push(Event::FinishTrick { player });
                 if complete && war.is_finished() {
@@ -149,7 +267,8 @@ impl ServerGame {
                 rummy.play_run(player, lo, hi)?;
                 events.push(Event::PlayRun { lo, hi });
                 if rummy.is_finished() {
-                    self.phase = ServerPhase::Goat(GoatPhase::new(rummy.next));
+                    let goat = rummy.next;
+                    self.finish(goat);
                 }
             }
             Action::PickUp => {
@@ -158,7 +277,7 @@ impl ServerGame {
                 let complete = rummy.pick_up(player)?;
                 events.push(Event::PickUp);
                 if complete {
-                    self.phase = ServerPhase::Goat(GoatPhase::new(player));
+                    self.finish(player);
                 }
             }
             Action::Goat { noise } => {
@@ -174,24 +293,48 @@ impl ServerGame {
         Ok(())
     }
 
+    /// Reveals the seed, unless it already has been, and returns whether it did.
+    pub fn reveal_seed(&mut self) -> bool {
+        if self
+            .events
+            .iter()
+            .any(|event| matches!(event, Event::RevealSeed { .. }))
+        {
+            return false;
+        }
+        self.events.push(Event::RevealSeed {
+            seed: Seed(self.seed),
+            nonce: self.nonce,
+        });
+        true
+    }
+
+    /// Ends the game with `goat` as the goat, revealing the seed now that it can't help anyone
+    /// unless it is being withheld.
+    fn finish(&mut self, goat: PlayerIdx) {
+        self.phase = ServerPhase::Goat(GoatPhase::new(goat));
+        if !self.withhold_seed {
+            self.reveal_seed();
+        }
+    }
+
     #[allow(clippy::type_complexity)]
     fn war(
         &mut self,
     ) -> Result<
         (
-            &mut WarPhase<Vec<Card>, ServerWarHand, ()>,
+            &mut WarPhase<Vec<Card>, ServerWarHand, Option<WarTrick>>,
             &mut Vec<Event>,
-            u64,
         ),
         GoatError,
     > {
         match &mut self.phase {
-            ServerPhase::War(war) => Ok((war, &mut self.events, self.seed)),
+            ServerPhase::War(war) => Ok((war, &mut self.events)),
             _ => Err(GoatError::InvalidAction),
         }
     }
 
-    fn rummy(&mut self) -> Result<(&mut RummyPhase<Cards, ()>, &mut Vec<Event>), GoatError> {
+    fn rummy(&mut self) -> Result<(&mut RummyPhase<Cards, Cards>, &mut Vec<Event>), GoatError> {
         match &mut self.phase {
             ServerPhase::Rummy(rummy) => Ok((rummy, &mut self.events)),
             _ => Err(GoatError::InvalidAction),
@@ -0,0 +1,294 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::{Display, Write as _};
+use std::str::FromStr;
+
+use rand::prelude::{SeedableRng, SliceRandom, StdRng};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use smallvec::SmallVec;
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::{Card, Cards, Event, GoatError, PlayerIdx, RandId};
+
+/// The secret a game is shuffled from. It is only revealed, as 16 hex digits, once the game is
+/// over.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, TS)]
+#[ts(type = "string")]
+pub struct Seed(pub u64);
+
+/// Random bytes hashed along with a [`Seed`] so that its [`Commitment`] can't be undone by hashing
+/// every possible seed. It is revealed, as 64 hex digits, with the seed.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, TS)]
+#[ts(type = "string")]
+pub struct Nonce(pub [u8; 32]);
+
+/// A SHA-256 hash of a [`Nonce`] and a [`Seed`], published as 64 hex digits when a game is created
+/// so that the server can't change the seed once players have joined.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, TS)]
+#[ts(type = "string")]
+pub struct Commitment([u8; 32]);
+
+impl Commitment {
+    pub fn of(seed: Seed, nonce: Nonce) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(nonce.0);
+        hasher.update(seed.0.to_le_bytes());
+        Self(hasher.finalize().into())
+    }
+}
+
+/// The seed the deck and the dreck are actually shuffled with: the server's seed, mixed with the
+/// entropy contributed by players before the game started. Without any contributions this is just
+/// the server's seed.
+pub fn deal_seed(seed: Seed, entropy: &[RandId]) -> u64 {
+    if entropy.is_empty() {
+        return seed.0;
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(seed.0.to_le_bytes());
+    for entropy in entropy {
+        hasher.update(entropy.to_string());
+    }
+    u64::from_le_bytes(hasher.finalize()[..8].try_into().unwrap())
+}
+
+/// The deck a game with `num_decks` decks starts with, drawn from the back.
+pub(crate) fn shuffle_deck(num_decks: u8, deal_seed: u64) -> Vec<Card> {
+    let mut deck: Vec<_> = (Cards::ONE_DECK * num_decks as usize).cards().collect();
+    deck.shuffle(&mut StdRng::seed_from_u64(deal_seed));
+    deck
+}
+
+/// Splits `dreck` between `players`, who are in seat order, giving each of them as even a share
+/// as possible.
+pub(crate) fn shuffle_dreck(
+    mut players: SmallVec<[PlayerIdx; 16]>,
+    dreck: Cards,
+    deal_seed: u64,
+) -> Vec<(PlayerIdx, Cards)> {
+    let mut dreck: Vec<_> = dreck.cards().collect();
+    let mut rng = StdRng::seed_from_u64(deal_seed ^ 1);
+    players.shuffle(&mut rng);
+    dreck.shuffle(&mut rng);
+    let mut players = players.into_iter();
+    let mut dreck = dreck.into_iter();
+    let mut shares = Vec::with_capacity(players.len());
+    while let Some(player) = players.next() {
+        let len = dreck.len() / (1 + players.len());
+        shares.push((player, dreck.by_ref().take(len).collect()));
+    }
+    shares
+}
+
+/// Why the events of a game don't match the seed the server committed to.
+#[derive(Debug, Eq, Error, PartialEq)]
+pub enum ShuffleError {
+    #[error("The game doesn't start with a commitment to its seed")]
+    NotCommitted,
+    #[error("The game has not revealed its seed")]
+    NotRevealed,
+    #[error("The seed {seed} doesn't match the commitment {commitment}")]
+    CommitmentMismatch { seed: Seed, commitment: Commitment },
+    #[error("Entropy was contributed after the game started")]
+    LateEntropy,
+    #[error("Event {index} is redacted, so the deal can't be checked")]
+    Redacted { index: usize },
+    #[error("Event {index} reveals {found} but the deck holds {expected}")]
+    DeckMismatch {
+        index: usize,
+        expected: Card,
+        found: Card,
+    },
+    #[error("Event {index} reveals {found} but the deck has run out")]
+    DeckExhausted { index: usize, found: Card },
+    #[error("Event {index} gives out dreck that the seed doesn't")]
+    DreckMismatch { index: usize },
+}
+
+/// Checks that the unredacted events of a finished game are the ones its committed seed deals:
+/// that the revealed seed hashes to the commitment, that every card taken from the deck and the
+/// trump are where the shuffled deck has them, and that the dreck went to whom the seed sends it.
+/// Returns the seed the game was dealt with.
+pub fn verify_shuffle(events: &[Event]) -> Result<u64, ShuffleError> {
+    let Some(Event::Commit { commitment }) = events.first() else {
+        return Err(ShuffleError::NotCommitted);
+    };
+    let (seed, nonce) = events
+        .iter()
+        .find_map(|event| match event {
+            Event::RevealSeed { seed, nonce } => Some((*seed, *nonce)),
+            _ => None,
+        })
+        .ok_or(ShuffleError::NotRevealed)?;
+    if Commitment::of(seed, nonce) != *commitment {
+        return Err(ShuffleError::CommitmentMismatch {
+            seed,
+            commitment: *commitment,
+        });
+    }
+
+    let mut entropy = Vec::new();
+    let mut deal_seed = None;
+    let mut deck = Vec::new();
+    let mut offered = Cards::NONE;
+    let mut received = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        match event {
+            Event::AddEntropy {
+                entropy: contributed,
+            } => {
+                if deal_seed.is_some() {
+                    return Err(ShuffleError::LateEntropy);
+                }
+                entropy.push(*contributed);
+            }
+            Event::Start { num_decks } => {
+                let seed = self::deal_seed(seed, &entropy);
+                deck = shuffle_deck(*num_decks, seed);
+                deal_seed = Some(seed);
+            }
+            Event::Draw { card, .. } | Event::PlayTop { card } => take(&mut deck, index, *card)?,
+            Event::RevealTrump { trump } => {
+                deck.truncate(1);
+                take(&mut deck, index, *trump)?;
+            }
+            Event::OfferDreck { dreck, .. } => offered += *dreck,
+            Event::ReceiveDreck { player, dreck } => {
+                received.push((*player, *dreck));
+                // The dreck is given out in consecutive events, so check it after the last one.
+                if matches!(events.get(index + 1), Some(Event::ReceiveDreck { .. })) {
+                    continue;
+                }
+                let mut players: SmallVec<[PlayerIdx; 16]> =
+                    received.iter().map(|(player, _)| *player).collect();
+                players.sort_by_key(|player| player.idx());
+                if shuffle_dreck(players, offered, deal_seed.unwrap_or(seed.0)) != received {
+                    return Err(ShuffleError::DreckMismatch { index });
+                }
+            }
+            Event::RedactedDraw { .. }
+            | Event::RedactedOfferDreck { .. }
+            | Event::RedactedReceiveDreck { .. } => {
+                return Err(ShuffleError::Redacted { index });
+            }
+            _ => {}
+        }
+    }
+    Ok(deal_seed.unwrap_or(seed.0))
+}
+
+/// Takes the top card of the deck, which should be `found`.
+fn take(deck: &mut Vec<Card>, index: usize, found: Card) -> Result<(), ShuffleError> {
+    match deck.pop() {
+        Some(expected) if expected != found => Err(ShuffleError::DeckMismatch {
+            index,
+            expected,
+            found,
+        }),
+        Some(_) => Ok(()),
+        None => Err(ShuffleError::DeckExhausted { index, found }),
+    }
+}
+
+impl Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for Seed {
+    type Err = GoatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 16 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(GoatError::InvalidHex { hex: s.to_string() });
+        }
+        Ok(Seed(u64::from_str_radix(s, 16).unwrap()))
+    }
+}
+
+/// Parses 64 hex digits into the 32 bytes they spell.
+fn parse_bytes(s: &str) -> Result<[u8; 32], GoatError> {
+    if s.len() != 64 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(GoatError::InvalidHex { hex: s.to_string() });
+    }
+    let mut bytes = [0; 32];
+    for (b, hex) in bytes.iter_mut().zip(s.as_bytes().chunks(2)) {
+        *b = u8::from_str_radix(std::str::from_utf8(hex).unwrap(), 16).unwrap();
+    }
+    Ok(bytes)
+}
+
+impl Display for Nonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl FromStr for Nonce {
+    type Err = GoatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_bytes(s).map(Self)
+    }
+}
+
+impl Display for Commitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl FromStr for Commitment {
+    type Err = GoatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_bytes(s).map(Self)
+    }
+}
+
+macro_rules! hex_serde {
+    ($ty:ident, $len:literal) => {
+        impl Serialize for $ty {
+            fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                let mut buf = String::with_capacity($len);
+                write!(buf, "{}", self).unwrap();
+                ser.serialize_str(&buf)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D>(des: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let s = Cow::<str>::deserialize(des)?;
+                s.parse().map_err(D::Error::custom)
+            }
+        }
+
+        impl JsonSchema for $ty {
+            fn schema_name() -> Cow<'static, str> {
+                stringify!($ty).into()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                json_schema!({
+                    "type": "string",
+                    "pattern": concat!("^[0-9a-f]{", $len, "}$"),
+                })
+            }
+        }
+    };
+}
+
+hex_serde!(Seed, 16);
+hex_serde!(Nonce, 64);
+hex_serde!(Commitment, 64);
@@ -1,12 +1,20 @@
 use core::mem;
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
+use std::iter;
 use std::iter::FromIterator;
 
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
 use crate::{
-    Card, Cards, ClientGame, ClientPhase, ClientRummyHand, ClientWarHand, PlayerIdx, Rank,
-    RummyPhase, RummyTrick, ServerGame, ServerPhase, ServerWarHand, Suit, WarHand, WarPhase,
-    WarPlayKind, WarTrick,
+    check_generated, finishing_places, json_schema, score_duplicate, seating, typescript,
+    verify_shuffle, Action, Capability, Card, Cards, Client, ClientGame, ClientPhase,
+    ClientRummyHand, ClientWarHand, Commitment, Event, GameId, GameRecord, GoatError,
+    InvariantChecker, Nonce, PlayerIdx, Position, Protocol, RandId, Rank, Response, RummyHand,
+    RummyPhase, RummyTrick, Seed, ServerGame, ServerPhase, ServerWarHand, ShuffleError, Snapshot,
+    Suit, TypeScript, UserId, WarHand, WarPhase, WarPlayKind, WarTrick, PROTOCOL_VERSION,
 };
 
 macro_rules! c {
@@ -23,6 +31,14 @@ fn card_display() {
     assert_eq!(Card::AceHearts.to_string(), "AH");
 }
 
+#[test]
+fn card_parse_invalid() {
+    for card in ["", "A", "1S", "AX", "ASS", "\u{e9}"] {
+        assert!(card.parse::<Card>().is_err(), "{:?}", card);
+    }
+    assert_eq!("TD".parse::<Card>().unwrap(), Card::TenDiamonds);
+}
+
 #[test]
 fn card_suit() {
     assert_eq!(Card::TwoClubs.suit(), Suit::Clubs);
@@ -160,6 +176,69 @@ fn cards_top_of_run() {
     );
 }
 
+#[test]
+fn cards_many_decks() {
+    let decks = Cards::ONE_DECK * Cards::MAX_COPIES;
+    assert_eq!(decks.len(), 52 * Cards::MAX_COPIES);
+    assert_eq!(decks.cards().count(), 52 * Cards::MAX_COPIES);
+    assert_eq!(decks.cards().rev().count(), 52 * Cards::MAX_COPIES);
+    assert!(decks.contains_all(Cards::ONE_DECK * 5));
+    assert!(!(Cards::ONE_DECK * 5).contains_all(decks));
+    let hand = c!(AAAAAKS 2222222C);
+    assert_eq!(hand.len(), 13);
+    assert_eq!(hand.above(Card::KingSpades), c!(AS));
+    assert_eq!(hand.top_of_run(Card::KingSpades), Card::AceSpades);
+    assert_eq!((hand - c!(AAAAS 22C)).to_string(), "[AKS 22222C]");
+}
+
+#[test]
+fn cards_reject_too_many_copies() {
+    assert_eq!(c!(222222C).checked_add(Card::TwoClubs), Some(c!(2222222C)));
+    assert_eq!(c!(2222222C).checked_add(Card::TwoClubs), None);
+    assert!("2222222C".parse::<Cards>().is_ok());
+    assert!("22222222C".parse::<Cards>().is_err());
+    let eight = format!("[{}]", ["\"2C\""; 8].join(","));
+    assert!(serde_json::from_str::<Cards>(&eight).is_err());
+}
+
+/// The inputs that crashed the `event_json` and `response_json` fuzz targets.
+#[test]
+fn json_with_too_many_copies() {
+    let game_id = serde_json::to_string(&GameId(RandId::from_hash(&[0; 16]))).unwrap();
+    for copies in [7, 8] {
+        let dreck = ["\"2C\""; 8][..copies].join(",");
+        let event = format!(r#"{{"type":"offerDreck","player":0,"dreck":[{}]}}"#, dreck);
+        let response = format!(
+            r#"{{"type":"game","gameId":{},"event":{}}}"#,
+            game_id, event
+        );
+        let valid = copies <= Cards::MAX_COPIES;
+        assert_eq!(serde_json::from_str::<Event>(&event).is_ok(), valid);
+        assert_eq!(serde_json::from_str::<Response>(&response).is_ok(), valid);
+    }
+}
+
+#[test]
+fn war_trick_end_mask() {
+    assert_eq!(WarTrick::new(PlayerIdx(0), 0).end_mask(), 0);
+    assert_eq!(WarTrick::new(PlayerIdx(0), 3).end_mask(), 0b111);
+    assert_eq!(WarTrick::new(PlayerIdx(63), 64).end_mask(), u64::MAX);
+}
+
+#[test]
+fn war_trick_many_players() {
+    let mut t = WarTrick::new(PlayerIdx(17), 20);
+    let order: Vec<_> = t.remaining_players().map(|p| p.0).collect();
+    assert_eq!(order[..4], [17, 18, 19, 0]);
+    assert_eq!(order.len(), 20);
+    for player in 0..19 {
+        assert!(!t.end(PlayerIdx(player)));
+        assert!(t.ended(PlayerIdx(player)));
+    }
+    assert!(!t.ended(PlayerIdx(19)));
+    assert!(t.end(PlayerIdx(19)));
+}
+
 #[test]
 fn war_trick_rank_winner_next() {
     fn next(t: &mut WarTrick, c: Card) -> (Option<Rank>, Option<u8>, Option<u8>) {
@@ -281,21 +360,806 @@ fn rummy_trick() {
     assert!(t.play(Card::EightSpades, Card::EightSpades));
 }
 
+#[test]
+fn rummy_phase_json() {
+    let hands = vec![c!(2C), c!(3C), c!(4C)].into_boxed_slice();
+    let phase = RummyPhase::<Cards, ()>::new(hands, PlayerIdx(1), Card::TwoSpades);
+    let json = serde_json::to_value(&phase).unwrap();
+    assert_eq!(
+        serde_json::from_value::<RummyPhase<Cards, ()>>(json.clone()).unwrap(),
+        phase
+    );
+    for (field, value) in [("pickUps", serde_json::json!([0, 0])), ("next", 3.into())] {
+        let mut json = json.clone();
+        json[field] = value;
+        assert!(
+            serde_json::from_value::<RummyPhase<Cards, ()>>(json).is_err(),
+            "{}",
+            field
+        );
+    }
+}
+
+#[test]
+fn leave_invalid_player() {
+    let user_id = UserId(RandId::from_hash(&[0; 16]));
+    let mut game = ServerGame::with_seed(0);
+    game.apply(user_id, Action::Join { user_id }).unwrap();
+    assert!(game
+        .apply(
+            user_id,
+            Action::Leave {
+                player: PlayerIdx(1)
+            }
+        )
+        .is_err());
+    assert_eq!(game.players, [user_id]);
+}
+
+#[test]
+fn war_cards_must_be_in_hand() {
+    let mut war: WarPhase<Vec<Card>, ServerWarHand, ()> = WarPhase {
+        deck: Vec::new(),
+        hands: vec![ServerWarHand::new(); 3].into_boxed_slice(),
+        won: vec![Cards::NONE; 3].into_boxed_slice(),
+        trick: WarTrick::new(PlayerIdx(0), 3),
+        prev_trick: (),
+    };
+    war.hands[0] += Card::TwoClubs;
+    assert!(matches!(
+        war.play_from_hand(PlayerIdx(0), Card::ThreeClubs),
+        Err(GoatError::NotYourCard {
+            card: Card::ThreeClubs
+        })
+    ));
+    assert!(war.slough(PlayerIdx(1), Card::TwoClubs).is_err());
+    assert!(war.trick.plays().is_empty());
+    assert_eq!(war.hands[0].len(), 1);
+    war.play_from_hand(PlayerIdx(0), Card::TwoClubs).unwrap();
+    assert!(war.hands[0].is_empty());
+    assert_eq!(war.trick.plays().len(), 1);
+}
+
 #[test]
 fn size_of() {
-    assert_eq!(mem::size_of::<ClientGame<(), ()>>(), 152);
-    assert_eq!(mem::size_of::<ClientPhase<(), ()>>(), 128);
-    assert_eq!(mem::size_of::<WarPhase<u8, ClientWarHand, ()>>(), 128);
-    assert_eq!(mem::size_of::<WarTrick>(), 88);
-    assert_eq!(mem::size_of::<RummyPhase<ClientRummyHand, ()>>(), 80);
-
-    assert_eq!(mem::size_of::<ServerGame>(), 200);
-    assert_eq!(mem::size_of::<ServerPhase>(), 144);
+    assert_eq!(mem::size_of::<ClientGame<(), ()>>(), 176);
+    assert_eq!(mem::size_of::<ClientPhase<(), ()>>(), 152);
+    assert_eq!(mem::size_of::<WarPhase<u8, ClientWarHand, ()>>(), 152);
+    assert_eq!(mem::size_of::<WarTrick>(), 112);
+    assert_eq!(mem::size_of::<RummyPhase<ClientRummyHand, ()>>(), 88);
+
+    assert_eq!(mem::size_of::<ServerGame>(), 376);
+    assert_eq!(mem::size_of::<ServerPhase>(), 280);
     assert_eq!(
-        mem::size_of::<WarPhase<Vec<Card>, ServerWarHand, ()>>(),
-        144
+        mem::size_of::<WarPhase<Vec<Card>, ServerWarHand, Option<WarTrick>>>(),
+        280
     );
-    assert_eq!(mem::size_of::<RummyPhase<Cards, ()>>(), 80);
+    assert_eq!(mem::size_of::<RummyPhase<Cards, Cards>>(), 120);
     assert_eq!(mem::size_of::<ServerWarHand>(), 3);
     assert_eq!(mem::size_of::<ClientWarHand>(), 3);
 }
+
+/// Every action that might be legal for the player in seat `idx`, plus some that aren't.
+fn candidate_actions(game: &ServerGame, idx: PlayerIdx) -> Vec<Action> {
+    let mut actions = Vec::new();
+    match &game.phase {
+        ServerPhase::War(war) => {
+            actions.extend([Action::Draw, Action::PlayTop, Action::FinishTrick]);
+            for card in war.hands[idx.idx()].cards() {
+                actions.extend([Action::PlayCard { card }, Action::Slough { card }]);
+            }
+        }
+        ServerPhase::Rummy(rummy) if rummy.next == idx => {
+            actions.push(Action::PickUp);
+            for (lo, hi) in rummy.hands[idx.idx()].runs() {
+                for lo in Cards::range(lo, hi).cards() {
+                    for hi in Cards::range(lo, hi).cards() {
+                        actions.push(Action::PlayRun { lo, hi });
+                    }
+                }
+            }
+        }
+        ServerPhase::Goat(goat) if goat.goat == idx && goat.noise.is_none() => {
+            actions.push(Action::Goat { noise: 0 });
+        }
+        _ => {}
+    }
+    actions
+}
+
+/// Plays a game where every step takes the `choice`th action that turns out to be legal, checking
+/// the invariants after every action, legal or not. Returns whether the game finished.
+fn play_checked(
+    seed: u64,
+    num_players: usize,
+    num_decks: u8,
+    choices: impl IntoIterator<Item = usize>,
+) -> Result<bool, TestCaseError> {
+    let users: Vec<_> = (0..num_players)
+        .map(|idx| UserId(RandId::from_hash(&[idx as u8; 16])))
+        .collect();
+    let mut game = ServerGame::with_seed(seed);
+    let mut checker = InvariantChecker::new();
+    for user_id in &users {
+        game.apply(*user_id, Action::Join { user_id: *user_id })?;
+    }
+    game.apply(users[0], Action::Start { num_decks })?;
+    checker.check(&game)?;
+    for choice in choices {
+        let mut candidates: Vec<_> = (0..num_players)
+            .flat_map(|idx| {
+                let idx = PlayerIdx(idx as u8);
+                candidate_actions(&game, idx)
+                    .into_iter()
+                    .map(move |action| (idx, action))
+            })
+            .collect();
+        loop {
+            if candidates.is_empty() {
+                prop_assert!(
+                    matches!(game.phase, ServerPhase::Goat(_)),
+                    "nobody can act in {:?}",
+                    game.phase
+                );
+                return Ok(true);
+            }
+            let (idx, action) = candidates.swap_remove(choice % candidates.len());
+            let applied = game.apply(users[idx.idx()], action).is_ok();
+            checker.check(&game)?;
+            if applied {
+                break;
+            }
+        }
+    }
+    Ok(false)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn random_games_keep_invariants(
+        seed in any::<u64>(),
+        num_players in 3..=15_usize,
+        num_decks in 1..=3_u8,
+        choices in proptest::collection::vec(any::<usize>(), 0..2000),
+    ) {
+        play_checked(seed, num_players, num_decks, choices)?;
+    }
+}
+
+#[test]
+fn full_games_redact_correctly() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for (num_players, num_decks) in [(3, 1), (4, 1), (7, 2), (15, 3), (20, 5)] {
+        let seed = rng.gen();
+        let choices = iter::repeat_with(|| rng.gen());
+        assert!(play_checked(seed, num_players, num_decks, choices).unwrap());
+    }
+}
+
+/// `game` as a snapshot for `viewer` keeps it: without what the viewer has learnt about other
+/// players' hands.
+fn forget_others(
+    mut game: ClientGame<(), Cards>,
+    viewer: Option<PlayerIdx>,
+) -> ClientGame<(), Cards> {
+    let Some(viewer) = viewer else {
+        return game;
+    };
+    match &mut game.phase {
+        ClientPhase::War(war) => {
+            for (idx, hand) in war.hands.iter_mut().enumerate() {
+                if idx != viewer.idx() {
+                    *hand = ClientWarHand::Hidden(hand.len() as u8);
+                }
+            }
+        }
+        ClientPhase::Rummy(rummy) => {
+            for (idx, hand) in rummy.hands.iter_mut().enumerate() {
+                if idx != viewer.idx() {
+                    *hand = ClientRummyHand {
+                        known: Cards::NONE,
+                        unknown: hand.len() as u16,
+                    };
+                }
+            }
+        }
+        _ => {}
+    }
+    game
+}
+
+#[test]
+fn snapshots_match_replays() {
+    let mut rng = StdRng::seed_from_u64(1);
+    for (num_players, num_decks) in [(3, 1), (5, 2)] {
+        let users: Vec<_> = (0..num_players)
+            .map(|idx| UserId(RandId::from_hash(&[idx as u8; 16])))
+            .collect();
+        let mut game = ServerGame::with_seed(rng.gen());
+        for user_id in &users {
+            game.apply(*user_id, Action::Join { user_id: *user_id })
+                .unwrap();
+        }
+        let viewers: Vec<_> = iter::once(None)
+            .chain((0..num_players).map(|i| Some(PlayerIdx(i as u8))))
+            .collect();
+        // Views that started from a snapshot taken before the game started, and have applied
+        // every event since.
+        let mut resumed: Vec<_> = viewers
+            .iter()
+            .map(|viewer| ClientGame::<(), Cards>::from_snapshot(game.snapshot(*viewer)))
+            .collect();
+        let mut applied = game.events.len();
+        game.apply(users[0], Action::Start { num_decks }).unwrap();
+        while !matches!(game.phase, ServerPhase::Goat(_)) {
+            let idx = PlayerIdx(rng.gen_range(0..num_players) as u8);
+            let actions = candidate_actions(&game, idx);
+            if actions.is_empty() {
+                continue;
+            }
+            let action = actions[rng.gen_range(0..actions.len())];
+            if game.apply(users[idx.idx()], action).is_err() {
+                continue;
+            }
+            for (viewer, resumed) in viewers.iter().zip(&mut resumed) {
+                let snapshot = game.snapshot(*viewer);
+                let json = serde_json::to_string(&snapshot).unwrap();
+                assert_eq!(serde_json::from_str::<Snapshot>(&json).unwrap(), snapshot);
+                let mut replayed = ClientGame::<(), Cards>::default();
+                for event in &game.events {
+                    replayed.apply(event.redact(*viewer)).unwrap();
+                }
+                for event in &game.events[applied..] {
+                    resumed.apply(event.redact(*viewer)).unwrap();
+                }
+                let replayed = forget_others(replayed, *viewer);
+                assert_eq!(ClientGame::from_snapshot(snapshot), replayed);
+                assert_eq!(forget_others(resumed.clone(), *viewer), replayed);
+            }
+            applied = game.events.len();
+        }
+    }
+}
+
+#[test]
+fn server_games_round_trip() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let users: Vec<_> = (0..4)
+        .map(|idx| UserId(RandId::from_hash(&[idx as u8; 16])))
+        .collect();
+    let mut game = ServerGame::with_seed(rng.gen());
+    for user_id in &users {
+        game.apply(*user_id, Action::Join { user_id: *user_id })
+            .unwrap();
+    }
+    game.apply(users[0], Action::Start { num_decks: 1 })
+        .unwrap();
+    let mut restored: Option<ServerGame> = None;
+    while !matches!(game.phase, ServerPhase::Goat(_)) {
+        let json = serde_json::to_string(&game).unwrap();
+        if rng.gen_ratio(1, 10) {
+            restored = Some(serde_json::from_str(&json).unwrap());
+        }
+        let idx = PlayerIdx(rng.gen_range(0..users.len()) as u8);
+        let actions = candidate_actions(&game, idx);
+        if actions.is_empty() {
+            continue;
+        }
+        let action = actions[rng.gen_range(0..actions.len())];
+        let applied = game.apply(users[idx.idx()], action).is_ok();
+        if let Some(restored) = &mut restored {
+            assert_eq!(restored.apply(users[idx.idx()], action).is_ok(), applied);
+            assert_eq!(
+                serde_json::to_string(restored).unwrap(),
+                serde_json::to_string(&game).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn shuffles_verify() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for (num_players, num_decks, entropy) in [(3, 1, false), (4, 1, true), (7, 2, true)] {
+        let users: Vec<_> = (0..num_players)
+            .map(|idx| UserId(RandId::from_hash(&[idx as u8; 16])))
+            .collect();
+        let mut game = ServerGame::with_seed(rng.gen());
+        for user_id in &users {
+            game.apply(*user_id, Action::Join { user_id: *user_id })
+                .unwrap();
+            if entropy {
+                let entropy = rng.gen();
+                game.apply(*user_id, Action::AddEntropy { entropy })
+                    .unwrap();
+            }
+        }
+        assert_eq!(game.deal_seed() == game.seed, !entropy);
+        game.apply(users[0], Action::Start { num_decks }).unwrap();
+        assert!(game
+            .apply(users[0], Action::AddEntropy { entropy: rng.gen() })
+            .is_err());
+        assert_eq!(verify_shuffle(&game.events), Err(ShuffleError::NotRevealed));
+        while !matches!(game.phase, ServerPhase::Goat(_)) {
+            let idx = PlayerIdx(rng.gen_range(0..num_players) as u8);
+            let actions = candidate_actions(&game, idx);
+            if actions.is_empty() {
+                continue;
+            }
+            let action = actions[rng.gen_range(0..actions.len())];
+            let _ = game.apply(users[idx.idx()], action);
+        }
+        assert_eq!(verify_shuffle(&game.events), Ok(game.deal_seed()));
+
+        let reveal = game.events.len() - 1;
+        for (seed, nonce) in [
+            (game.seed + 1, game.nonce),
+            (game.seed, Nonce([!game.nonce.0[0]; 32])),
+        ] {
+            let mut events = game.events.clone();
+            let seed = Seed(seed);
+            events[reveal] = Event::RevealSeed { seed, nonce };
+            assert!(matches!(
+                verify_shuffle(&events),
+                Err(ShuffleError::CommitmentMismatch { .. })
+            ));
+        }
+
+        let mut events = game.events.clone();
+        let draw = events
+            .iter()
+            .position(|event| matches!(event, Event::Draw { .. }))
+            .unwrap();
+        if let Event::Draw { card, .. } = &mut events[draw] {
+            *card = card.with_rank(card.rank().next_down());
+        }
+        assert!(matches!(
+            verify_shuffle(&events),
+            Err(ShuffleError::DeckMismatch { index, .. }) if index == draw
+        ));
+
+        let mut events = game.events.clone();
+        let reveal = events
+            .iter()
+            .position(|event| matches!(event, Event::RevealTrump { .. }))
+            .unwrap();
+        let extra = Event::PlayTop {
+            card: Card::AceSpades,
+        };
+        events.insert(reveal + 1, extra);
+        assert!(matches!(
+            verify_shuffle(&events),
+            Err(ShuffleError::DeckExhausted { index, .. }) if index == reveal + 1
+        ));
+
+        let redacted: Vec<_> = game
+            .events
+            .iter()
+            .map(|e| e.redact(Some(PlayerIdx(0))))
+            .collect();
+        assert!(matches!(
+            verify_shuffle(&redacted),
+            Err(ShuffleError::Redacted { .. })
+        ));
+    }
+}
+
+#[test]
+fn seeds_and_commitments_round_trip() {
+    let seed = Seed(0x0123_4567_89ab_cdef);
+    let nonce = Nonce([0xa5; 32]);
+    let commitment = Commitment::of(seed, nonce);
+    assert_ne!(commitment, Commitment::of(seed, Nonce([0; 32])));
+    assert_eq!(
+        serde_json::to_string(&seed).unwrap(),
+        r#""0123456789abcdef""#
+    );
+    assert_eq!(
+        serde_json::to_string(&nonce).unwrap(),
+        format!("\"{}\"", "a5".repeat(32))
+    );
+    assert_eq!("a5".repeat(32).parse::<Nonce>().unwrap(), nonce);
+    let json = serde_json::to_string(&commitment).unwrap();
+    assert_eq!(json.len(), 66);
+    assert_eq!(
+        serde_json::from_str::<Commitment>(&json).unwrap(),
+        commitment
+    );
+    for invalid in [
+        "",
+        "+123456789abcdef",
+        "0123456789abcdeg",
+        "0123456789abcdef0",
+    ] {
+        assert!(invalid.parse::<Seed>().is_err(), "{:?}", invalid);
+    }
+}
+
+#[test]
+fn duplicate_tables_score_seat_by_seat() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let users: Vec<_> = (0..4)
+        .map(|idx| UserId(RandId::from_hash(&[idx as u8; 16])))
+        .collect();
+    let seed = rng.gen();
+    let mut tables = Vec::new();
+    for table in 0..users.len() {
+        let seats = seating(&users, table);
+        assert_eq!(seats[0], users[table]);
+        let mut game = ServerGame::with_seed(seed);
+        for user_id in &seats {
+            game.apply(*user_id, Action::Join { user_id: *user_id })
+                .unwrap();
+        }
+        game.apply(seats[0], Action::Start { num_decks: 1 })
+            .unwrap();
+        assert_eq!(
+            finishing_places(&game.events),
+            Err(GoatError::GameNotFinished)
+        );
+        while !matches!(game.phase, ServerPhase::Goat(_)) {
+            let idx = PlayerIdx(rng.gen_range(0..seats.len()) as u8);
+            let actions = candidate_actions(&game, idx);
+            if actions.is_empty() {
+                continue;
+            }
+            let action = actions[rng.gen_range(0..actions.len())];
+            let _ = game.apply(seats[idx.idx()], action);
+        }
+        assert_eq!(verify_shuffle(&game.events), Ok(seed));
+        let places = finishing_places(&game.events).unwrap();
+        let ServerPhase::Goat(goat) = &game.phase else {
+            unreachable!()
+        };
+        assert_eq!(places[goat.goat.idx()], users.len() - 1);
+        assert!(places.contains(&0), "{:?}", places);
+        tables.push(game);
+    }
+
+    let scores = score_duplicate(&tables.iter().collect::<Vec<_>>()).unwrap();
+    assert_eq!(scores.len(), users.len() * tables.len());
+    for seat in 0..users.len() {
+        let matchpoints: f64 = scores
+            .iter()
+            .filter(|score| score.seat.idx() == seat)
+            .map(|score| score.matchpoints)
+            .sum();
+        // Every pair of tables shares out one point for the seat.
+        assert_eq!(matchpoints, 6.0);
+    }
+    for score in &scores {
+        assert_eq!(score.user_id, tables[score.table].players[score.seat.idx()]);
+    }
+
+    let other = ServerGame::with_seed(seed + 1);
+    assert!(matches!(
+        score_duplicate(&[&tables[0], &other]),
+        Err(GoatError::InvalidState { .. })
+    ));
+}
+
+#[test]
+fn positions_round_trip() {
+    let rummy = "hands [Q9S JD] [] [AKH 2C] [3C]\n\
+                 trump 9H\n\
+                 trick 5D-8D 9D\n\
+                 next P2\n\
+                 played [T8C]\n";
+    let won = Cards::ONE_DECK - c!(9H 2C AS KD 7H QS JD 2D);
+    let war = format!(
+        "deck 9H 2C AS KD\n\
+         hands [7H] [QS JD] [] [2D]\n\
+         won {} [] [] []\n\
+         leader P1\n",
+        won
+    );
+    for text in [rummy, &war] {
+        let position: Position = text.parse().unwrap();
+        assert_eq!(position.to_string(), text);
+    }
+    let Position::Rummy(position) = rummy.parse().unwrap() else {
+        panic!("not a rummy position");
+    };
+    assert_eq!(position.hands[0], c!(Q9S JD));
+    assert_eq!(
+        position.trick,
+        [
+            (Card::FiveDiamonds, Card::EightDiamonds),
+            (Card::NineDiamonds, Card::NineDiamonds)
+        ]
+    );
+    assert_eq!(position.next, PlayerIdx(2));
+
+    for invalid in [
+        "",
+        "hands [Q9S JD] [] [AKH 2C]\ntrump 9H",
+        "hands [Q9S JD] [] [AKH 2C]\ntrump 9H\nnext P1",
+        "hands [Q9S JD] [] [AKH 2C]\ntrump 9H\nnext P0\nnext P0",
+        "hands [Q9S JD] [] [AKH 2C]\ntrump 9H\nnext P0\ntrick 8D-5D",
+        "hands [Q9S JD] [] [AKH 2C]\ntrump 9H\nnext P0\nleader P0",
+        "hands [Q9S JD] [] [AKH 2X]\ntrump 9H\nnext P0",
+        "hands [Q9S JD] [] [AKH 2C]\ndeck 9H\nnext P0",
+        "hands [Q9S JD] [] [AKH 2C]\ndeck 9H 2C 3C 4C\nhands [] [] []",
+        "hands [QT9S JD] [] [AKH 2C]\ndeck 9H 2C 3C 4C",
+        "hands [QQQQQQQQS] [2C]\ntrump 9H\nnext P0",
+        "hands [QQS] [2C]\ntrump 9H\nnext P0",
+        "hands [QQQS] [QS 2C]\ntrump 9H\nnext P0\nplayed [QQQS]",
+    ] {
+        assert!(invalid.parse::<Position>().is_err(), "{:?}", invalid);
+    }
+}
+
+#[test]
+fn positions_play_on() {
+    let users: Vec<_> = (0..3)
+        .map(|idx| UserId(RandId::from_hash(&[idx as u8; 16])))
+        .collect();
+    let position: Position = "hands [2C] [3C] [AS 4C]\ntrump 9H\nnext P0"
+        .parse()
+        .unwrap();
+    let mut game = position.server_game(users.clone(), 0).unwrap();
+    let mut views: Vec<ClientGame<(), Cards>> = (0..3)
+        .map(|idx| {
+            position
+                .client_game(users.clone(), Some(PlayerIdx(idx)))
+                .unwrap()
+        })
+        .collect();
+    for (idx, lo, hi) in [
+        (0, Card::TwoClubs, Card::TwoClubs),
+        (1, Card::ThreeClubs, Card::ThreeClubs),
+    ] {
+        let applied = game.events.len();
+        game.apply(users[idx], Action::PlayRun { lo, hi }).unwrap();
+        for (seat, view) in views.iter_mut().enumerate() {
+            for event in &game.events[applied..] {
+                view.apply(event.redact(Some(PlayerIdx(seat as u8))))
+                    .unwrap();
+            }
+        }
+    }
+    let ServerPhase::Goat(goat) = &game.phase else {
+        panic!("the game should be over");
+    };
+    assert_eq!(goat.goat, PlayerIdx(2));
+    for view in &views {
+        assert!(matches!(&view.phase, ClientPhase::Goat(goat) if goat.goat == PlayerIdx(2)));
+    }
+}
+
+#[test]
+fn game_records_round_trip() {
+    let mut rng = StdRng::seed_from_u64(5);
+    for (num_players, num_decks) in [(3, 1), (5, 2)] {
+        let users: Vec<_> = (0..num_players)
+            .map(|idx| UserId(RandId::from_hash(&[idx as u8; 16])))
+            .collect();
+        let mut game = ServerGame::with_seed(rng.gen());
+        for user_id in &users {
+            game.apply(*user_id, Action::Join { user_id: *user_id })
+                .unwrap();
+        }
+        game.apply(users[1], Action::AddEntropy { entropy: rng.gen() })
+            .unwrap();
+        game.apply(users[0], Action::Start { num_decks }).unwrap();
+        while !matches!(game.phase, ServerPhase::Goat(_)) {
+            let idx = PlayerIdx(rng.gen_range(0..num_players) as u8);
+            let actions = candidate_actions(&game, idx);
+            if actions.is_empty() {
+                continue;
+            }
+            let action = actions[rng.gen_range(0..actions.len())];
+            let _ = game.apply(users[idx.idx()], action);
+        }
+        let ServerPhase::Goat(goat) = &game.phase else {
+            unreachable!()
+        };
+        game.apply(users[goat.goat.idx()], Action::Goat { noise: 2 })
+            .unwrap();
+
+        for viewer in iter::once(None).chain(Some(Some(PlayerIdx(1)))) {
+            let events: Vec<_> = game.events.iter().map(|e| e.redact(viewer)).collect();
+            let text = GameRecord(events.clone()).to_string();
+            assert!(text.starts_with("[Commit "), "{}", text);
+            assert!(
+                !text.contains("JOIN") && !text.contains("START"),
+                "{}",
+                text
+            );
+            assert!(
+                text.contains("\nRUN ") || text.contains(" RUN "),
+                "{}",
+                text
+            );
+            assert_eq!(text.parse::<GameRecord>().unwrap().0, events);
+        }
+    }
+
+    // Events that aren't where the header puts them are written as moves.
+    let user_id = UserId(RandId::from_hash(&[0; 16]));
+    let events = vec![
+        Event::Start { num_decks: 1 },
+        Event::Join { user_id },
+        Event::RevealSeed {
+            seed: Seed(1),
+            nonce: Nonce([2; 32]),
+        },
+        Event::PlayRun {
+            lo: Card::FiveDiamonds,
+            hi: Card::EightDiamonds,
+        },
+        Event::Commit {
+            commitment: Commitment::of(Seed(1), Nonce([2; 32])),
+        },
+        Event::Unknown,
+        Event::Leave {
+            player: PlayerIdx(0),
+        },
+    ];
+    let text = GameRecord(events.clone()).to_string();
+    assert!(text.contains("RUN 5D-8D"), "{}", text);
+    assert_eq!(text.parse::<GameRecord>().unwrap().0, events);
+
+    for invalid in [
+        "RUN",
+        "P0 RUN 5D-8D",
+        "FT",
+        "P1 D 9X",
+        "P1 OFFER [2C",
+        "[Decks \"one\"]",
+        "PU\n[Decks \"1\"]",
+        "[Seed \"0\"]",
+        "[Seed \"0000000000000001\"]",
+        "SEED 0000000000000001",
+        "NOISE",
+        "DRAW",
+    ] {
+        assert!(invalid.parse::<GameRecord>().is_err(), "{:?}", invalid);
+    }
+}
+
+#[test]
+fn protocol_is_up_to_date() {
+    check_generated("frontend/src/protocol/goat_api.ts", &typescript().module());
+    for (name, schema) in [
+        ("action", json_schema::<Action>()),
+        ("response", json_schema::<Response>()),
+        ("error", json_schema::<GoatError>()),
+    ] {
+        let json = serde_json::to_string_pretty(&schema).unwrap() + "\n";
+        check_generated(&format!("schema/{}.schema.json", name), &json);
+    }
+}
+
+#[test]
+#[should_panic(expected = "undeclared types")]
+fn typescript_rejects_undeclared_types() {
+    TypeScript::new().declare::<Snapshot>().module();
+}
+
+#[test]
+fn unknown_responses_are_ignored() {
+    let game_id = GameId(RandId::from_hash(&[0; 16]));
+    let mut client: Client<(), (), Cards> = Client::new(());
+    client
+        .apply(Response::Replay {
+            game_id,
+            events: Vec::new(),
+        })
+        .unwrap();
+    let before = client.games.clone();
+    let json = format!(
+        r#"[{{"type":"chat","text":"hi"}},
+            {{"type":"game","gameId":"{}","event":{{"type":"startTimer","seconds":30}}}},
+            {{"type":"replay","gameId":"{0}","events":[{{"type":"startTimer","seconds":30}}]}}]"#,
+        game_id
+    );
+    let responses: Vec<Response> = serde_json::from_str(&json).unwrap();
+    assert_eq!(responses[0], Response::Unknown);
+    for response in responses {
+        client.apply(response).unwrap();
+    }
+    assert_eq!(client.games, before);
+    assert_eq!(
+        serde_json::from_str::<Capability>(r#""timers""#).unwrap(),
+        Capability::Unknown
+    );
+}
+
+#[test]
+fn protocol_negotiation() {
+    let server = Protocol::current();
+    let older = Protocol::legacy();
+    assert_eq!(server.negotiate(&older), older);
+
+    let newer = Protocol {
+        version: PROTOCOL_VERSION + 1,
+        capabilities: vec![Capability::Unknown, Capability::Snapshots],
+    };
+    assert_eq!(server.negotiate(&newer), server);
+
+    let hello = Response::Hello {
+        version: PROTOCOL_VERSION,
+        capabilities: Vec::new(),
+    };
+    assert!(!older.understands(&hello));
+    assert!(server.understands(&hello));
+    let snapshot = Response::Snapshot {
+        game_id: GameId(RandId::from_hash(&[0; 16])),
+        game: Box::default(),
+    };
+    assert!(!older.understands(&snapshot));
+    assert!(server.understands(&snapshot));
+    assert!(older.understands(&Response::Game {
+        game_id: GameId(RandId::from_hash(&[0; 16])),
+        event: Event::PickUp,
+    }));
+
+    let game = ServerGame::with_seed(0);
+    let replay = Response::Replay {
+        game_id: GameId(RandId::from_hash(&[0; 16])),
+        events: game.events.clone(),
+    };
+    assert_eq!(server.downgrade(replay.clone()), Some(replay.clone()));
+    assert_eq!(
+        older.downgrade(replay),
+        Some(Response::Replay {
+            game_id: GameId(RandId::from_hash(&[0; 16])),
+            events: Vec::new(),
+        })
+    );
+    let commit = Response::Game {
+        game_id: GameId(RandId::from_hash(&[0; 16])),
+        event: game.events[0].clone(),
+    };
+    assert_eq!(older.downgrade(commit), None);
+    let meter = Response::GoatMeter {
+        game_id: GameId(RandId::from_hash(&[0; 16])),
+        goat_chances: vec![20, 50, 30],
+    };
+    let version_two = Protocol {
+        version: 2,
+        capabilities: vec![Capability::Snapshots],
+    };
+    assert_eq!(version_two.downgrade(meter.clone()), None);
+    assert_eq!(server.downgrade(meter.clone()), Some(meter));
+    let set = Response::DuplicateSet {
+        game_ids: vec![GameId(RandId::from_hash(&[0; 16]))],
+    };
+    let version_three = Protocol {
+        version: 3,
+        capabilities: vec![Capability::Snapshots],
+    };
+    assert_eq!(version_three.downgrade(set.clone()), None);
+    assert_eq!(server.downgrade(set.clone()), Some(set));
+}
+
+#[test]
+fn errors_have_stable_codes() {
+    let errors = [
+        (
+            GoatError::MustMatchRank { rank: Rank::Queen },
+            r#"{"code":"mustMatchRank","rank":"Q"}"#,
+        ),
+        (
+            GoatError::NotYourTurn {
+                player: PlayerIdx(2),
+            },
+            r#"{"code":"notYourTurn","player":2}"#,
+        ),
+        (
+            GoatError::InvalidRange {
+                lo: Card::TwoHearts,
+                hi: Card::FiveHearts,
+            },
+            r#"{"code":"invalidRange","lo":"2H","hi":"5H"}"#,
+        ),
+        (GoatError::InvalidAction, r#"{"code":"invalidAction"}"#),
+    ];
+    for (error, json) in errors {
+        assert_eq!(serde_json::to_string(&error).unwrap(), json);
+        assert_eq!(serde_json::from_str::<GoatError>(json).unwrap(), error);
+    }
+}
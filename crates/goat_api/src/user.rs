@@ -1,7 +1,12 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
 pub struct User {
     pub name: String,
     pub online: bool,
+    /// Whether the user is a bot, so that it can be marked as one.
+    #[serde(default)]
+    pub bot: bool,
 }
@@ -2,6 +2,10 @@ use std::fmt;
 use std::fmt::Debug;
 use std::ops::{AddAssign, SubAssign};
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
 use crate::{Card, Cards, ClientRummyHand, GoatError, RummyHand};
 
 pub trait WarHand: AddAssign<Card> + SubAssign<Card> + Debug {
@@ -18,7 +22,9 @@ pub trait WarHand: AddAssign<Card> + SubAssign<Card> + Debug {
     fn merge_into_rummy_hand(&self, won: Cards) -> Self::RummyHand;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(into = "Vec<Card>", try_from = "Vec<Card>")]
+#[ts(as = "Vec<Card>")]
 pub struct ServerWarHand {
     cards: [Option<Card>; 3],
 }
@@ -75,11 +81,37 @@ impl AddAssign<Card> for ServerWarHand {
 }
 
 impl SubAssign<Card> for ServerWarHand {
+    /// Removes `rhs` from the hand. Callers check that it is there first with
+    /// [`WarHand::check_has_card`].
     fn sub_assign(&mut self, rhs: Card) {
-        let idx = self.cards.iter().position(|c| *c == Some(rhs)).unwrap();
-        #[allow(clippy::suspicious_op_assign_impl)]
-        self.cards.copy_within(idx + 1.., idx);
-        self.cards[2] = None;
+        let idx = self.cards.iter().position(|c| *c == Some(rhs));
+        debug_assert!(idx.is_some(), "{} is not in {:?}", rhs, self);
+        if let Some(idx) = idx {
+            #[allow(clippy::suspicious_op_assign_impl)]
+            self.cards.copy_within(idx + 1.., idx);
+            self.cards[2] = None;
+        }
+    }
+}
+
+impl From<ServerWarHand> for Vec<Card> {
+    fn from(hand: ServerWarHand) -> Self {
+        hand.cards().collect()
+    }
+}
+
+impl TryFrom<Vec<Card>> for ServerWarHand {
+    type Error = GoatError;
+
+    fn try_from(cards: Vec<Card>) -> Result<Self, Self::Error> {
+        if cards.len() > 3 {
+            return Err(GoatError::CannotDrawMoreThanThreeCards);
+        }
+        let mut hand = ServerWarHand::new();
+        for card in cards {
+            hand += card;
+        }
+        Ok(hand)
     }
 }
 
@@ -98,12 +130,42 @@ impl Debug for ServerWarHand {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(from = "ClientWarHandRepr", into = "ClientWarHandRepr")]
+#[ts(as = "ClientWarHandRepr")]
 pub enum ClientWarHand {
     Visible(ServerWarHand),
     Hidden(u8),
 }
 
+#[derive(Deserialize, JsonSchema, Serialize, TS)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "ClientWarHand")]
+#[ts(rename = "ClientWarHand")]
+enum ClientWarHandRepr {
+    Visible { cards: ServerWarHand },
+    Hidden { length: u8 },
+}
+
+impl From<ClientWarHand> for ClientWarHandRepr {
+    fn from(hand: ClientWarHand) -> Self {
+        match hand {
+            ClientWarHand::Visible(cards) => Self::Visible { cards },
+            ClientWarHand::Hidden(length) => Self::Hidden { length },
+        }
+    }
+}
+
+impl From<ClientWarHandRepr> for ClientWarHand {
+    fn from(repr: ClientWarHandRepr) -> Self {
+        match repr {
+            ClientWarHandRepr::Visible { cards } => Self::Visible(cards),
+            ClientWarHandRepr::Hidden { length } => Self::Hidden(length),
+        }
+    }
+}
+
 impl WarHand for ClientWarHand {
     type RummyHand = ClientRummyHand;
 
@@ -125,8 +187,12 @@ impl WarHand for ClientWarHand {
         }
     }
 
-    fn check_has_card(&self, _: Card) -> Result<(), GoatError> {
-        Ok(())
+    fn check_has_card(&self, card: Card) -> Result<(), GoatError> {
+        match self {
+            ClientWarHand::Visible(hand) => hand.check_has_card(card),
+            ClientWarHand::Hidden(0) => Err(GoatError::NotYourCard { card }),
+            ClientWarHand::Hidden(_) => Ok(()),
+        }
     }
 
     fn merge_into_rummy_hand(&self, won: Cards) -> Self::RummyHand {
@@ -137,7 +203,7 @@ impl WarHand for ClientWarHand {
             },
             ClientWarHand::Hidden(len) => ClientRummyHand {
                 known: won,
-                unknown: *len,
+                unknown: *len as u16,
             },
         }
     }
@@ -1,12 +1,17 @@
 use std::fmt::Debug;
 use std::mem;
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
 use crate::{
     Card, Cards, Deck, GoatError, PlayerIdx, PreviousTrick, RummyHistory, RummyPhase, WarHand,
     WarPlayKind, WarTrick,
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
 pub struct WarPhase<Deck, Hand, Trick> {
     pub deck: Deck,
     pub hands: Box<[Hand]>,
@@ -16,20 +21,24 @@ pub struct WarPhase<Deck, Hand, Trick> {
 }
 
 impl<D: Deck, Hand: WarHand, Trick: PreviousTrick> WarPhase<D, Hand, Trick> {
-    pub fn play_from_hand(&mut self, player: PlayerIdx, card: Card) {
-        self.trick.play(WarPlayKind::PlayHand, card);
+    pub fn play_from_hand(&mut self, player: PlayerIdx, card: Card) -> Result<(), GoatError> {
         let hand = &mut self.hands[player.idx()];
+        hand.check_has_card(card)?;
         *hand -= card;
+        self.trick.play(WarPlayKind::PlayHand, card);
+        Ok(())
     }
 
     pub fn play_from_top(&mut self, card: Card) {
         self.trick.play(WarPlayKind::PlayTop, card);
     }
 
-    pub fn slough(&mut self, player: PlayerIdx, card: Card) {
+    pub fn slough(&mut self, player: PlayerIdx, card: Card) -> Result<(), GoatError> {
         let hand = &mut self.hands[player.idx()];
-        self.trick.slough(player, card);
+        hand.check_has_card(card)?;
         *hand -= card;
+        self.trick.slough(player, card);
+        Ok(())
     }
 
     pub fn finish_trick(&mut self, player: PlayerIdx) -> Result<bool, GoatError> {
@@ -77,7 +86,7 @@ impl<D: Deck, Hand: WarHand, Trick: PreviousTrick> WarPhase<D, Hand, Trick> {
         let next = self
             .trick
             .plays()
-            .get(0)
+            .first()
             .map(|play| play.player())
             .unwrap_or_else(|| self.trick.next_player().unwrap());
         RummyPhase::new(hands, next, trump)
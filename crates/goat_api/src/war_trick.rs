@@ -2,32 +2,19 @@ use std::cmp::Ordering;
 use std::fmt::Debug;
 use std::{fmt, mem};
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
+use ts_rs::TS;
 
 use crate::{
     Card, Cards, GoatError, PlayerIdx, Rank, ServerWarHand, WarHand, WarPlay, WarPlayKind,
+    MAX_PLAYERS,
 };
 
-static PLAYERS: &[PlayerIdx] = &[
-    PlayerIdx(0),
-    PlayerIdx(1),
-    PlayerIdx(2),
-    PlayerIdx(3),
-    PlayerIdx(4),
-    PlayerIdx(5),
-    PlayerIdx(6),
-    PlayerIdx(7),
-    PlayerIdx(8),
-    PlayerIdx(9),
-    PlayerIdx(10),
-    PlayerIdx(11),
-    PlayerIdx(12),
-    PlayerIdx(13),
-    PlayerIdx(14),
-    PlayerIdx(15),
-];
-
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(into = "WarTrickRepr", try_from = "WarTrickRepr")]
+#[ts(as = "WarTrickRepr")]
 pub struct WarTrick {
     /// The index of the next player in players that needs to play a card.
     next: u8,
@@ -50,25 +37,24 @@ pub struct WarTrick {
 
     /// A bit mask with set bits for each player who has not acknowledged the
     /// trick as complete.
-    end_mask: u16,
+    end_mask: u64,
 }
 
 impl WarTrick {
     pub fn new(leader: PlayerIdx, num_players: usize) -> Self {
-        let mut players = SmallVec::new();
-        players.extend_from_slice(&PLAYERS[leader.idx()..num_players]);
-        players.extend_from_slice(&PLAYERS[..leader.idx()]);
+        debug_assert!(num_players <= MAX_PLAYERS);
+        debug_assert!(num_players == 0 || leader.idx() < num_players);
+        let players = (leader.0..num_players as u8)
+            .chain(0..leader.0)
+            .map(PlayerIdx)
+            .collect();
         Self {
             next: 0,
             rank: Rank::Two,
             players,
             winners: SmallVec::new(),
             plays: SmallVec::new(),
-            end_mask: if num_players == 16 {
-                0xffff
-            } else {
-                (1 << num_players) - 1
-            },
+            end_mask: u64::MAX.checked_shr(64 - num_players as u32).unwrap_or(0),
         }
     }
 
@@ -188,7 +174,7 @@ impl WarTrick {
             .push(WarPlay::new(player, WarPlayKind::Slough, card));
     }
 
-    pub fn end_mask(&self) -> u16 {
+    pub fn end_mask(&self) -> u64 {
         self.end_mask
     }
 
@@ -210,6 +196,73 @@ impl WarTrick {
     }
 }
 
+/// How a [`WarTrick`] is serialized. The players who have yet to end the trick are listed rather
+/// than given as a bit mask, which JavaScript can't hold as a number.
+#[derive(Deserialize, JsonSchema, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "WarTrick")]
+#[ts(rename = "WarTrick")]
+struct WarTrickRepr {
+    next: u8,
+    rank: Rank,
+    #[ts(as = "Vec<PlayerIdx>")]
+    players: SmallVec<[PlayerIdx; 16]>,
+    #[ts(as = "Vec<PlayerIdx>")]
+    winners: SmallVec<[PlayerIdx; 16]>,
+    #[ts(as = "Vec<WarPlay>")]
+    plays: SmallVec<[WarPlay; 12]>,
+    not_ended: Vec<PlayerIdx>,
+}
+
+impl From<WarTrick> for WarTrickRepr {
+    fn from(trick: WarTrick) -> Self {
+        let not_ended = (0..MAX_PLAYERS as u8)
+            .map(PlayerIdx)
+            .filter(|&player| !trick.ended(player))
+            .collect();
+        Self {
+            next: trick.next,
+            rank: trick.rank,
+            players: trick.players,
+            winners: trick.winners,
+            plays: trick.plays,
+            not_ended,
+        }
+    }
+}
+
+impl TryFrom<WarTrickRepr> for WarTrick {
+    type Error = GoatError;
+
+    fn try_from(repr: WarTrickRepr) -> Result<Self, Self::Error> {
+        let mut players = repr
+            .players
+            .iter()
+            .chain(&repr.winners)
+            .chain(&repr.not_ended)
+            .copied()
+            .chain(repr.plays.iter().map(|play| play.player()));
+        if repr.next as usize >= repr.players.len()
+            || players.any(|player| player.idx() >= MAX_PLAYERS)
+        {
+            return Err(GoatError::InvalidState {
+                what: "war trick".into(),
+            });
+        }
+        Ok(Self {
+            next: repr.next,
+            rank: repr.rank,
+            players: repr.players,
+            winners: repr.winners,
+            plays: repr.plays,
+            end_mask: repr
+                .not_ended
+                .iter()
+                .fold(0, |mask, player| mask | (1 << player.0)),
+        })
+    }
+}
+
 impl Debug for WarTrick {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut f = f.debug_struct("");
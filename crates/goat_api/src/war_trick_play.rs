@@ -1,15 +1,19 @@
-use std::mem;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
 use crate::{Card, PlayerIdx};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
 pub struct WarPlay {
-    player_and_kind: u8,
+    player: PlayerIdx,
+    kind: WarPlayKind,
     pub card: Card,
 }
 
 #[repr(u8)]
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
 pub enum WarPlayKind {
     PlayHand = 0,
     PlayTop,
@@ -18,17 +22,14 @@ pub enum WarPlayKind {
 
 impl WarPlay {
     pub fn new(player: PlayerIdx, kind: WarPlayKind, card: Card) -> Self {
-        Self {
-            player_and_kind: player.0 | ((kind as u8) << 4),
-            card,
-        }
+        Self { player, kind, card }
     }
 
     pub fn player(self) -> PlayerIdx {
-        PlayerIdx(self.player_and_kind & 0xf)
+        self.player
     }
 
     pub fn kind(self) -> WarPlayKind {
-        unsafe { mem::transmute(self.player_and_kind >> 4) }
+        self.kind
     }
 }
@@ -0,0 +1,41 @@
+//! Generates self-play training data.
+//!
+//! Usage: `self_play <games> <output.jsonl> [model]`
+//!
+//! Every game seats three to six bots picked at random from the built-in strategies, plus the
+//! linear policy in `model` when one is given, and appends one JSON line per decision to the
+//! output file.
+
+use std::fs::File;
+use std::io::BufWriter;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+use goat_bot::{
+    self_play, write_samples, AdaptSimple, CoverSimple, DuckSimple, LinearPolicy, PlayTopSimple,
+    Policy,
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let games: usize = args.next().ok_or("missing game count")?.parse()?;
+    let mut out = BufWriter::new(File::create(args.next().ok_or("missing output path")?)?);
+    let model = args.next().map(LinearPolicy::load).transpose()?;
+
+    let mut pool: Vec<&dyn Policy> = vec![&AdaptSimple, &CoverSimple, &DuckSimple, &PlayTopSimple];
+    if let Some(model) = &model {
+        pool.push(model);
+    }
+    let mut rng = rand::thread_rng();
+    for _ in 0..games {
+        let num_players = rng.gen_range(3..=6);
+        let policies: Vec<_> = (0..num_players)
+            .map(|_| *pool.choose(&mut rng).unwrap())
+            .collect();
+        let num_decks = rng.gen_range(1..=2);
+        let samples = self_play(rng.next_u64(), num_decks, &policies)?;
+        write_samples(&mut out, &samples)?;
+    }
+    Ok(())
+}
@@ -1,4 +1,3 @@
-use async_trait::async_trait;
 use goat_api::{
     Action, Cards, ClientDeck, ClientRummyHand, ClientWarHand, PlayerIdx, RummyPhase, WarPhase,
 };
@@ -7,7 +6,6 @@ use crate::{strategy, Strategy};
 
 pub struct AdaptSimple;
 
-#[async_trait]
 impl Strategy for AdaptSimple {
     fn war(&self, idx: PlayerIdx, war: &WarPhase<ClientDeck, ClientWarHand, ()>) -> Option<Action> {
         if war.hands.len() < 4 {
@@ -17,7 +15,7 @@ impl Strategy for AdaptSimple {
         }
     }
 
-    async fn rummy(&self, rummy: &RummyPhase<ClientRummyHand, Cards>) -> Action {
+    fn rummy(&self, rummy: &RummyPhase<ClientRummyHand, Cards>) -> Action {
         strategy::rummy_simple(rummy)
     }
 }
@@ -1,4 +1,3 @@
-use async_trait::async_trait;
 use goat_api::{
     Action, Cards, ClientDeck, ClientRummyHand, ClientWarHand, PlayerIdx, RummyPhase, WarPhase,
 };
@@ -7,7 +6,10 @@ use crate::{strategy, Strategy};
 
 pub struct AdaptSimulate;
 
-#[async_trait]
+/// [`AdaptSimulate`] with a fixed number of simulations per rummy decision rather than a time
+/// limit.
+pub struct AdaptSimulateN(pub u64);
+
 impl Strategy for AdaptSimulate {
     fn war(&self, idx: PlayerIdx, war: &WarPhase<ClientDeck, ClientWarHand, ()>) -> Option<Action> {
         if war.hands.len() < 4 {
@@ -17,7 +19,17 @@ impl Strategy for AdaptSimulate {
         }
     }
 
-    async fn rummy(&self, rummy: &RummyPhase<ClientRummyHand, Cards>) -> Action {
-        strategy::rummy_simulate(rummy).await
+    fn rummy(&self, rummy: &RummyPhase<ClientRummyHand, Cards>) -> Action {
+        strategy::rummy_simulate(rummy)
+    }
+}
+
+impl Strategy for AdaptSimulateN {
+    fn war(&self, idx: PlayerIdx, war: &WarPhase<ClientDeck, ClientWarHand, ()>) -> Option<Action> {
+        AdaptSimulate.war(idx, war)
+    }
+
+    fn rummy(&self, rummy: &RummyPhase<ClientRummyHand, Cards>) -> Action {
+        strategy::rummy_simulate_n(rummy, self.0)
     }
 }
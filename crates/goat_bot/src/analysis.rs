@@ -0,0 +1,252 @@
+use std::fmt::{self, Display, Formatter};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use goat_api::{
+    Action, Cards, ClientPhase, ClientRummyHand, Event, GoatError, PlayerIdx, Position, RummyHand,
+    RummyPosition, Run, UserId,
+};
+
+use crate::{rollout, rummy_actions, Solver};
+
+type ClientGame = goat_api::ClientGame<(), Cards>;
+type RummyPhase = goat_api::RummyPhase<ClientRummyHand, Cards>;
+
+/// How much more often a move must make its player goat than the best alternative to count as a
+/// blunder.
+pub const BLUNDER_MARGIN: f64 = 0.25;
+
+/// The most cards left in hands for the analysis to try solving a position exactly.
+const MAX_SOLVED_CARDS: usize = 12;
+
+/// How many positions the analysis searches per decision before falling back to rollouts.
+const ANALYSIS_SOLVER_BUDGET: usize = 20_000;
+
+/// One rummy decision from a finished game, judged in hindsight with every hand known.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Decision {
+    /// The index of the decision's event in the game's events.
+    pub event: usize,
+    pub seat: PlayerIdx,
+    pub action: Action,
+    pub best: Action,
+    /// The chance of each seat ending up goat after the action taken.
+    pub goat_chances: Vec<f64>,
+    /// How much more often the action taken makes the seat goat than the best action does.
+    pub loss: f64,
+    /// Whether the chances were solved exactly rather than estimated from rollouts.
+    pub exact: bool,
+    pub blunder: bool,
+}
+
+/// How one player did over a finished game.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerReport {
+    pub seat: PlayerIdx,
+    pub user_id: UserId,
+    pub goat: bool,
+    pub decisions: Vec<Decision>,
+}
+
+impl PlayerReport {
+    pub fn blunders(&self) -> impl Iterator<Item = &Decision> {
+        self.decisions.iter().filter(|decision| decision.blunder)
+    }
+}
+
+/// Walks the events of a finished game and judges every rummy decision against the alternatives,
+/// playing `rollouts` random games per legal action seeded from `seed`, or solving the position
+/// exactly when few enough cards are left. Returns a report for each seat.
+///
+/// The decisions are judged in hindsight, with every hand known rather than only what their player
+/// could see, so a reasonable guess that turned out badly still counts as a blunder. Decisions in
+/// the war phase aren't judged.
+pub fn analyze(events: &[Event], rollouts: u64, seed: u64) -> Result<Vec<PlayerReport>, GoatError> {
+    analyze_from(ClientGame::default(), events, rollouts, seed)
+}
+
+/// Like [`analyze`], for the `events` of a game between `players` set up at a rummy `position`.
+pub fn analyze_position(
+    position: &RummyPosition,
+    players: Vec<UserId>,
+    events: &[Event],
+    rollouts: u64,
+    seed: u64,
+) -> Result<Vec<PlayerReport>, GoatError> {
+    let mut game: ClientGame = Position::Rummy(position.clone()).client_game(players, None)?;
+    if let ClientPhase::Rummy(rummy) = &mut game.phase {
+        for (hand, cards) in rummy.hands.iter_mut().zip(&position.hands) {
+            *hand = ClientRummyHand {
+                known: *cards,
+                unknown: 0,
+            };
+        }
+    }
+    analyze_from(game, events, rollouts, seed)
+}
+
+fn analyze_from(
+    mut game: ClientGame,
+    events: &[Event],
+    rollouts: u64,
+    seed: u64,
+) -> Result<Vec<PlayerReport>, GoatError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut decisions = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        if let ClientPhase::Rummy(rummy) = &game.phase {
+            let action = match event {
+                Event::PlayRun { lo, hi } => Some(Action::PlayRun { lo: *lo, hi: *hi }),
+                Event::PickUp => Some(Action::PickUp),
+                _ => None,
+            };
+            if let Some(action) = action {
+                if rummy.hands.iter().any(|hand| hand.unknown != 0) {
+                    return Err(GoatError::InvalidState {
+                        what: "redacted game".into(),
+                    });
+                }
+                decisions.push(judge(rummy, index, action, rollouts, &mut rng)?);
+            }
+        }
+        game.apply(event.clone())?;
+    }
+    let ClientPhase::Goat(goat) = &game.phase else {
+        return Err(GoatError::GameNotFinished);
+    };
+    Ok(game
+        .players
+        .iter()
+        .enumerate()
+        .map(|(seat, user_id)| {
+            let seat = PlayerIdx(seat as u8);
+            PlayerReport {
+                seat,
+                user_id: *user_id,
+                goat: goat.goat == seat,
+                decisions: decisions
+                    .iter()
+                    .filter(|decision| decision.seat == seat)
+                    .cloned()
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+/// Judges `action`, taken as the `event`th event of a game, against the other actions its player
+/// could have taken in `rummy`.
+fn judge(
+    rummy: &RummyPhase,
+    event: usize,
+    action: Action,
+    rollouts: u64,
+    rng: &mut StdRng,
+) -> Result<Decision, GoatError> {
+    let seat = rummy.next;
+    let actions = rummy_actions(seat, rummy);
+    let Some(taken) = actions.iter().position(|a| *a == action) else {
+        return Err(GoatError::InvalidState {
+            what: format!("{:?} missing from legal actions", action).into(),
+        });
+    };
+    let cards: usize = rummy.hands.iter().map(|hand| hand.len()).sum();
+    let mut solver = Solver::new(ANALYSIS_SOLVER_BUDGET);
+    let solved = (cards <= MAX_SOLVED_CARDS)
+        .then(|| {
+            actions
+                .iter()
+                .map(|action| solver.goat_after(rummy, *action))
+                .collect::<Option<Vec<_>>>()
+        })
+        .flatten();
+    let chances: Vec<Vec<f64>> = match &solved {
+        Some(goats) => goats
+            .iter()
+            .map(|goat| {
+                let mut chances = vec![0.0; rummy.hands.len()];
+                chances[goat.idx()] = 1.0;
+                chances
+            })
+            .collect(),
+        None => actions
+            .iter()
+            .map(|action| {
+                let mut chances = vec![0.0; rummy.hands.len()];
+                for _ in 0..rollouts.max(1) {
+                    let goat = rollout(rng, rummy.clone(), *action);
+                    chances[goat.idx()] += 1.0 / rollouts.max(1) as f64;
+                }
+                chances
+            })
+            .collect(),
+    };
+    let (best, best_chances) = actions
+        .iter()
+        .zip(&chances)
+        .min_by(|(_, a), (_, b)| a[seat.idx()].total_cmp(&b[seat.idx()]))
+        .unwrap();
+    let loss = chances[taken][seat.idx()] - best_chances[seat.idx()];
+    Ok(Decision {
+        event,
+        seat,
+        action,
+        best: *best,
+        goat_chances: chances[taken].clone(),
+        loss,
+        exact: solved.is_some(),
+        blunder: loss >= BLUNDER_MARGIN,
+    })
+}
+
+fn describe(action: Action) -> String {
+    match action {
+        Action::PlayRun { lo, hi } => Run(lo, hi).to_string(),
+        Action::PickUp => "pick up".into(),
+        action => format!("{:?}", action),
+    }
+}
+
+impl Display for PlayerReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "P{} {}: {} decisions, {} blunders{}",
+            self.seat.0,
+            self.user_id,
+            self.decisions.len(),
+            self.blunders().count(),
+            if self.goat { ", goat" } else { "" },
+        )?;
+        for decision in &self.decisions {
+            write!(
+                f,
+                "  event {}: {} goat {:.0}%",
+                decision.event,
+                describe(decision.action),
+                100.0 * decision.goat_chances[self.seat.idx()],
+            )?;
+            if decision.action != decision.best {
+                let best = decision.goat_chances[self.seat.idx()] - decision.loss;
+                write!(
+                    f,
+                    " (best {} goat {:.0}%)",
+                    describe(decision.best),
+                    100.0 * best,
+                )?;
+            }
+            if decision.exact {
+                write!(f, " exact")?;
+            }
+            if decision.blunder {
+                write!(f, " BLUNDER")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
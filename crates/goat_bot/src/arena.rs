@@ -0,0 +1,145 @@
+use goat_api::{
+    Action, Cards, GameId, GoatError, PlayerIdx, Position, RandId, Response, ServerGame,
+    ServerPhase, UserId,
+};
+
+use crate::Policy;
+
+type ClientGame = goat_api::ClientGame<(), Cards>;
+
+/// Plays games between bots in process, without a server. Every seat gets its own view of the
+/// game built from the events redacted for that seat, exactly as a bot connected to a server
+/// would see it.
+pub struct Arena {
+    pub game: ServerGame,
+    pub views: Box<[ClientGame]>,
+    users: Box<[UserId]>,
+    applied: usize,
+    /// How many of the game's events each seat's policy has observed.
+    observed: Box<[usize]>,
+    turn: usize,
+}
+
+impl Arena {
+    pub fn new(seed: u64, num_players: usize, num_decks: u8) -> Result<Self, GoatError> {
+        let users = users(num_players);
+        let mut arena = Self {
+            game: ServerGame::with_seed(seed),
+            views: vec![ClientGame::default(); num_players].into_boxed_slice(),
+            users,
+            applied: 0,
+            observed: vec![0; num_players].into_boxed_slice(),
+            turn: 0,
+        };
+        for idx in 0..num_players {
+            let user_id = arena.users[idx];
+            arena.apply(PlayerIdx(idx as u8), Action::Join { user_id })?;
+        }
+        arena.apply(PlayerIdx(0), Action::Start { num_decks })?;
+        Ok(arena)
+    }
+
+    /// Plays on from `position`, for puzzles and for replaying positions bots got wrong. Dreck is
+    /// given out with `seed` if the position is in the war phase.
+    pub fn from_position(position: &Position, seed: u64) -> Result<Self, GoatError> {
+        let users = users(position.hands().len());
+        let views = (0..users.len())
+            .map(|idx| position.client_game(users.to_vec(), Some(PlayerIdx(idx as u8))))
+            .collect::<Result<_, _>>()?;
+        let game = position.server_game(users.to_vec(), seed)?;
+        Ok(Self {
+            applied: game.events.len(),
+            observed: vec![game.events.len(); users.len()].into_boxed_slice(),
+            game,
+            views,
+            users,
+            turn: 0,
+        })
+    }
+
+    /// The id policies are told the arena's game has, since it isn't on any server.
+    pub fn game_id(&self) -> GameId {
+        GameId(RandId::from_hash(&[0xff; 16]))
+    }
+
+    pub fn user(&self, idx: PlayerIdx) -> UserId {
+        self.users[idx.idx()]
+    }
+
+    pub fn goat(&self) -> Option<PlayerIdx> {
+        match &self.game.phase {
+            ServerPhase::Goat(goat) => Some(goat.goat),
+            _ => None,
+        }
+    }
+
+    /// Applies `action` on behalf of the player in seat `idx` and forwards the resulting events to
+    /// every seat's view.
+    pub fn apply(&mut self, idx: PlayerIdx, action: Action) -> Result<(), GoatError> {
+        self.game.apply(self.users[idx.idx()], action)?;
+        for event in &self.game.events[self.applied..] {
+            for (seat, view) in self.views.iter_mut().enumerate() {
+                view.apply(event.redact(Some(PlayerIdx(seat as u8))))?;
+            }
+        }
+        self.applied = self.game.events.len();
+        Ok(())
+    }
+
+    /// Asks each seat in turn for an action and applies the first one offered. Seats are polled
+    /// starting one seat further along every step, so that no seat always gets to act first in the
+    /// war phase. Each seat's policy observes the events it hasn't seen yet before it's asked.
+    /// Returns the seat that acted and its action, or `None` if nobody wanted to act.
+    pub fn step(
+        &mut self,
+        policies: &[&dyn Policy],
+        mut observe: impl FnMut(&ClientGame, PlayerIdx, Action),
+    ) -> Result<Option<(PlayerIdx, Action)>, GoatError> {
+        let num_players = self.views.len();
+        self.turn += 1;
+        for i in 0..num_players {
+            let idx = PlayerIdx(((self.turn + i) % num_players) as u8);
+            let policy = policies[idx.idx()];
+            let game_id = self.game_id();
+            for event in &self.game.events[self.observed[idx.idx()]..] {
+                let event = event.redact(Some(idx));
+                policy.observe(&Response::Game { game_id, event });
+            }
+            self.observed[idx.idx()] = self.game.events.len();
+            let view = &self.views[idx.idx()];
+            if let Some(action) = policy.game_action(game_id, view, idx) {
+                observe(view, idx, action);
+                self.apply(idx, action)?;
+                return Ok(Some((idx, action)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Plays the game to completion and returns the goat.
+    pub fn play(
+        &mut self,
+        policies: &[&dyn Policy],
+        mut observe: impl FnMut(&ClientGame, PlayerIdx, Action),
+    ) -> Result<PlayerIdx, GoatError> {
+        loop {
+            if let Some(goat) = self.goat() {
+                return Ok(goat);
+            }
+            if self.step(policies, &mut observe)?.is_none() {
+                return Err(GoatError::InvalidAction);
+            }
+        }
+    }
+}
+
+/// The users who sit at an arena of `num_players`.
+pub(crate) fn users(num_players: usize) -> Box<[UserId]> {
+    (0..num_players)
+        .map(|idx| {
+            let mut hash = [0; 16];
+            hash[0] = idx as u8;
+            UserId(RandId::from_hash(&hash))
+        })
+        .collect()
+}
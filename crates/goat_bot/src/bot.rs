@@ -1,43 +1,79 @@
 use std::collections::HashSet;
+use std::sync::Arc;
 
 use rand::Rng;
 use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::time::{Duration, Instant};
 
 use goat_api::{
-    Action, Cards, Client, ClientPhase, GameId, GoatError, PlayerIdx, Response, UserId,
+    Action, Cards, Client, ClientPhase, Event, GameId, GoatError, PlayerIdx, Response, UserId,
+    MAX_PLAYERS,
 };
 
-use crate::Strategy;
+use crate::Policy;
 
-pub struct Bot<Tx, S> {
+/// How a bot manages its seat outside of play.
+#[derive(Clone, Debug)]
+pub struct Lifecycle {
+    /// Join unstarted games the bot has been invited to.
+    pub auto_join: bool,
+
+    /// Leave unstarted games when the bot is seated beyond the most players a game can start
+    /// with.
+    pub auto_leave: bool,
+
+    /// Start unstarted games once at least this many players are seated, with this many decks.
+    pub auto_start: Option<(usize, u8)>,
+
+    /// The number of goat noises to pick from when the bot is the goat. No noise is made if this
+    /// is zero.
+    pub noises: usize,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self {
+            auto_join: true,
+            auto_leave: true,
+            auto_start: None,
+            noises: 0,
+        }
+    }
+}
+
+pub struct Bot<Tx, P> {
     client: Client<(), (), Cards>,
     user_id: UserId,
     rx: UnboundedReceiver<Response>,
     tx: Tx,
-    strategy: S,
+    policy: Arc<P>,
     sleep: fn(Action) -> Duration,
+    lifecycle: Lifecycle,
+    invites: HashSet<GameId>,
 }
 
 impl<
         Tx: Fn(UserId, GameId, Action) -> Result<(), GoatError> + Clone + Send + Sync + 'static,
-        S: Strategy,
-    > Bot<Tx, S>
+        P: Policy,
+    > Bot<Tx, P>
 {
     pub fn new(
         user_id: UserId,
         rx: UnboundedReceiver<Response>,
         tx: Tx,
-        strategy: S,
+        policy: P,
         sleep: fn(Action) -> Duration,
+        lifecycle: Lifecycle,
     ) -> Self {
         Self {
             client: Client::new(()),
             user_id,
             rx,
             tx,
-            strategy,
+            policy: Arc::new(policy),
             sleep,
+            lifecycle,
+            invites: HashSet::new(),
         }
     }
 
@@ -46,18 +82,14 @@ impl<
         loop {
             match self.rx.recv().await {
                 Some(response) => {
-                    changed_game(&response).map(|id| changed.insert(id));
                     log::debug!("recv {}: {:?}", self.user_id, response);
-                    self.client.apply(response)?;
-                    log::debug!("state {}: {:?}", self.user_id, self.client.games);
+                    self.receive(response, &mut changed)?;
                 }
                 None => return Ok(()),
             };
             while let Ok(response) = self.rx.try_recv() {
-                changed_game(&response).map(|id| changed.insert(id));
                 log::debug!("try_recv {}: {:?}", self.user_id, response);
-                self.client.apply(response)?;
-                log::debug!("state {}: {:?}", self.user_id, self.client.games);
+                self.receive(response, &mut changed)?;
             }
             for game_id in changed.drain() {
                 let start = Instant::now();
@@ -79,24 +111,99 @@ impl<
         }
     }
 
+    fn receive(
+        &mut self,
+        response: Response,
+        changed: &mut HashSet<GameId>,
+    ) -> Result<(), GoatError> {
+        match &response {
+            Response::Replay { game_id, events } => {
+                changed.insert(*game_id);
+                for event in events {
+                    self.track_invite(*game_id, event);
+                }
+            }
+            Response::Game { game_id, event } => {
+                changed.insert(*game_id);
+                self.track_invite(*game_id, event);
+            }
+            Response::ForgetGame { game_id } => {
+                self.invites.remove(game_id);
+            }
+            _ => {}
+        }
+        self.policy.observe(&response);
+        self.client.apply(response)?;
+        log::debug!("state {}: {:?}", self.user_id, self.client.games);
+        Ok(())
+    }
+
+    /// Remembers invites until the bot joins, so that it doesn't rejoin a game it has left.
+    fn track_invite(&mut self, game_id: GameId, event: &Event) {
+        match event {
+            Event::Invite { user_id } if *user_id == self.user_id => {
+                self.invites.insert(game_id);
+            }
+            Event::Join { user_id } if *user_id == self.user_id => {
+                self.invites.remove(&game_id);
+            }
+            _ => {}
+        }
+    }
+
     async fn action(&self, game_id: GameId) -> Option<Action> {
         let game = self.client.games.get(&game_id)?;
-        let idx = game.players.iter().position(|id| *id == self.user_id)?;
-        let idx = PlayerIdx(idx as u8);
-        match &game.phase {
-            ClientPhase::Unstarted => None,
-            ClientPhase::War(war) => self.strategy.war(idx, war),
-            ClientPhase::Rummy(rummy) => {
-                if rummy.next == idx {
-                    Some(self.strategy.rummy(rummy).await)
+        let idx = game.players.iter().position(|id| *id == self.user_id);
+        let idx = match (idx, &game.phase) {
+            (None, ClientPhase::Unstarted) => {
+                return if self.lifecycle.auto_join && self.invites.contains(&game_id) {
+                    Some(Action::Join {
+                        user_id: self.user_id,
+                    })
                 } else {
                     None
+                };
+            }
+            (None, _) => return None,
+            (Some(idx), _) => PlayerIdx(idx as u8),
+        };
+        match &game.phase {
+            ClientPhase::Unstarted => {
+                let num_players = game.players.len();
+                if self.lifecycle.auto_leave && idx.idx() >= MAX_PLAYERS {
+                    Some(Action::Leave { player: idx })
+                } else {
+                    match self.lifecycle.auto_start {
+                        Some((min_players, num_decks))
+                            if (min_players..=MAX_PLAYERS).contains(&num_players) =>
+                        {
+                            Some(Action::Start { num_decks })
+                        }
+                        _ => None,
+                    }
+                }
+            }
+            ClientPhase::War(_) if !self.policy.slow_war() => {
+                self.policy.game_action(game_id, game, idx)
+            }
+            ClientPhase::War(_) | ClientPhase::Rummy(_) => {
+                // Policies may simulate for a while or wait on another process, so run them off
+                // the async worker threads.
+                let policy = self.policy.clone();
+                let game = game.clone();
+                match tokio::task::spawn_blocking(move || policy.game_action(game_id, &game, idx))
+                    .await
+                {
+                    Ok(action) => action,
+                    Err(e) => {
+                        log::error!("policy {} failed in {}: {}", self.user_id, game_id, e);
+                        None
+                    }
                 }
             }
             ClientPhase::Goat(goat) => {
-                if goat.goat == idx && goat.noise.is_none() {
-                    let total_noises = std::fs::read_dir("./assets/noises/").ok()?.count();
-                    let noise = rand::thread_rng().gen_range(0..total_noises);
+                if goat.goat == idx && goat.noise.is_none() && self.lifecycle.noises > 0 {
+                    let noise = rand::thread_rng().gen_range(0..self.lifecycle.noises);
                     Some(Action::Goat { noise })
                 } else {
                     None
@@ -105,10 +212,3 @@ impl<
         }
     }
 }
-
-fn changed_game(response: &Response) -> Option<GameId> {
-    match response {
-        Response::Replay { game_id, .. } | Response::Game { game_id, .. } => Some(*game_id),
-        _ => None,
-    }
-}
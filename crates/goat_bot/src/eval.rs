@@ -0,0 +1,113 @@
+use goat_api::{Card, Cards, ClientRummyHand, PlayerIdx, RummyHand, Suit};
+
+type RummyPhase = goat_api::RummyPhase<ClientRummyHand, Cards>;
+
+/// A heuristic summary of how good a rummy hand is, given what is known about the cards that have
+/// already been played.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HandEval {
+    /// The number of cards in the hand.
+    pub cards: usize,
+
+    /// The number of maximal runs in the hand. Every run needs a turn of its own to be played.
+    pub runs: usize,
+
+    /// The number of trumps in the hand.
+    pub trumps: usize,
+
+    /// The number of trumps in the hand that no unseen trump can beat.
+    pub master_trumps: usize,
+
+    /// The number of non-trump suits the hand is void in while opponents may still lead them.
+    pub voids: usize,
+
+    /// The number of opponents still holding cards.
+    pub opponents: usize,
+}
+
+impl HandEval {
+    /// Evaluates `hand` when `trump` is the revealed trump card, `opponents` other players still
+    /// hold cards, `played` are the cards known to have left play (see `RummyHistory for Cards`)
+    /// and the game is played with `num_decks` decks.
+    pub fn new(
+        hand: Cards,
+        trump: Card,
+        opponents: usize,
+        played: Cards,
+        num_decks: usize,
+    ) -> Self {
+        let unseen = unseen(hand, trump, played, num_decks);
+        let trump_suit = trump.suit();
+        let trumps = hand.in_suit(trump_suit);
+        let unseen_trumps = unseen.in_suit(trump_suit);
+        let master_trumps = if unseen_trumps.is_empty() {
+            trumps.len()
+        } else {
+            trumps.above(unseen_trumps.max()).len()
+        };
+        let voids = Suit::VALUES
+            .iter()
+            .filter(|&&suit| {
+                suit != trump_suit
+                    && hand.in_suit(suit).is_empty()
+                    && !unseen.in_suit(suit).is_empty()
+            })
+            .count();
+        Self {
+            cards: hand.len(),
+            runs: hand.runs().count(),
+            trumps: trumps.len(),
+            master_trumps,
+            voids,
+            opponents,
+        }
+    }
+
+    /// Evaluates the hand of the player in seat `idx` as seen from that player's view of the
+    /// rummy phase.
+    pub fn of_player(rummy: &RummyPhase, idx: PlayerIdx) -> Self {
+        Self::of_hand(rummy, rummy.hands[idx.idx()].known, rummy.history)
+    }
+
+    /// Evaluates `hand` as if it were held by a player in `rummy` after `played` had left play,
+    /// for instance to compare the hands left over by different plays.
+    pub fn of_hand(rummy: &RummyPhase, hand: Cards, played: Cards) -> Self {
+        let opponents = rummy
+            .hands
+            .iter()
+            .filter(|h| !h.is_empty())
+            .count()
+            .saturating_sub(1);
+        Self::new(hand, rummy.trump, opponents, played, num_decks(rummy))
+    }
+
+    /// The estimated number of turns needed to get rid of every card in the hand. Every run takes
+    /// one turn, and every void that cannot be covered with a trump is expected to cost a pick up.
+    pub fn tricks_needed(&self) -> usize {
+        self.runs + self.voids.saturating_sub(self.trumps)
+    }
+
+    /// A single score for the hand, higher is better. Master trumps can always be played, other
+    /// trumps are worth less the more opponents there are who might over-trump them.
+    pub fn score(&self) -> f64 {
+        if self.cards == 0 {
+            return f64::INFINITY;
+        }
+        let loose_trumps = (self.trumps - self.master_trumps) as f64;
+        self.master_trumps as f64 + loose_trumps / self.opponents.max(1) as f64
+            - self.tricks_needed() as f64
+    }
+}
+
+/// The cards that could still be in an opponent's hand, i.e. every card in `num_decks` decks that
+/// is not in `hand`, has not been `played` and is not the revealed `trump`.
+pub fn unseen(hand: Cards, trump: Card, played: Cards, num_decks: usize) -> Cards {
+    Cards::ONE_DECK * num_decks - hand - played - trump
+}
+
+/// The number of decks the game is being played with, derived from the number of cards still in
+/// play.
+pub fn num_decks(rummy: &RummyPhase) -> usize {
+    let count = 1 + rummy.history.len() + rummy.hands.iter().map(|h| h.len()).sum::<usize>();
+    count.div_ceil(52)
+}
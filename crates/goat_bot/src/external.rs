@@ -0,0 +1,201 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use goat_api::{Action, Cards, ClientGame, ClientPhase, GameId, PlayerIdx, Response};
+
+use crate::{legal_actions, Policy};
+
+/// How many lines can wait to be written to an external bot before it is taken to have stopped
+/// reading its stdin.
+const MAX_QUEUED_LINES: usize = 1024;
+
+/// What an external bot is asked, on its own line of its stdin between the responses it is sent.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum BotRequest {
+    /// Asks for the action the bot in seat `seat` wants to take next. The bot answers with an
+    /// [`Action`] on one line of its stdout, or `null` to wait for something to change.
+    #[serde(rename_all = "camelCase")]
+    Go {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        game_id: Option<GameId>,
+        seat: PlayerIdx,
+    },
+}
+
+struct Process {
+    child: Child,
+    /// Lines for the thread that writes to the bot's stdin, so that a bot that stops reading
+    /// can't block whoever is sending it messages.
+    stdin: SyncSender<Vec<u8>>,
+    lines: Receiver<String>,
+}
+
+/// A bot that runs as a separate process and speaks JSON, one message per line. Every
+/// [`Response`] the bot would get from `/subscribe` is written to its stdin, followed by a
+/// [`BotRequest`] whenever it might act, and it answers each request on its stdout. If the process
+/// crashes, answers with something that isn't an action, or takes longer than the timeout to
+/// answer, or falls too far behind reading its stdin, it is stopped and `fallback` plays in its
+/// place from then on. Actions that aren't legal are also left to `fallback`.
+///
+/// Asking the bot for an action blocks for up to the timeout, so async callers should do it off
+/// their worker threads, as [`Policy::slow_war`] tells them to.
+pub struct External<F> {
+    process: Mutex<Option<Process>>,
+    timeout: Duration,
+    fallback: F,
+}
+
+impl<F: Policy> External<F> {
+    /// Spawns `command` as an external bot, which gets `timeout` to answer each request.
+    pub fn spawn(command: &mut Command, timeout: Duration, fallback: F) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (writer, queued) = mpsc::sync_channel::<Vec<u8>>(MAX_QUEUED_LINES);
+        thread::spawn(move || {
+            for line in queued {
+                if let Err(e) = stdin.write_all(&line).and_then(|_| stdin.flush()) {
+                    log::warn!("External bot stopped reading: {}", e);
+                    break;
+                }
+            }
+        });
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            process: Mutex::new(Some(Process {
+                child,
+                stdin: writer,
+                lines,
+            })),
+            timeout,
+            fallback,
+        })
+    }
+
+    /// Whether the external bot is still playing, rather than its fallback.
+    pub fn is_running(&self) -> bool {
+        self.process.lock().unwrap().is_some()
+    }
+
+    /// Asks the external bot what to do, or `None` if it has stopped.
+    fn request(&self, game_id: Option<GameId>, seat: PlayerIdx) -> Option<Option<Action>> {
+        let mut process = self.process.lock().unwrap();
+        let request = BotRequest::Go { game_id, seat };
+        if !send(&mut process, &request) {
+            return None;
+        }
+        let line = process.as_ref()?.lines.recv_timeout(self.timeout);
+        match line.map(|line| serde_json::from_str::<Option<Action>>(&line)) {
+            Ok(Ok(action)) => Some(action),
+            Ok(Err(e)) => {
+                log::warn!("External bot answered with something else: {}", e);
+                stop(&mut process);
+                None
+            }
+            Err(e) => {
+                log::warn!("External bot didn't answer: {}", e);
+                stop(&mut process);
+                None
+            }
+        }
+    }
+}
+
+impl<F: Policy> Policy for External<F> {
+    fn action(&self, game: &ClientGame<(), Cards>, idx: PlayerIdx) -> Option<Action> {
+        match self.request(None, idx) {
+            Some(action) if is_legal(game, idx, action) => action,
+            _ => self.fallback.action(game, idx),
+        }
+    }
+
+    fn game_action(
+        &self,
+        game_id: GameId,
+        game: &ClientGame<(), Cards>,
+        idx: PlayerIdx,
+    ) -> Option<Action> {
+        match self.request(Some(game_id), idx) {
+            Some(action) if is_legal(game, idx, action) => action,
+            _ => self.fallback.game_action(game_id, game, idx),
+        }
+    }
+
+    fn observe(&self, response: &Response) {
+        send(&mut self.process.lock().unwrap(), response);
+        self.fallback.observe(response);
+    }
+
+    fn slow_war(&self) -> bool {
+        true
+    }
+}
+
+impl<F> Drop for External<F> {
+    fn drop(&mut self) {
+        stop(self.process.get_mut().unwrap());
+    }
+}
+
+fn is_legal(game: &ClientGame<(), Cards>, idx: PlayerIdx, action: Option<Action>) -> bool {
+    match (action, &game.phase) {
+        (Some(action), ClientPhase::War(_) | ClientPhase::Rummy(_)) => {
+            let legal = legal_actions(game, idx).contains(&action);
+            if !legal {
+                log::warn!("External bot tried {:?}, which isn't legal", action);
+            }
+            legal
+        }
+        _ => true,
+    }
+}
+
+/// Queues `message` to be written as a line to the external bot, stopping it if it can't be
+/// written to or has too many lines waiting already.
+fn send(process: &mut Option<Process>, message: &impl Serialize) -> bool {
+    let Some(running) = process else {
+        return false;
+    };
+    let mut line = serde_json::to_vec(message).unwrap();
+    line.push(b'\n');
+    match running.stdin.try_send(line) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            log::warn!("External bot fell behind reading its stdin");
+            stop(process);
+            false
+        }
+        Err(TrySendError::Disconnected(_)) => {
+            stop(process);
+            false
+        }
+    }
+}
+
+fn stop(process: &mut Option<Process>) {
+    if let Some(mut process) = process.take() {
+        let _ = process.child.kill();
+        let _ = process.child.wait();
+    }
+}
@@ -0,0 +1,172 @@
+use goat_api::{
+    Action, Cards, ClientDeck, ClientPhase, ClientRummyHand, ClientWarHand, Deck, PlayerIdx,
+    RummyHand, WarHand,
+};
+
+use crate::HandEval;
+
+type ClientGame = goat_api::ClientGame<(), Cards>;
+type WarPhase = goat_api::WarPhase<ClientDeck, ClientWarHand, ()>;
+type RummyPhase = goat_api::RummyPhase<ClientRummyHand, Cards>;
+
+/// The number of values in an observation of the game from one seat.
+pub const STATE_FEATURES: usize = 12;
+
+/// The number of values describing one candidate action.
+pub const ACTION_FEATURES: usize = 14;
+
+/// Summarizes the game as seen by the player in seat `idx`.
+///
+/// 0. 1 in the war phase
+/// 1. 1 in the rummy phase
+/// 2. number of players / 16
+/// 3. cards left in the deck / 52
+/// 4. cards in hand / 52
+/// 5. smallest opponent hand / 52
+/// 6. largest opponent hand / 52
+/// 7. plays in the current trick / number of players
+/// 8. runs in hand / 13
+/// 9. trumps in hand / 13
+/// 10. master trumps in hand / 13
+/// 11. hand score / 13
+pub fn observe(game: &ClientGame, idx: PlayerIdx) -> [f32; STATE_FEATURES] {
+    match &game.phase {
+        ClientPhase::War(war) => observe_war(idx, war),
+        ClientPhase::Rummy(rummy) => observe_rummy(idx, rummy),
+        _ => [0.0; STATE_FEATURES],
+    }
+}
+
+pub fn observe_war(idx: PlayerIdx, war: &WarPhase) -> [f32; STATE_FEATURES] {
+    let mut obs = [0.0; STATE_FEATURES];
+    obs[0] = 1.0;
+    obs[2] = war.hands.len() as f32 / 16.0;
+    obs[3] = war.deck.cards_remaining() as f32 / 52.0;
+    let sizes = war
+        .hands
+        .iter()
+        .zip(war.won.iter())
+        .map(|(hand, won)| hand.len() + won.len());
+    observe_hand_sizes(&mut obs, idx, sizes);
+    obs[7] = war.trick.plays().len() as f32 / war.hands.len() as f32;
+    obs
+}
+
+pub fn observe_rummy(idx: PlayerIdx, rummy: &RummyPhase) -> [f32; STATE_FEATURES] {
+    let mut obs = [0.0; STATE_FEATURES];
+    obs[1] = 1.0;
+    obs[2] = rummy.hands.len() as f32 / 16.0;
+    observe_hand_sizes(&mut obs, idx, rummy.hands.iter().map(|hand| hand.len()));
+    obs[7] = rummy.trick.len() as f32 / rummy.hands.len() as f32;
+    let eval = HandEval::of_player(rummy, idx);
+    obs[8] = eval.runs as f32 / 13.0;
+    obs[9] = eval.trumps as f32 / 13.0;
+    obs[10] = eval.master_trumps as f32 / 13.0;
+    obs[11] = eval.score().min(13.0) as f32 / 13.0;
+    obs
+}
+
+fn observe_hand_sizes(
+    obs: &mut [f32; STATE_FEATURES],
+    idx: PlayerIdx,
+    sizes: impl Iterator<Item = usize>,
+) {
+    let (mut min, mut max) = (usize::MAX, 0);
+    for (player, size) in sizes.enumerate() {
+        if player == idx.idx() {
+            obs[4] = size as f32 / 52.0;
+        } else if size != 0 {
+            min = min.min(size);
+            max = max.max(size);
+        }
+    }
+    obs[5] = if min == usize::MAX {
+        0.0
+    } else {
+        min as f32 / 52.0
+    };
+    obs[6] = max as f32 / 52.0;
+}
+
+/// Describes the effect of the player in seat `idx` taking `action`.
+///
+/// 0. always 1
+/// 1. 1 when drawing a card
+/// 2. rank / 12 when playing a card from hand
+/// 3. 1 when playing from the top of the deck
+/// 4. rank / 12 when sloughing a card
+/// 5. 1 when finishing a trick
+/// 6. 1 when picking up
+/// 7. 1 when playing a run
+/// 8. length of the run / 13
+/// 9. rank of the lowest card in the run / 12
+/// 10. 1 when the run is trump
+/// 11. hand score after the action / 13
+/// 12. runs in hand after the action / 13
+/// 13. master trumps in hand after the action / 13
+pub fn action_features(
+    game: &ClientGame,
+    idx: PlayerIdx,
+    action: Action,
+) -> [f32; ACTION_FEATURES] {
+    match &game.phase {
+        ClientPhase::War(_) => war_action_features(action),
+        ClientPhase::Rummy(rummy) => rummy_action_features(idx, rummy, action),
+        _ => [0.0; ACTION_FEATURES],
+    }
+}
+
+pub fn war_action_features(action: Action) -> [f32; ACTION_FEATURES] {
+    let mut features = [0.0; ACTION_FEATURES];
+    features[0] = 1.0;
+    match action {
+        Action::Draw => features[1] = 1.0,
+        Action::PlayCard { card } => features[2] = card.rank().idx() as f32 / 12.0,
+        Action::PlayTop => features[3] = 1.0,
+        Action::Slough { card } => features[4] = card.rank().idx() as f32 / 12.0,
+        Action::FinishTrick => features[5] = 1.0,
+        _ => {}
+    }
+    features
+}
+
+pub fn rummy_action_features(
+    idx: PlayerIdx,
+    rummy: &RummyPhase,
+    action: Action,
+) -> [f32; ACTION_FEATURES] {
+    let mut features = [0.0; ACTION_FEATURES];
+    features[0] = 1.0;
+    match action {
+        Action::PickUp => {
+            features[6] = 1.0;
+            let (lo, hi) = rummy.trick.clone().pick_up();
+            let range = Cards::range(lo, hi);
+            let hand = rummy.hands[idx.idx()].known + range;
+            observe_rummy_hand(&mut features, rummy, hand, rummy.history - range);
+        }
+        Action::PlayRun { lo, hi } => {
+            let run = Cards::range(lo, hi);
+            features[7] = 1.0;
+            features[8] = run.len() as f32 / 13.0;
+            features[9] = lo.rank().idx() as f32 / 12.0;
+            features[10] = (lo.suit() == rummy.trump.suit()) as u8 as f32;
+            let hand = rummy.hands[idx.idx()].known - run;
+            observe_rummy_hand(&mut features, rummy, hand, rummy.history + run);
+        }
+        _ => {}
+    }
+    features
+}
+
+fn observe_rummy_hand(
+    features: &mut [f32; ACTION_FEATURES],
+    rummy: &RummyPhase,
+    hand: Cards,
+    played: Cards,
+) {
+    let eval = HandEval::of_hand(rummy, hand, played);
+    features[11] = eval.score().min(13.0) as f32 / 13.0;
+    features[12] = eval.runs as f32 / 13.0;
+    features[13] = eval.master_trumps as f32 / 13.0;
+}
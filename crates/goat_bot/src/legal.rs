@@ -0,0 +1,71 @@
+use goat_api::{
+    Action, Cards, ClientDeck, ClientPhase, ClientRummyHand, ClientWarHand, Deck, PlayerIdx,
+    WarHand,
+};
+
+type ClientGame = goat_api::ClientGame<(), Cards>;
+type WarPhase = goat_api::WarPhase<ClientDeck, ClientWarHand, ()>;
+type RummyPhase = goat_api::RummyPhase<ClientRummyHand, Cards>;
+
+/// Every action the player in seat `idx` could legally take right now.
+pub fn legal_actions(game: &ClientGame, idx: PlayerIdx) -> Vec<Action> {
+    match &game.phase {
+        ClientPhase::War(war) => war_actions(idx, war),
+        ClientPhase::Rummy(rummy) => rummy_actions(idx, rummy),
+        _ => Vec::new(),
+    }
+}
+
+pub fn war_actions(idx: PlayerIdx, war: &WarPhase) -> Vec<Action> {
+    let hand = match &war.hands[idx.idx()] {
+        ClientWarHand::Visible(hand) => hand,
+        _ => panic!("bot hand is hidden"),
+    };
+    let mut actions = Vec::new();
+    let deck_empty = war.deck.cards_remaining() == 0;
+    if hand.len() < 3 && !deck_empty {
+        actions.push(Action::Draw);
+    }
+    for card in hand.cards() {
+        if war.trick.check_can_slough(idx, hand, card).is_ok() {
+            actions.push(Action::Slough { card });
+        }
+    }
+    if war.is_finished() || war.trick.winner().is_some() {
+        if !war.trick.ended(idx) {
+            actions.push(Action::FinishTrick);
+        }
+        return actions;
+    }
+    for card in hand.cards() {
+        if war.trick.check_can_play(idx, hand, card).is_ok() {
+            actions.push(Action::PlayCard { card });
+        }
+    }
+    if !deck_empty && war.trick.check_can_play_top(idx, hand).is_ok() {
+        actions.push(Action::PlayTop);
+    }
+    actions
+}
+
+pub fn rummy_actions(idx: PlayerIdx, rummy: &RummyPhase) -> Vec<Action> {
+    if rummy.next != idx {
+        return Vec::new();
+    }
+    let hand = rummy.hands[idx.idx()].known;
+    let trump = rummy.trump.suit();
+    let mut actions = Vec::new();
+    for lo in hand.cards() {
+        if !rummy.trick.can_play(lo, trump) || actions.contains(&Action::PlayRun { lo, hi: lo }) {
+            continue;
+        }
+        let top = hand.top_of_run(lo);
+        for hi in Cards::range(lo, top).cards() {
+            actions.push(Action::PlayRun { lo, hi });
+        }
+    }
+    if !rummy.trick.is_empty() {
+        actions.push(Action::PickUp);
+    }
+    actions
+}
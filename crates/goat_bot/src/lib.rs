@@ -1,15 +1,38 @@
 pub use adapt_simple::*;
 pub use adapt_simulate::*;
+pub use analysis::*;
+pub use arena::*;
+#[cfg(feature = "tokio")]
 pub use bot::*;
 pub use cover_simple::*;
 pub use duck_simple::*;
+pub use eval::*;
+pub use external::*;
+pub use features::*;
+pub use legal::*;
+pub use linear_policy::*;
 pub use play_top_simple::*;
+pub use self_play::*;
+pub use solver::*;
 pub use strategy::*;
 
 mod adapt_simple;
 mod adapt_simulate;
+mod analysis;
+mod arena;
+#[cfg(feature = "tokio")]
 mod bot;
 mod cover_simple;
 mod duck_simple;
+mod eval;
+mod external;
+mod features;
+mod legal;
+mod linear_policy;
 mod play_top_simple;
+mod self_play;
+mod solver;
 mod strategy;
+
+#[cfg(test)]
+mod test;
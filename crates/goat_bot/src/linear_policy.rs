@@ -0,0 +1,93 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::{fs, io};
+
+use goat_api::{
+    Action, Cards, ClientDeck, ClientRummyHand, ClientWarHand, PlayerIdx, RummyPhase, WarPhase,
+};
+
+use crate::{
+    rummy_action_features, rummy_actions, strategy, war_action_features, war_actions, Strategy,
+    ACTION_FEATURES,
+};
+
+/// A learned strategy that scores every legal action with a linear function of its features and
+/// takes the best one. The weights are trained offline on self-play samples and stored as plain
+/// text, one number per feature, with `#` starting a comment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearPolicy {
+    weights: [f32; ACTION_FEATURES],
+}
+
+impl LinearPolicy {
+    pub fn new(weights: [f32; ACTION_FEATURES]) -> Self {
+        Self { weights }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn weights(&self) -> &[f32; ACTION_FEATURES] {
+        &self.weights
+    }
+
+    fn score(&self, features: [f32; ACTION_FEATURES]) -> f32 {
+        self.weights.iter().zip(features).map(|(w, f)| w * f).sum()
+    }
+
+    fn best(
+        &self,
+        actions: Vec<Action>,
+        features: impl Fn(Action) -> [f32; ACTION_FEATURES],
+    ) -> Option<Action> {
+        actions
+            .into_iter()
+            .map(|action| (action, self.score(features(action))))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(action, _)| action)
+    }
+}
+
+impl FromStr for LinearPolicy {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut weights = [0.0; ACTION_FEATURES];
+        let mut count = 0;
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap();
+            for word in line.split_whitespace() {
+                let weight = word
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid weight {}", word)))?;
+                if count < ACTION_FEATURES {
+                    weights[count] = weight;
+                }
+                count += 1;
+            }
+        }
+        if count != ACTION_FEATURES {
+            return Err(invalid(format!(
+                "expected {} weights, found {}",
+                ACTION_FEATURES, count
+            )));
+        }
+        Ok(Self::new(weights))
+    }
+}
+
+impl Strategy for LinearPolicy {
+    fn war(&self, idx: PlayerIdx, war: &WarPhase<ClientDeck, ClientWarHand, ()>) -> Option<Action> {
+        self.best(war_actions(idx, war), war_action_features)
+    }
+
+    fn rummy(&self, rummy: &RummyPhase<ClientRummyHand, Cards>) -> Action {
+        let idx = rummy.next;
+        self.best(rummy_actions(idx, rummy), |action| {
+            rummy_action_features(idx, rummy, action)
+        })
+        .unwrap_or_else(|| strategy::rummy_simple(rummy))
+    }
+}
@@ -0,0 +1,70 @@
+use std::io;
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+
+use goat_api::{Action, GoatError, PlayerIdx};
+
+use crate::{
+    action_features, legal_actions, observe, Arena, Policy, ACTION_FEATURES, STATE_FEATURES,
+};
+
+/// A single decision made by a bot during self-play, along with how the game ended for it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sample {
+    pub seat: PlayerIdx,
+    pub observation: [f32; STATE_FEATURES],
+    pub legal: Vec<Action>,
+    pub features: Vec<[f32; ACTION_FEATURES]>,
+    pub chosen: usize,
+    pub goat: bool,
+}
+
+/// Plays one game between `policies`, one per seat, and records every decision they made.
+pub fn self_play(
+    seed: u64,
+    num_decks: u8,
+    policies: &[&dyn Policy],
+) -> Result<Vec<Sample>, GoatError> {
+    let mut arena = Arena::new(seed, policies.len(), num_decks)?;
+    let mut samples = Vec::new();
+    let mut missing = None;
+    let goat = arena.play(policies, |game, seat, action| {
+        let legal = legal_actions(game, seat);
+        // An action the rules allow but `legal_actions` doesn't list would make a wrong label.
+        let Some(chosen) = legal.iter().position(|a| *a == action) else {
+            missing.get_or_insert(action);
+            return;
+        };
+        samples.push(Sample {
+            seat,
+            observation: observe(game, seat),
+            features: legal
+                .iter()
+                .map(|a| action_features(game, seat, *a))
+                .collect(),
+            legal,
+            chosen,
+            goat: false,
+        });
+    })?;
+    if let Some(action) = missing {
+        return Err(GoatError::InvalidState {
+            what: format!("{:?} missing from legal actions", action).into(),
+        });
+    }
+    for sample in &mut samples {
+        sample.goat = sample.seat == goat;
+    }
+    Ok(samples)
+}
+
+/// Writes `samples` as JSON, one sample per line.
+pub fn write_samples<W: Write>(mut writer: W, samples: &[Sample]) -> io::Result<()> {
+    for sample in samples {
+        serde_json::to_writer(&mut writer, sample)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
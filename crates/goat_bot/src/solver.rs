@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use goat_api::{Action, Card, Cards, ClientRummyHand, PlayerIdx};
+
+use crate::{rummy_actions, unseen_cards};
+
+type RummyPhase = goat_api::RummyPhase<ClientRummyHand, Cards>;
+
+/// How many positions a [`Solver`] searches before giving up by default.
+pub const SOLVER_BUDGET: usize = 200_000;
+
+/// How many moves ahead a [`Solver`] looks before giving up, which keeps long chains of pick-ups
+/// from overflowing the stack.
+const MAX_DEPTH: usize = 512;
+
+/// The most hidden cards [`rummy_solve`] will deal out every way before leaving the decision to
+/// simulation.
+const MAX_HIDDEN: usize = 6;
+
+#[derive(Eq, Hash, PartialEq)]
+struct Key {
+    hands: Box<[Cards]>,
+    trick: Vec<(Card, Card)>,
+    trick_players: usize,
+    next: PlayerIdx,
+    pick_ups: Vec<u8>,
+}
+
+impl Key {
+    fn new(rummy: &RummyPhase) -> Self {
+        Key {
+            hands: rummy.hands.iter().map(|hand| hand.known).collect(),
+            trick: rummy.trick.plays().to_vec(),
+            trick_players: rummy.trick.num_players(),
+            next: rummy.next,
+            pick_ups: rummy.pick_ups.to_vec(),
+        }
+    }
+}
+
+/// Searches rummy positions where every hand is known, finding who becomes goat if everybody
+/// plays perfectly. Every player only cares about not being the goat, so a player takes the
+/// first move that saves them, and the search stops looking at their other moves.
+pub struct Solver {
+    memo: HashMap<Key, PlayerIdx>,
+    budget: usize,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new(SOLVER_BUDGET)
+    }
+}
+
+impl Solver {
+    /// A solver that gives up after searching `budget` positions.
+    pub fn new(budget: usize) -> Self {
+        Solver {
+            memo: HashMap::new(),
+            budget,
+        }
+    }
+
+    /// Who becomes goat from `rummy` under perfect play, or `None` if a hand isn't fully known or
+    /// the search ran out of budget.
+    pub fn goat(&mut self, rummy: &RummyPhase) -> Option<PlayerIdx> {
+        if rummy.hands.iter().any(|hand| hand.unknown != 0) {
+            return None;
+        }
+        if rummy.is_finished() {
+            return Some(rummy.next);
+        }
+        self.search(rummy, 0)
+    }
+
+    /// Who becomes goat if the next player takes `action` from `rummy` and everybody plays
+    /// perfectly from then on.
+    pub fn goat_after(&mut self, rummy: &RummyPhase, action: Action) -> Option<PlayerIdx> {
+        if rummy.hands.iter().any(|hand| hand.unknown != 0) {
+            return None;
+        }
+        match play(rummy, action)? {
+            Ok(goat) => Some(goat),
+            Err(child) => self.search(&child, 1),
+        }
+    }
+
+    /// The best action for the next player in `rummy`, along with who becomes goat after it.
+    pub fn solve(&mut self, rummy: &RummyPhase) -> Option<(Action, PlayerIdx)> {
+        let mut best = None;
+        for action in rummy_actions(rummy.next, rummy) {
+            let goat = self.goat_after(rummy, action)?;
+            if goat != rummy.next {
+                return Some((action, goat));
+            }
+            best.get_or_insert((action, goat));
+        }
+        best
+    }
+
+    fn search(&mut self, rummy: &RummyPhase, depth: usize) -> Option<PlayerIdx> {
+        let key = Key::new(rummy);
+        if let Some(goat) = self.memo.get(&key) {
+            return Some(*goat);
+        }
+        if depth == MAX_DEPTH || self.memo.len() >= self.budget {
+            return None;
+        }
+        let mut goat = rummy.next;
+        for action in rummy_actions(rummy.next, rummy) {
+            let outcome = match play(rummy, action)? {
+                Ok(goat) => goat,
+                Err(child) => self.search(&child, depth + 1)?,
+            };
+            if outcome != rummy.next {
+                goat = outcome;
+                break;
+            }
+        }
+        self.memo.insert(key, goat);
+        Some(goat)
+    }
+}
+
+/// Takes `action` in `rummy`, returning the goat if that ends the game, or else the position it
+/// leads to.
+fn play(rummy: &RummyPhase, action: Action) -> Option<Result<PlayerIdx, RummyPhase>> {
+    let mut rummy = rummy.clone();
+    let player = rummy.next;
+    match action {
+        Action::PlayRun { lo, hi } => {
+            rummy.play_run(player, lo, hi).ok()?;
+            if rummy.is_finished() {
+                return Some(Ok(rummy.next));
+            }
+        }
+        Action::PickUp => {
+            if rummy.pick_up(player).ok()? {
+                return Some(Ok(player));
+            }
+        }
+        _ => return None,
+    }
+    Some(Err(rummy))
+}
+
+/// A rummy strategy for the endgame: when few enough cards are hidden, deals them out every
+/// possible way, solves each deal exactly, and takes the action that makes the next player goat
+/// in the fewest deals. Returns `None` when too much is hidden or the search runs out of budget.
+pub fn rummy_solve(rummy: &RummyPhase) -> Option<Action> {
+    let hidden: usize = rummy.hands.iter().map(|hand| hand.unknown as usize).sum();
+    if hidden > MAX_HIDDEN {
+        return None;
+    }
+    let unknown = unseen_cards(rummy)?;
+    if unknown.len() != hidden {
+        return None;
+    }
+    let actions = rummy_actions(rummy.next, rummy);
+    let mut losses = vec![0; actions.len()];
+    let mut solver = Solver::default();
+    let mut deal = rummy.clone();
+    let mut cards: Vec<_> = unknown.cards().collect();
+    cards.sort();
+    let mut complete = true;
+    deals(&mut deal, 0, &cards, &mut |deal| {
+        for (action, losses) in actions.iter().zip(&mut losses) {
+            match solver.goat_after(deal, *action) {
+                Some(goat) => *losses += (goat == deal.next) as u32,
+                None => {
+                    complete = false;
+                    return false;
+                }
+            }
+        }
+        true
+    });
+    if !complete {
+        return None;
+    }
+    log::debug!("Solved {:?} with losses {:?}", rummy, losses);
+    actions
+        .into_iter()
+        .zip(losses)
+        .min_by_key(|(_, losses)| *losses)
+        .map(|(action, _)| action)
+}
+
+/// Calls `f` with every way of giving the sorted `cards` to the hidden cards of the hands from
+/// `player` on, stopping early if `f` returns false.
+fn deals(
+    rummy: &mut RummyPhase,
+    player: usize,
+    cards: &[Card],
+    f: &mut impl FnMut(&RummyPhase) -> bool,
+) -> bool {
+    let Some(hand) = rummy.hands.get(player) else {
+        return f(rummy);
+    };
+    let hand = hand.clone();
+    let mut chosen = Vec::with_capacity(hand.unknown as usize);
+    let keep_going = combinations(cards, hand.unknown as usize, &mut chosen, &mut |chosen| {
+        let mut known = hand.known;
+        chosen.iter().for_each(|index| known += cards[*index]);
+        rummy.hands[player] = ClientRummyHand { known, unknown: 0 };
+        let remaining: Vec<_> = (0..cards.len())
+            .filter(|index| !chosen.contains(index))
+            .map(|index| cards[index])
+            .collect();
+        deals(rummy, player + 1, &remaining, f)
+    });
+    rummy.hands[player] = hand;
+    keep_going
+}
+
+/// Calls `f` with the indices of every distinct way to choose `n` of the sorted `cards`.
+fn combinations(
+    cards: &[Card],
+    n: usize,
+    chosen: &mut Vec<usize>,
+    f: &mut impl FnMut(&[usize]) -> bool,
+) -> bool {
+    if chosen.len() == n {
+        return f(chosen);
+    }
+    let start = chosen.last().map_or(0, |last| last + 1);
+    for index in start..cards.len() {
+        // Choosing either of two identical cards gives the same hand.
+        if index > start && cards[index] == cards[index - 1] {
+            continue;
+        }
+        chosen.push(index);
+        let keep_going = combinations(cards, n, chosen, f);
+        chosen.pop();
+        if !keep_going {
+            return false;
+        }
+    }
+    true
+}
@@ -1,20 +1,59 @@
-use async_trait::async_trait;
 use goat_api::{
     Action, Cards, ClientDeck, ClientGame, ClientPhase, ClientRummyHand, ClientWarHand, Deck,
-    Event, PlayerIdx, Rank, RummyHand, Suit, WarHand,
+    Event, GameId, PlayerIdx, Rank, Response, RummyHand, Suit, WarHand,
 };
 use rand::seq::SliceRandom;
 use rand::Rng;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
+use crate::rummy_solve;
+
 type WarPhase = goat_api::WarPhase<ClientDeck, ClientWarHand, ()>;
 type RummyPhase = goat_api::RummyPhase<ClientRummyHand, Cards>;
 
-#[async_trait]
+/// A bot that decides what to do given its view of the game, without any async runtime.
+pub trait Policy: Send + Sync + 'static {
+    /// The action the player in seat `idx` should take next, or `None` to wait for something to
+    /// change.
+    fn action(&self, game: &ClientGame<(), Cards>, idx: PlayerIdx) -> Option<Action>;
+
+    /// Like [`Policy::action`], in the game `game_id`. Only policies that play several games over
+    /// one connection need to know which game they're in.
+    fn game_action(
+        &self,
+        _game_id: GameId,
+        game: &ClientGame<(), Cards>,
+        idx: PlayerIdx,
+    ) -> Option<Action> {
+        self.action(game, idx)
+    }
+
+    /// Sees every response for the bot before its view of the game is updated, for policies that
+    /// keep their own state.
+    fn observe(&self, _response: &Response) {}
+
+    /// Whether the policy can take a while to pick even a war action, so that async callers should
+    /// ask it off their worker threads as they do for rummy actions.
+    fn slow_war(&self) -> bool {
+        false
+    }
+}
+
+/// A policy split into its war and rummy halves.
 pub trait Strategy: Send + Sync + 'static {
     fn war(&self, idx: PlayerIdx, war: &WarPhase) -> Option<Action>;
-    async fn rummy(&self, rummy: &RummyPhase) -> Action;
+    fn rummy(&self, rummy: &RummyPhase) -> Action;
+}
+
+impl<S: Strategy> Policy for S {
+    fn action(&self, game: &ClientGame<(), Cards>, idx: PlayerIdx) -> Option<Action> {
+        match &game.phase {
+            ClientPhase::War(war) => self.war(idx, war),
+            ClientPhase::Rummy(rummy) if rummy.next == idx => Some(self.rummy(rummy)),
+            _ => None,
+        }
+    }
 }
 
 /// The simplest possible war strategy, never hold any cards in hand and always play from the top
@@ -252,48 +291,87 @@ pub fn rummy_random<R: Rng>(rng: &mut R, rummy: &RummyPhase) -> Action {
     Action::PickUp
 }
 
-pub async fn rummy_simulate(rummy: &RummyPhase) -> Action {
-    let mut unknown = Cards::ONE_DECK * 3;
-    let mut count = 1 + rummy.history.len();
-    unknown -= rummy.trump;
-    unknown -= rummy.history;
-    for hand in rummy.hands.iter() {
-        unknown -= hand.known;
-        count += hand.len();
-    }
-    if count % 52 != 0 {
-        panic!("unexpected state: {:?}", rummy);
-    }
-    if count == 52 {
-        unknown -= Cards::ONE_DECK * 2;
-    } else if count == 104 {
-        unknown -= Cards::ONE_DECK;
-    }
+/// A rummy strategy that plays out random games for three seconds and takes the action that lost
+/// least often. Once few enough cards are hidden it solves the position exactly instead.
+pub fn rummy_simulate(rummy: &RummyPhase) -> Action {
     let start = Instant::now();
+    rummy_simulate_while(rummy, |_| start.elapsed() < Duration::from_secs(3))
+}
+
+/// Like [`rummy_simulate`], but plays out a fixed number of random games instead of running for a
+/// fixed time. Use this where there is no clock, such as in the browser.
+pub fn rummy_simulate_n(rummy: &RummyPhase, simulations: u64) -> Action {
+    rummy_simulate_while(rummy, |count| count < simulations.max(1))
+}
+
+fn rummy_simulate_while(rummy: &RummyPhase, mut keep_going: impl FnMut(u64) -> bool) -> Action {
+    if let Some(action) = rummy_solve(rummy) {
+        return action;
+    }
+    let unknown = unseen_cards(rummy).unwrap_or_else(|| panic!("unexpected state: {:?}", rummy));
     let mut simulations = HashMap::new();
-    while start.elapsed() < Duration::from_secs(3) {
+    let mut count = 0;
+    while keep_going(count) {
         let (action, goat) = simulate_once(&mut rand::thread_rng(), rummy.clone(), unknown);
         let (losses, games) = simulations.entry(action).or_insert((0, 0));
         *losses += (goat == rummy.next) as u64;
         *games += 1;
-        if *games & 0xfff == 0 {
-            tokio::task::yield_now().await;
-        }
+        count += 1;
     }
     log::debug!("Simulations on {:?} produced {:?}", rummy, simulations);
     simulations
         .into_iter()
         .min_by_key(|(_, (losses, games))| {
-            if *games == 0 {
-                u32::MAX
-            } else {
-                ((*losses * (u32::MAX as u64)) / *games) as u32
-            }
+            (*losses * (u32::MAX as u64))
+                .checked_div(*games)
+                .map_or(u32::MAX, |rate| rate as u32)
         })
         .map(|(action, _)| action)
         .unwrap()
 }
 
+/// The cards that neither the trump, the history nor any known hand accounts for, which must be
+/// the hidden cards in hands, or `None` if the cards don't add up to whole decks.
+pub fn unseen_cards(rummy: &RummyPhase) -> Option<Cards> {
+    let mut count = 1 + rummy.history.len();
+    for hand in rummy.hands.iter() {
+        count += hand.len();
+    }
+    if !count.is_multiple_of(52) {
+        return None;
+    }
+    let mut unknown = Cards::ONE_DECK * (count / 52);
+    unknown -= rummy.trump;
+    unknown -= rummy.history;
+    for hand in rummy.hands.iter() {
+        unknown -= hand.known;
+    }
+    Some(unknown)
+}
+
+/// Estimates the chance of each seat ending up goat from `rummy` by playing out random games, with
+/// the hidden cards dealt at random for each one, until `keep_going` says to stop.
+pub fn goat_chances(
+    rummy: &RummyPhase,
+    mut keep_going: impl FnMut(u64) -> bool,
+) -> Option<Vec<f64>> {
+    let unknown = unseen_cards(rummy)?;
+    let mut goats = vec![0; rummy.hands.len()];
+    let mut count = 0;
+    while keep_going(count) {
+        let (_, goat) = simulate_once(&mut rand::thread_rng(), rummy.clone(), unknown);
+        goats[goat.idx()] += 1;
+        count += 1;
+    }
+    let count = count.max(1) as f64;
+    Some(
+        goats
+            .into_iter()
+            .map(|goats| goats as f64 / count)
+            .collect(),
+    )
+}
+
 pub fn simulate_once<R: Rng>(
     rng: &mut R,
     mut rummy: RummyPhase,
@@ -310,25 +388,28 @@ pub fn simulate_once<R: Rng>(
             hand.unknown = 0;
         }
     }
+    let action = rummy_random(rng, &rummy);
+    (action, rollout(rng, rummy, action))
+}
+
+/// Takes `action` in a rummy phase where every hand is known, then plays the game out at random
+/// and returns the goat.
+pub fn rollout<R: Rng>(rng: &mut R, rummy: RummyPhase, mut action: Action) -> PlayerIdx {
     let mut game = ClientGame {
         phase: ClientPhase::<(), Cards>::Rummy(rummy),
         players: vec![],
     };
-    let mut first_action = None;
     loop {
-        let action = match &game.phase {
-            ClientPhase::Rummy(rummy) => rummy_random(rng, rummy),
-            ClientPhase::Goat(goat) => return (first_action.unwrap(), goat.goat),
-            _ => panic!("unexpected phase"),
-        };
-        if first_action.is_none() {
-            first_action = Some(action);
-        }
         let event = match action {
             Action::PickUp => Event::PickUp,
             Action::PlayRun { lo, hi } => Event::PlayRun { lo, hi },
             _ => panic!("unexpected action"),
         };
         game.apply(event).unwrap();
+        action = match &game.phase {
+            ClientPhase::Rummy(rummy) => rummy_random(rng, rummy),
+            ClientPhase::Goat(goat) => return goat.goat,
+            _ => panic!("unexpected phase"),
+        };
     }
 }
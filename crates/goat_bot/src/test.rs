@@ -0,0 +1,292 @@
+#[cfg(unix)]
+use std::process::Command;
+#[cfg(unix)]
+use std::time::Duration;
+
+use goat_api::{
+    Action, Card, Cards, ClientGame, ClientPhase, ClientRummyHand, GoatError, PlayerIdx, Position,
+    Response, ServerPhase, WarPosition,
+};
+
+use crate::arena::users;
+use crate::{
+    analyze, analyze_position, legal_actions, rummy_solve, self_play, AdaptSimple, AdaptSimulateN,
+    Arena, DuckSimple, External, HandEval, LinearPolicy, PlayTopSimple, Policy, Solver,
+    ACTION_FEATURES,
+};
+
+macro_rules! c {
+    ($($cards:tt)*) => {
+        stringify!($($cards)*).parse::<Cards>().unwrap()
+    };
+}
+
+#[test]
+fn eval_runs_and_trumps() {
+    let eval = HandEval::new(c!(AK9S 432H 5D), Card::TwoSpades, 2, Cards::NONE, 1);
+    assert_eq!(eval.cards, 7);
+    assert_eq!(eval.runs, 4);
+    assert_eq!(eval.trumps, 3);
+    assert_eq!(eval.master_trumps, 2);
+    assert_eq!(eval.voids, 1);
+    assert_eq!(eval.tricks_needed(), 4);
+}
+
+#[test]
+fn eval_played_cards_promote_trumps() {
+    let hand = c!(Q9S 432H 5D);
+    let unplayed = HandEval::new(hand, Card::TwoSpades, 2, Cards::NONE, 1);
+    assert_eq!(unplayed.master_trumps, 0);
+    let played = HandEval::new(hand, Card::TwoSpades, 2, c!(AKS), 1);
+    assert_eq!(played.master_trumps, 1);
+    assert!(played.score() > unplayed.score());
+}
+
+#[test]
+fn eval_uncovered_voids() {
+    let eval = HandEval::new(c!(5432H), Card::TwoSpades, 3, Cards::NONE, 1);
+    assert_eq!(eval.voids, 2);
+    assert_eq!(eval.tricks_needed(), 3);
+    let eval = HandEval::new(c!(5432H), Card::TwoSpades, 3, c!(AKQJT98765432C), 1);
+    assert_eq!(eval.voids, 1);
+    assert_eq!(eval.tricks_needed(), 2);
+}
+
+#[test]
+fn eval_fewer_runs_is_better() {
+    let connected = HandEval::new(c!(5432H), Card::TwoSpades, 2, Cards::NONE, 1);
+    let split = HandEval::new(c!(A842H), Card::TwoSpades, 2, Cards::NONE, 1);
+    assert!(connected.score() > split.score());
+    assert_eq!(
+        HandEval::new(Cards::NONE, Card::TwoSpades, 2, Cards::NONE, 1).score(),
+        f64::INFINITY
+    );
+}
+
+#[test]
+fn self_play_records_legal_decisions() {
+    let policy = LinearPolicy::new([1.0; ACTION_FEATURES]);
+    let policies: [&dyn Policy; 4] = [&AdaptSimple, &DuckSimple, &PlayTopSimple, &policy];
+    let samples = self_play(7, 1, &policies).unwrap();
+    assert!(!samples.is_empty());
+    for sample in &samples {
+        assert_eq!(sample.legal.len(), sample.features.len());
+        assert!(sample.chosen < sample.legal.len());
+    }
+    let goats: Vec<_> = samples.iter().filter(|s| s.goat).map(|s| s.seat).collect();
+    assert!(!goats.is_empty());
+    assert!(goats.iter().all(|seat| *seat == goats[0]));
+}
+
+#[test]
+fn simulate_n_plays_a_game() {
+    let policies: [&dyn Policy; 3] = [&AdaptSimulateN(10), &AdaptSimple, &AdaptSimulateN(1)];
+    let mut arena = Arena::new(3, 3, 1).unwrap();
+    arena.play(&policies, |_, _, _| {}).unwrap();
+}
+
+#[test]
+fn arena_plays_on_from_position() {
+    let hands = vec![c!(2C), c!(3C), Cards::NONE];
+    let deck = (Cards::ONE_DECK - c!(32C)).cards().collect();
+    let position = Position::War(WarPosition {
+        deck,
+        won: vec![Cards::NONE; hands.len()],
+        hands,
+        leader: PlayerIdx(1),
+    });
+    let policies: [&dyn Policy; 3] = [&AdaptSimple; 3];
+    let mut arena = Arena::from_position(&position, 5).unwrap();
+    arena.play(&policies, |_, _, _| {}).unwrap();
+}
+
+fn rummy_view(position: &str, viewer: PlayerIdx) -> goat_api::RummyPhase<ClientRummyHand, Cards> {
+    let position: Position = position.parse().unwrap();
+    let users = users(position.hands().len()).to_vec();
+    let game: ClientGame<(), Cards> = position.client_game(users, Some(viewer)).unwrap();
+    let ClientPhase::Rummy(rummy) = game.phase else {
+        panic!("not a rummy position");
+    };
+    rummy
+}
+
+#[test]
+fn solver_finds_the_goat() {
+    let position = "hands [2C 3C] [4C] []\ntrump 9H\nnext P0";
+    let mut rummy = rummy_view(position, PlayerIdx(0));
+    rummy.hands[1] = ClientRummyHand {
+        known: c!(4C),
+        unknown: 0,
+    };
+    let mut solver = Solver::default();
+    // Leading the 2C alone lets the 4C kill the trick and go out.
+    let lo = Card::TwoClubs;
+    assert_eq!(
+        solver.goat_after(&rummy, Action::PlayRun { lo, hi: lo }),
+        Some(PlayerIdx(0))
+    );
+    let run = Action::PlayRun {
+        lo,
+        hi: Card::ThreeClubs,
+    };
+    assert_eq!(solver.solve(&rummy), Some((run, PlayerIdx(1))));
+    assert_eq!(solver.goat(&rummy), Some(PlayerIdx(1)));
+
+    let mut rummy = rummy_view(position, PlayerIdx(1));
+    assert_eq!(solver.goat(&rummy), None);
+    rummy.hands[0].unknown = 0;
+    rummy.hands[0].known = c!(2C 3C);
+    rummy.next = PlayerIdx(1);
+    assert_eq!(solver.goat(&rummy), Some(PlayerIdx(0)));
+}
+
+#[test]
+fn rummy_solve_deals_out_hidden_cards() {
+    let played = Cards::ONE_DECK - c!(2C 3C 4C 5C 9H);
+    let position = format!("hands [2C 3C] [4C 5C] []\ntrump 9H\nnext P0\nplayed {played}");
+    let rummy = rummy_view(&position, PlayerIdx(0));
+    // P1's hand can only be what's left, so P0 knows to go out in one go.
+    let run = Action::PlayRun {
+        lo: Card::TwoClubs,
+        hi: Card::ThreeClubs,
+    };
+    assert_eq!(rummy_solve(&rummy), Some(run));
+    let rummy = rummy_view("hands [2C 3C] [4C 5C] []\ntrump 9H\nnext P0", PlayerIdx(0));
+    assert_eq!(rummy_solve(&rummy), None);
+}
+
+#[test]
+fn analysis_reports_every_rummy_decision() {
+    let policies: [&dyn Policy; 3] = [&AdaptSimple; 3];
+    let mut arena = Arena::new(7, 3, 1).unwrap();
+    let goat = arena.play(&policies, |_, _, _| {}).unwrap();
+    let reports = analyze(&arena.game.events, 20, 0).unwrap();
+    assert_eq!(reports.len(), 3);
+    for report in &reports {
+        assert_eq!(report.goat, report.seat == goat);
+        assert!(!report.to_string().is_empty());
+        for decision in &report.decisions {
+            assert_eq!(decision.seat, report.seat);
+            assert!(decision.loss >= 0.0);
+            let total: f64 = decision.goat_chances.iter().sum();
+            assert!((total - 1.0).abs() < 1e-9);
+        }
+    }
+    // The last move ends the game, so it is always solved exactly.
+    let last = reports
+        .iter()
+        .flat_map(|report| &report.decisions)
+        .max_by_key(|decision| decision.event)
+        .unwrap();
+    assert!(last.exact);
+    assert_eq!(last.goat_chances[goat.idx()], 1.0);
+
+    let unfinished = &arena.game.events[..arena.game.events.len() / 2];
+    assert_eq!(analyze(unfinished, 20, 0), Err(GoatError::GameNotFinished));
+}
+
+#[cfg(unix)]
+fn external(script: &str, timeout: Duration) -> External<AdaptSimple> {
+    let mut command = Command::new("sh");
+    command.args(["-c", script]);
+    External::spawn(&mut command, timeout, AdaptSimple).unwrap()
+}
+
+#[test]
+#[cfg(unix)]
+fn external_bots_fall_back_when_they_fail() {
+    // Plays from the top whenever asked, which is only sometimes legal.
+    let top = external(
+        r#"while read line; do case "$line" in *'"type":"go"'*) echo '{"type":"playTop"}';; esac; done"#,
+        Duration::from_secs(10),
+    );
+    let crashed = external("exit 1", Duration::from_secs(10));
+    let silent = external("cat > /dev/null", Duration::from_millis(50));
+    for policy in [&top, &crashed, &silent] {
+        let policies: [&dyn Policy; 3] = [policy, &AdaptSimple, &AdaptSimple];
+        let mut arena = Arena::new(3, 3, 1).unwrap();
+        arena.play(&policies, |_, _, _| {}).unwrap();
+    }
+    assert!(top.is_running());
+    assert!(!crashed.is_running());
+    assert!(!silent.is_running());
+}
+
+#[test]
+#[cfg(unix)]
+fn external_bots_that_stop_reading_are_stopped() {
+    let deaf = external("sleep 60", Duration::from_secs(10));
+    for _ in 0..100_000 {
+        deaf.observe(&Response::Ping);
+    }
+    assert!(!deaf.is_running());
+    let policies: [&dyn Policy; 3] = [&deaf, &AdaptSimple, &AdaptSimple];
+    let mut arena = Arena::new(3, 3, 1).unwrap();
+    arena.play(&policies, |_, _, _| {}).unwrap();
+}
+
+#[test]
+fn large_game() {
+    let policies: [&dyn Policy; 20] = [&AdaptSimple; 20];
+    let mut arena = Arena::new(20, 20, 5).unwrap();
+    arena.play(&policies, |_, _, _| {}).unwrap();
+}
+
+#[test]
+fn linear_policy_parse() {
+    let text = "# bias\n1\n0 0 0 0 0 0 0 0 0 0 0 0\n-2.5 # master trumps\n";
+    let policy: LinearPolicy = text.parse().unwrap();
+    assert_eq!(policy.weights()[0], 1.0);
+    assert_eq!(policy.weights()[ACTION_FEATURES - 1], -2.5);
+    assert!("1 2 3".parse::<LinearPolicy>().is_err());
+    assert!("x".parse::<LinearPolicy>().is_err());
+}
+
+#[test]
+fn analysis_flags_a_known_blunder() {
+    let position: Position = "hands [32C] [4C AS] [5C KS]\ntrump 9S\nnext P0"
+        .parse()
+        .unwrap();
+    let Position::Rummy(rummy) = &position else {
+        panic!("not a rummy position");
+    };
+    let players = users(3).to_vec();
+    let mut game = position.server_game(players.clone(), 0).unwrap();
+    let mut views: Vec<ClientGame<(), Cards>> = (0..3)
+        .map(|idx| {
+            position
+                .client_game(players.clone(), Some(PlayerIdx(idx)))
+                .unwrap()
+        })
+        .collect();
+    // Playing the two alone leaves P0 with the three, which can't get out in time.
+    let mut action = Action::PlayRun {
+        lo: Card::TwoClubs,
+        hi: Card::TwoClubs,
+    };
+    while let ServerPhase::Rummy(phase) = &game.phase {
+        let applied = game.events.len();
+        game.apply(players[phase.next.idx()], action).unwrap();
+        for (seat, view) in views.iter_mut().enumerate() {
+            for event in &game.events[applied..] {
+                view.apply(event.redact(Some(PlayerIdx(seat as u8))))
+                    .unwrap();
+            }
+        }
+        if let ServerPhase::Rummy(phase) = &game.phase {
+            action = legal_actions(&views[phase.next.idx()], phase.next)[0];
+        }
+    }
+    let reports = analyze_position(rummy, players, &game.events, 20, 0).unwrap();
+    let first = &reports[0].decisions[0];
+    assert!(first.exact);
+    assert!(first.blunder);
+    assert_eq!(first.loss, 1.0);
+    assert_eq!(
+        first.best,
+        Action::PlayRun {
+            lo: Card::TwoClubs,
+            hi: Card::ThreeClubs,
+        }
+    );
+}
@@ -0,0 +1,43 @@
+//! Plays as a bot on a running server, in its own process.
+//!
+//! Usage: `remote_bot <url> <api key>`
+//!
+//! The API key comes from `/new_api_key`. The bot joins the games it is invited to and plays
+//! with the same strategy as the server's built-in bots.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use goat_api::{Action, Protocol, PROTOCOL_VERSION};
+use goat_bot::{AdaptSimulate, Bot, Lifecycle};
+use goat_client::{Connection, Credentials};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let mut args = std::env::args().skip(1);
+    let url = args.next().ok_or("missing server URL")?;
+    let key = args.next().ok_or("missing API key")?;
+
+    let connection = Arc::new(Connection::new(&url, Credentials::ApiKey(key)));
+    // Bots need replays to see their invites, so they don't take snapshots.
+    let protocol = Protocol {
+        version: PROTOCOL_VERSION,
+        capabilities: Vec::new(),
+    };
+    let (user_id, rx) = connection.subscribe(protocol).await?;
+    let sleep = |action| match action {
+        Action::PlayRun { .. } | Action::PickUp => Duration::from_secs(1),
+        _ => Duration::from_millis(500),
+    };
+    let mut bot = Bot::new(
+        user_id,
+        rx,
+        connection.action_sender(),
+        AdaptSimulate,
+        sleep,
+        Lifecycle::default(),
+    );
+    bot.run().await?;
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::body::{Bytes, HttpBody};
+use hyper::client::HttpConnector;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, COOKIE, SET_COOKIE};
+use hyper::{Body, Method, Request, Uri};
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time;
+
+use goat_api::{Action, Capability, GameId, GoatError, Protocol, RandId, Response, UserId};
+
+use crate::sse::EventParser;
+use crate::Error;
+
+/// How long a subscription may go without hearing anything before it reconnects. The server
+/// pings every 20 seconds.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long to wait before the first attempt to reconnect, doubling after each failed attempt up
+/// to [`MAX_RECONNECT_DELAY`].
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Who a [`Connection`] acts as.
+#[derive(Clone, Debug)]
+pub enum Credentials {
+    /// A person, known by a secret they keep and the name they go by, as the browser keeps them
+    /// in its cookies.
+    Cookie { secret: String, name: String },
+    /// A bot, known by an API key it was given by `/new_api_key`.
+    ApiKey(String),
+}
+
+impl Credentials {
+    /// A person called `name` with a new random secret.
+    pub fn random(name: String) -> Self {
+        Credentials::Cookie {
+            secret: rand::random::<RandId>().to_string(),
+            name,
+        }
+    }
+}
+
+/// A connection to a goat server over plain HTTP, acting as one user. Actions are posted as
+/// requests, and subscriptions stream server-sent events, reconnecting whenever the stream
+/// drops.
+pub struct Connection {
+    http: hyper::Client<HttpConnector>,
+    url: String,
+    credentials: Mutex<Credentials>,
+}
+
+impl Connection {
+    /// Connects to the server at `url`, such as `http://127.0.0.1:9402`, as `credentials`.
+    pub fn new(url: &str, credentials: Credentials) -> Self {
+        Self {
+            http: hyper::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            credentials: Mutex::new(credentials),
+        }
+    }
+
+    pub fn credentials(&self) -> Credentials {
+        self.credentials.lock().unwrap().clone()
+    }
+
+    pub async fn new_game(&self) -> Result<GameId, Error> {
+        let body = self.send(Method::POST, "new_game", Body::empty()).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    pub async fn apply_action(&self, game_id: GameId, action: Action) -> Result<(), Error> {
+        let path = format!("apply_action?game_id={}", game_id);
+        let body = serde_json::to_vec(&action)?;
+        self.send(Method::POST, &path, body.into()).await?;
+        Ok(())
+    }
+
+    /// Changes the name of a person. Bots keep the name their API key was registered with.
+    pub async fn change_name(&self, name: String) -> Result<(), Error> {
+        if let Credentials::Cookie { name: old, .. } = &mut *self.credentials.lock().unwrap() {
+            *old = name;
+        }
+        self.send(Method::POST, "change_name", Body::empty())
+            .await?;
+        Ok(())
+    }
+
+    /// Registers a bot called `name` and returns its API key. The server only keeps keys in memory,
+    /// so they have to be registered again whenever it restarts.
+    pub async fn new_api_key(&self, name: &str) -> Result<String, Error> {
+        let path = format!("new_api_key?name={}", encode_query(name));
+        let body = self.send(Method::POST, &path, Body::empty()).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Subscribes to every game and user, speaking `protocol`. Returns the id the server knows
+    /// this user by, and the responses it sends. Whenever the subscription drops, it reconnects
+    /// and the server starts over with a [`Response::Hello`], followed by the state of every
+    /// game and user. The subscription ends once the receiver is dropped.
+    pub async fn subscribe(
+        self: &Arc<Self>,
+        protocol: Protocol,
+    ) -> Result<(UserId, UnboundedReceiver<Response>), Error> {
+        let mut path = format!("subscribe?version={}", protocol.version);
+        let capabilities: Vec<_> = protocol
+            .capabilities
+            .iter()
+            .filter(|capability| **capability != Capability::Unknown)
+            .filter_map(|capability| {
+                serde_json::to_value(capability)
+                    .ok()?
+                    .as_str()
+                    .map(str::to_string)
+            })
+            .collect();
+        if !capabilities.is_empty() {
+            path += &format!("&capabilities={}", capabilities.join(","));
+        }
+        let (user_id, body) = self.open(&path).await?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(stream(self.clone(), path, body, tx));
+        Ok((user_id, rx))
+    }
+
+    /// A function that posts actions in the background, for driving a `goat_bot::Bot` against
+    /// the server. Failed actions are logged rather than returned.
+    pub fn action_sender(
+        self: &Arc<Self>,
+    ) -> impl Fn(UserId, GameId, Action) -> Result<(), GoatError> + Clone + Send + Sync + 'static
+    {
+        let connection = self.clone();
+        let handle = Handle::current();
+        move |_, game_id, action| {
+            let connection = connection.clone();
+            handle.spawn(async move {
+                if let Err(e) = connection.apply_action(game_id, action).await {
+                    log::warn!("Failed to apply {:?} to {}: {}", action, game_id, e);
+                }
+            });
+            Ok(())
+        }
+    }
+
+    fn request(&self, method: Method, path: &str, body: Body) -> Result<Request<Body>, Error> {
+        let url = format!("{}/{}", self.url, path);
+        let uri: Uri = url.parse().map_err(|_| Error::InvalidUrl { url })?;
+        let mut request = Request::builder().method(method).uri(uri);
+        request = match &*self.credentials.lock().unwrap() {
+            Credentials::Cookie { secret, name } => request.header(
+                COOKIE,
+                format!("USER_SECRET={}; USER_NAME={}", secret, name),
+            ),
+            Credentials::ApiKey(key) => request.header(AUTHORIZATION, format!("Bearer {}", key)),
+        };
+        Ok(request
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)?)
+    }
+
+    /// Makes a request and returns the body of the response, or the error the server answered
+    /// with.
+    async fn send(&self, method: Method, path: &str, body: Body) -> Result<Bytes, Error> {
+        let request = self.request(method, path, body)?;
+        let response = self.http.request(request).await?;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        if !status.is_success() {
+            return Err(Error::from_response(status.as_u16(), &body));
+        }
+        Ok(body)
+    }
+
+    /// Opens a subscription, returning the user id the server set and the stream of events.
+    async fn open(&self, path: &str) -> Result<(UserId, Body), Error> {
+        let request = self.request(Method::GET, path, Body::empty())?;
+        let response = self.http.request(request).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            return Err(Error::from_response(status.as_u16(), &body));
+        }
+        let user_id = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|cookie| cookie.to_str().ok()?.strip_prefix("USER_ID="))
+            .find_map(|id| id.split(';').next()?.trim().parse().ok())
+            .ok_or_else(|| GoatError::InvalidState {
+                what: "subscription without a user id".into(),
+            })?;
+        Ok((UserId(user_id), response.into_body()))
+    }
+}
+
+/// Forwards the responses in `body` to `tx`, reconnecting to `path` whenever the stream ends,
+/// fails, or goes quiet, until `tx` is closed.
+async fn stream(
+    connection: Arc<Connection>,
+    path: String,
+    mut body: Body,
+    tx: UnboundedSender<Response>,
+) {
+    let mut delay = MIN_RECONNECT_DELAY;
+    loop {
+        let mut parser = EventParser::default();
+        loop {
+            let chunk = match time::timeout(IDLE_TIMEOUT, body.data()).await {
+                Ok(Some(Ok(chunk))) => chunk,
+                Ok(Some(Err(e))) => {
+                    log::warn!("Subscription failed: {}", e);
+                    break;
+                }
+                Ok(None) => {
+                    log::info!("Subscription ended");
+                    break;
+                }
+                Err(_) => {
+                    log::warn!("Subscription went quiet");
+                    break;
+                }
+            };
+            for data in parser.push(&chunk) {
+                match serde_json::from_str(&data) {
+                    Ok(response) => {
+                        if tx.send(response).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => log::warn!("Ignoring response {:?}: {}", data, e),
+                }
+            }
+            delay = MIN_RECONNECT_DELAY;
+        }
+        body = loop {
+            if tx.is_closed() {
+                return;
+            }
+            time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            match connection.open(&path).await {
+                Ok((_, body)) => break body,
+                Err(e) => log::warn!("Failed to resubscribe: {}", e),
+            }
+        };
+    }
+}
+
+/// Percent-encodes `s` for use in a query string.
+fn encode_query(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
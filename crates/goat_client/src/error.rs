@@ -0,0 +1,66 @@
+use serde::Deserialize;
+use thiserror::Error;
+
+use goat_api::GoatError;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Invalid URL: {url}")]
+    InvalidUrl { url: String },
+    /// A request that can't be sent, such as one with a line break in the user's name.
+    #[error("Invalid request: {error}")]
+    Request {
+        #[from]
+        error: hyper::http::Error,
+    },
+    #[error("HTTP error: {error}")]
+    Http {
+        #[from]
+        error: hyper::Error,
+    },
+    #[error("Invalid response: {error}")]
+    Json {
+        #[from]
+        error: serde_json::Error,
+    },
+    #[error("Rules error: {error}")]
+    Rules {
+        #[from]
+        error: GoatError,
+    },
+    /// A failure that isn't about the rules, such as an unknown API key or too many requests.
+    #[error("Server error {status} ({code}): {message}")]
+    Server {
+        status: u16,
+        code: String,
+        message: String,
+    },
+}
+
+/// The body of an error response that isn't a [`GoatError`].
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: String,
+    message: String,
+}
+
+impl Error {
+    /// The error the server answered with `status` and `body`.
+    pub(crate) fn from_response(status: u16, body: &[u8]) -> Self {
+        if let Ok(error) = serde_json::from_slice::<GoatError>(body) {
+            return error.into();
+        }
+        match serde_json::from_slice::<ErrorBody>(body) {
+            Ok(ErrorBody { code, message }) => Error::Server {
+                status,
+                code,
+                message,
+            },
+            Err(_) => Error::Server {
+                status,
+                code: String::new(),
+                message: String::from_utf8_lossy(body).into_owned(),
+            },
+        }
+    }
+}
@@ -0,0 +1,11 @@
+pub use connection::*;
+pub use error::*;
+pub use session::*;
+
+mod connection;
+mod error;
+mod session;
+mod sse;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,27 @@
+use goat_api::{Client, GoatError, PreviousTrick, Response, RummyHistory, UserDb, UserId};
+
+/// The games and users one subscription has heard about. Since a subscription that reconnects
+/// is sent the state of every game and user again, the session starts over whenever it is
+/// greeted, so that games forgotten while it was away are forgotten here too.
+pub struct Session<Users, PrevTrick, History> {
+    pub user_id: UserId,
+    pub client: Client<Users, PrevTrick, History>,
+}
+
+impl<Users: UserDb + Default, PrevTrick: PreviousTrick, History: RummyHistory>
+    Session<Users, PrevTrick, History>
+{
+    pub fn new(user_id: UserId) -> Self {
+        Self {
+            user_id,
+            client: Client::new(Users::default()),
+        }
+    }
+
+    pub fn apply(&mut self, response: Response) -> Result<(), GoatError> {
+        if let Response::Hello { .. } = response {
+            self.client = Client::new(Users::default());
+        }
+        self.client.apply(response)
+    }
+}
@@ -0,0 +1,37 @@
+/// Splits a stream of server-sent events into the data of each event, however the stream is
+/// chunked.
+#[derive(Default)]
+pub(crate) struct EventParser {
+    line: Vec<u8>,
+    data: String,
+}
+
+impl EventParser {
+    /// Feeds `chunk` to the parser, returning the data of every event it completes.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+        for &b in chunk {
+            if b != b'\n' {
+                self.line.push(b);
+                continue;
+            }
+            if self.line.last() == Some(&b'\r') {
+                self.line.pop();
+            }
+            let line = String::from_utf8_lossy(&self.line).into_owned();
+            self.line.clear();
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(std::mem::take(&mut self.data));
+                }
+            } else if let Some(data) = line.strip_prefix("data:") {
+                if !self.data.is_empty() {
+                    self.data.push('\n');
+                }
+                self.data.push_str(data.strip_prefix(' ').unwrap_or(data));
+            }
+            // Comments, event names, ids and retry hints are of no use to the client.
+        }
+        events
+    }
+}
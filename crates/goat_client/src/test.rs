@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use hyper::header::{AUTHORIZATION, SET_COOKIE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response as HttpResponse, StatusCode};
+
+use goat_api::{
+    Action, Event, GameId, GoatError, Protocol, RandId, Response, User, UserId, PROTOCOL_VERSION,
+};
+
+use crate::sse::EventParser;
+use crate::{Connection, Credentials, Error, Session};
+
+#[test]
+fn events_split_across_chunks() {
+    let mut parser = EventParser::default();
+    assert!(parser.push(b": comment\n\ndata:{\"ty").is_empty());
+    assert_eq!(
+        parser.push(b"pe\":\"ping\"}\r\n\r\ndata: a\n"),
+        [r#"{"type":"ping"}"#]
+    );
+    assert_eq!(parser.push(b"data: b\n\nevent: x\n\n"), ["a\nb"]);
+}
+
+fn user_id() -> UserId {
+    UserId(RandId::from_hash(&[7; 16]))
+}
+
+fn game_id() -> GameId {
+    GameId(RandId::from_hash(&[9; 16]))
+}
+
+fn sse(responses: &[Response]) -> String {
+    responses
+        .iter()
+        .map(|response| format!("data:{}\n\n", serde_json::to_string(response).unwrap()))
+        .collect()
+}
+
+/// Serves a fake goat server that only takes one API key. Every subscription is sent a greeting
+/// and a replay, then dropped, so that clients have to reconnect.
+async fn serve(requests: Arc<Mutex<Vec<String>>>) -> SocketAddr {
+    let subscriptions = Arc::new(AtomicUsize::new(0));
+    let make_service = make_service_fn(move |_| {
+        let requests = requests.clone();
+        let subscriptions = subscriptions.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                let requests = requests.clone();
+                let subscriptions = subscriptions.clone();
+                async move {
+                    let authorized = request
+                        .headers()
+                        .get(AUTHORIZATION)
+                        .map(|key| key.as_bytes())
+                        == Some(b"Bearer key");
+                    let uri = request.uri().to_string();
+                    let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+                    requests.lock().unwrap().push(format!(
+                        "{} {}",
+                        uri,
+                        String::from_utf8_lossy(&body)
+                    ));
+                    let response = HttpResponse::builder();
+                    let response = if !authorized {
+                        response.status(StatusCode::UNAUTHORIZED).body(Body::from(
+                            r#"{"code":"unauthorized","message":"Unknown API key"}"#,
+                        ))
+                    } else if uri.starts_with("/subscribe") {
+                        let n = subscriptions.fetch_add(1, Ordering::SeqCst);
+                        let mut events = vec![Event::Join { user_id: user_id() }];
+                        if n == 0 {
+                            events.push(Event::Join {
+                                user_id: UserId(RandId::from_hash(&[8; 16])),
+                            });
+                        }
+                        response
+                            .header(SET_COOKIE, format!("USER_ID={}", user_id()))
+                            .body(Body::from(sse(&[
+                                Response::Hello {
+                                    version: PROTOCOL_VERSION,
+                                    capabilities: vec![],
+                                },
+                                Response::User {
+                                    user_id: user_id(),
+                                    user: User {
+                                        name: "Bot".into(),
+                                        online: true,
+                                        bot: true,
+                                    },
+                                },
+                                Response::Replay {
+                                    game_id: game_id(),
+                                    events,
+                                },
+                            ])))
+                    } else {
+                        let error = GoatError::InvalidGame { game_id: game_id() };
+                        response
+                            .status(StatusCode::NOT_FOUND)
+                            .body(Body::from(serde_json::to_string(&error).unwrap()))
+                    };
+                    Ok::<_, Infallible>(response.unwrap())
+                }
+            }))
+        }
+    });
+    let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    addr
+}
+
+#[tokio::test]
+async fn subscriptions_reconnect() -> Result<(), Error> {
+    let requests = Arc::default();
+    let addr = serve(Arc::clone(&requests)).await;
+    let url = format!("http://{}/", addr);
+
+    let stranger = Arc::new(Connection::new(&url, Credentials::ApiKey("nope".into())));
+    match stranger.subscribe(Protocol::current()).await {
+        Err(Error::Server { status, code, .. }) => {
+            assert_eq!(status, 401);
+            assert_eq!(code, "unauthorized");
+        }
+        other => panic!("{:?}", other.map(|(user_id, _)| user_id)),
+    }
+
+    let connection = Arc::new(Connection::new(&url, Credentials::ApiKey("key".into())));
+    let (user_id, mut rx) = connection.subscribe(Protocol::current()).await?;
+    assert_eq!(user_id, self::user_id());
+    let mut session = Session::<HashMap<UserId, User>, (), ()>::new(user_id);
+    let mut hellos = 0;
+    let mut replays = 0;
+    while replays < 2 {
+        let response = rx.recv().await.unwrap();
+        match response {
+            Response::Hello { .. } => hellos += 1,
+            Response::Replay { .. } => replays += 1,
+            _ => {}
+        }
+        session.apply(response)?;
+        // The other player only joined before reconnecting, and starting over forgets them.
+        if let Some(game) = session.client.games.get(&game_id()) {
+            assert_eq!(game.players.len(), 3 - hellos);
+        }
+    }
+    assert_eq!(session.client.users[&user_id].name, "Bot");
+
+    match connection.apply_action(game_id(), Action::PlayTop).await {
+        Err(Error::Rules {
+            error: GoatError::InvalidGame { game_id },
+        }) => assert_eq!(game_id, self::game_id()),
+        other => panic!("{:?}", other),
+    }
+    let requests = requests.lock().unwrap();
+    let subscribe = format!(
+        "/subscribe?version={}&capabilities=snapshots ",
+        PROTOCOL_VERSION
+    );
+    assert_eq!(requests[1], subscribe);
+    assert_eq!(requests[2], subscribe);
+    assert_eq!(
+        requests.last().unwrap(),
+        &format!(
+            r#"/apply_action?game_id={} {{"type":"playTop"}}"#,
+            game_id()
+        )
+    );
+    Ok(())
+}
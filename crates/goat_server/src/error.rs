@@ -1,8 +1,9 @@
 use std::convert::Infallible;
 
+use serde::Serialize;
 use thiserror::Error;
-use warp::http::{Response, StatusCode};
-use warp::reject::Reject;
+use warp::http::StatusCode;
+use warp::reject::{InvalidHeader, MissingCookie, Reject};
 use warp::{Rejection, Reply};
 
 use goat_api::GoatError;
@@ -14,22 +15,153 @@ pub enum Error {
         #[from]
         error: GoatError,
     },
+    #[error("Unknown API key")]
+    UnknownApiKey,
+    #[error("Too many requests with this API key")]
+    RateLimited,
+    #[error("A duplicate set needs between {min} and {max} tables")]
+    InvalidTables { min: usize, max: usize },
 }
 
 impl Reject for Error {}
 
+/// Failures that aren't about the rules, serialized the same way as a [`GoatError`].
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "code")]
+#[serde(rename_all = "camelCase")]
+enum RequestError {
+    #[error("{message}")]
+    Unauthorized { message: String },
+    #[error("Not found")]
+    NotFound,
+    #[error("{message}")]
+    BadRequest { message: String },
+    #[error("{message}")]
+    RateLimited { message: String },
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+/// The body of an error response: the error's code and fields, and an English message.
+#[derive(Serialize)]
+struct ErrorBody<'a, E> {
+    #[serde(flatten)]
+    error: &'a E,
+    message: String,
+}
+
+impl<'a, E: std::error::Error> ErrorBody<'a, E> {
+    fn new(error: &'a E) -> Self {
+        Self {
+            error,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// The status of a response to a request that broke the rules. Requests about games that don't
+/// exist aren't found, requests from users who can't take the action are forbidden, requests
+/// that are well formed but can't be taken in the current state of the game conflict with it,
+/// and anything else is a bad request.
+fn status(error: &GoatError) -> StatusCode {
+    use GoatError::*;
+    match error {
+        InvalidGame { .. } => StatusCode::NOT_FOUND,
+        InvalidPlayer { .. } | NoFreeShows => StatusCode::FORBIDDEN,
+        CannotDrawFromEmptyDeck
+        | CannotDrawMoreThanThreeCards
+        | CannotFinishIncompleteTrick
+        | CannotPickUpFromEmptyTrick
+        | CannotPlayFromEmptyDeck
+        | CannotPlayOnFinishedTrick
+        | CannotPlayRange { .. }
+        | CannotSloughOnEndedTrick
+        | GameNotFinished
+        | IllegalSlough { .. }
+        | InvalidAction
+        | InvalidNumberOfPlayers
+        | InvalidState { .. }
+        | MustMatchRank { .. }
+        | NotYourCard { .. }
+        | NotYourTurn { .. } => StatusCode::CONFLICT,
+        InvalidCard { .. }
+        | InvalidHex { .. }
+        | InvalidId { .. }
+        | InvalidNotation { .. }
+        | InvalidNumberOfDecks
+        | InvalidRange { .. }
+        | NoSuchPlayer { .. } => StatusCode::BAD_REQUEST,
+    }
+}
+
 pub async fn handle_error(err: Rejection) -> Result<impl Reply, Infallible> {
-    if let Some(err) = err.find::<Error>() {
-        let mut resp = Response::new(err.to_string());
-        *resp.status_mut() = StatusCode::BAD_REQUEST;
-        Ok(resp)
+    let reply = |body, status| Ok(warp::reply::with_status(warp::reply::json(&body), status));
+    let (error, status) = if let Some(error) = err.find::<Error>() {
+        let message = error.to_string();
+        match error {
+            Error::Rules { error } => {
+                return reply(
+                    serde_json::to_value(ErrorBody::new(error)).unwrap(),
+                    status(error),
+                );
+            }
+            Error::UnknownApiKey => (
+                RequestError::Unauthorized { message },
+                StatusCode::UNAUTHORIZED,
+            ),
+            Error::RateLimited => (
+                RequestError::RateLimited { message },
+                StatusCode::TOO_MANY_REQUESTS,
+            ),
+            Error::InvalidTables { .. } => (
+                RequestError::BadRequest { message },
+                StatusCode::BAD_REQUEST,
+            ),
+        }
+    } else if let Some(message) = unauthorized(&err) {
+        let message = message.to_string();
+        (
+            RequestError::Unauthorized { message },
+            StatusCode::UNAUTHORIZED,
+        )
     } else if err.is_not_found() {
-        let mut resp = Response::new(String::new());
-        *resp.status_mut() = StatusCode::NOT_FOUND;
-        Ok(resp)
+        (RequestError::NotFound, StatusCode::NOT_FOUND)
+    } else if let Some(message) = bad_request(&err) {
+        let message = message.to_string();
+        (
+            RequestError::BadRequest { message },
+            StatusCode::BAD_REQUEST,
+        )
+    } else {
+        let message = format!("{:?}", err);
+        let status = StatusCode::INTERNAL_SERVER_ERROR;
+        (RequestError::Internal { message }, status)
+    };
+    reply(
+        serde_json::to_value(ErrorBody::new(&error)).unwrap(),
+        status,
+    )
+}
+
+/// The reason warp rejected a request without the cookies that identify the user, if it did.
+fn unauthorized(err: &Rejection) -> Option<&dyn std::error::Error> {
+    if let Some(err) = err.find::<MissingCookie>() {
+        Some(err)
+    } else if let Some(err) = err.find::<InvalidHeader>() {
+        // Requests without any cookies are missing the header itself.
+        (err.name() == "cookie").then_some(err as _)
+    } else {
+        None
+    }
+}
+
+/// The reason warp rejected a malformed request, if it did.
+fn bad_request(err: &Rejection) -> Option<&dyn std::error::Error> {
+    if let Some(err) = err.find::<warp::reject::InvalidQuery>() {
+        Some(err)
+    } else if let Some(err) = err.find::<warp::body::BodyDeserializeError>() {
+        Some(err)
     } else {
-        let mut resp = Response::new(format!("{:?}", err));
-        *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-        Ok(resp)
+        None
     }
 }
@@ -1,5 +1,10 @@
+use std::convert::Infallible;
+use std::process::Command;
+
 use futures_util::StreamExt;
 use rand::RngCore;
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::de::IntoDeserializer;
 use serde::Deserialize;
 use sha2::{Digest, Sha256};
 use tokio::time;
@@ -8,8 +13,10 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 use warp::{sse, Filter, Rejection, Reply};
 
 pub use error::*;
-use goat_api::{Action, GameId, GoatError, RandId, UserId};
-use goat_bot::{AdaptSimulate, Bot, Strategy};
+use goat_api::{
+    Action, Capability, GameId, GoatError, Protocol, RandId, UserId, MAX_PLAYERS, PROTOCOL_VERSION,
+};
+use goat_bot::{AdaptSimulate, Bot, External, Lifecycle, Policy};
 pub use server::*;
 pub use subscriber::*;
 
@@ -20,11 +27,48 @@ mod subscriber;
 #[cfg(test)]
 mod test;
 
+fn hash_secret(secret: &str) -> UserId {
+    let hash = Sha256::digest(secret.as_bytes());
+    UserId(RandId::from_hash(&hash))
+}
+
+/// Like [`hash_secret`] for API keys, but hashed apart from cookies so that a key can't be used as
+/// a cookie to get around its rate limit or rename its bot.
+fn hash_api_key(key: &str) -> UserId {
+    let hash = Sha256::new()
+        .chain_update(b"goat api key\0")
+        .chain_update(key.as_bytes())
+        .finalize();
+    UserId(RandId::from_hash(&hash))
+}
+
 fn user_id() -> impl Filter<Extract = (UserId,), Error = Rejection> + Clone {
-    warp::cookie("USER_SECRET").map(|id: String| {
-        let hash = Sha256::digest(id.as_bytes());
-        UserId(RandId::from_hash(&hash))
-    })
+    warp::cookie("USER_SECRET").map(|id: String| hash_secret(&id))
+}
+
+/// Bots send the API key they were given as a bearer token, and are known by the id and name it
+/// was registered with.
+fn api_key(
+    state: &'static Server,
+) -> impl Filter<Extract = (UserId, String), Error = Rejection> + Clone {
+    warp::header("authorization")
+        .and_then(move |authorization: String| async move {
+            let key = authorization
+                .strip_prefix("Bearer ")
+                .ok_or(Error::UnknownApiKey)?;
+            let user_id = hash_api_key(key);
+            let name = state.use_api_key(user_id)?;
+            Ok::<_, Rejection>((user_id, name))
+        })
+        .untuple_one()
+}
+
+/// The user making a request, whether a bot with an API key or a person with cookies.
+fn user(state: &'static Server) -> impl Filter<Extract = (UserId,), Error = Rejection> + Clone {
+    api_key(state)
+        .map(|user_id, _| user_id)
+        .or(user_id())
+        .unify()
 }
 
 fn user_name() -> impl Filter<Extract = (String,), Error = Rejection> + Clone {
@@ -57,6 +101,70 @@ fn new_game(
         .map(handle)
 }
 
+fn new_duplicate(
+    state: &'static Server,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    #[derive(Deserialize)]
+    struct Wrapper {
+        tables: usize,
+    }
+    async fn handle(state: &Server, Wrapper { tables }: Wrapper) -> Result<impl Reply, Rejection> {
+        if !(2..=MAX_PLAYERS).contains(&tables) {
+            let (min, max) = (2, MAX_PLAYERS);
+            return Err(Error::InvalidTables { min, max }.into());
+        }
+        let seed = rand::thread_rng().next_u64();
+        let game_ids = state.new_duplicate(seed, tables);
+        Ok(warp::reply::json(&game_ids))
+    }
+    warp::path!("new_duplicate")
+        .and(warp::post())
+        .and(warp::any().map(move || state))
+        .and(warp::query())
+        .and_then(handle)
+}
+
+fn duplicate_scores(
+    state: &'static Server,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    #[derive(Deserialize)]
+    struct Wrapper {
+        game_id: GameId,
+    }
+    async fn handle(state: &Server, Wrapper { game_id }: Wrapper) -> Result<impl Reply, Rejection> {
+        let scores = state.duplicate_scores(game_id).map_err(Error::from)?;
+        Ok(warp::reply::json(&scores))
+    }
+    warp::path!("duplicate_scores")
+        .and(warp::get())
+        .and(warp::any().map(move || state))
+        .and(warp::query())
+        .and_then(handle)
+}
+
+/// Gives the user a new API key for a bot called `name`. Keys are only kept in memory, so they
+/// stop working when the server restarts. Anyone with a cookie can make as many keys as they
+/// like, and the rate limit is per key, so it only stops a bot that sticks to one key.
+fn new_api_key(
+    state: &'static Server,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    #[derive(Deserialize)]
+    struct Wrapper {
+        name: String,
+    }
+    fn handle(state: &Server, _: UserId, Wrapper { name }: Wrapper) -> impl Reply {
+        let key = rand::random::<RandId>().to_string();
+        state.add_api_key(hash_api_key(&key), name);
+        warp::reply::json(&key)
+    }
+    warp::path!("new_api_key")
+        .and(warp::post())
+        .and(warp::any().map(move || state))
+        .and(user_id())
+        .and(warp::query())
+        .map(handle)
+}
+
 fn change_name(
     state: &'static Server,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
@@ -93,7 +201,7 @@ fn apply_action(
     warp::path!("apply_action")
         .and(warp::post())
         .and(warp::any().map(move || state))
-        .and(user_id())
+        .and(user(state))
         .and(warp::query())
         .and(warp::body::json())
         .and_then(handle)
@@ -102,38 +210,88 @@ fn apply_action(
 fn subscribe(
     state: &'static Server,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    fn handle(state: &Server, user_id: UserId, user_name: String) -> impl Reply {
-        let rx = state.subscribe(user_id, user_name);
-        let rx = UnboundedReceiverStream::new(rx);
+    /// Clients declare the version of the protocol they speak and a comma separated list of the
+    /// capabilities they understand. Clients from before the handshake only say whether they
+    /// want snapshots.
+    #[derive(Deserialize)]
+    struct Wrapper {
+        version: Option<u32>,
+        #[serde(default)]
+        capabilities: String,
+        #[serde(default)]
+        snapshot: bool,
+    }
+    async fn handle(
+        state: &Server,
+        user_id: UserId,
+        user_name: String,
+        wrapper: Wrapper,
+    ) -> Result<impl Reply, Rejection> {
+        let mut protocol = Protocol::legacy();
+        if let Some(version) = wrapper.version {
+            protocol.version = version;
+            protocol.capabilities = wrapper
+                .capabilities
+                .split(',')
+                .filter(|capability| !capability.is_empty())
+                .filter_map(|capability| {
+                    let de: StrDeserializer<ValueError> = capability.into_deserializer();
+                    Capability::deserialize(de).ok()
+                })
+                .collect();
+        } else if wrapper.snapshot {
+            protocol.capabilities.push(Capability::Snapshots);
+        }
+        let rx = UnboundedReceiverStream::new(state.subscribe(user_id, user_name, protocol));
         let stream = rx
             .map(|response| Ok::<_, GoatError>(sse::Event::default().json_data(response).unwrap()));
-        warp::reply::with_header(
+        Ok(warp::reply::with_header(
             sse::reply(stream),
             "Set-Cookie",
             format!("USER_ID={}", user_id),
-        )
+        ))
     }
     warp::path!("subscribe")
         .and(warp::get())
         .and(warp::any().map(move || state))
-        .and(user_id())
-        .and(user_name())
-        .map(handle)
+        .and(api_key(state).or(user_id().and(user_name())).unify())
+        .and(warp::query())
+        .and_then(handle)
+}
+
+/// Every route the server answers.
+fn app(state: &'static Server) -> impl Filter<Extract = impl Reply, Error = Infallible> + Clone {
+    root()
+        .or(assets())
+        .or(new_game(state))
+        .or(new_duplicate(state))
+        .or(duplicate_scores(state))
+        .or(new_api_key(state))
+        .or(change_name(state))
+        .or(apply_action(state))
+        .or(subscribe(state))
+        .recover(handle_error)
 }
 
-fn run_bot<S: Strategy>(state: &'static Server, name: String, strategy: S) {
+fn run_bot<P: Policy>(state: &'static Server, name: String, policy: P, lifecycle: Lifecycle) {
     tokio::spawn(async move {
-        let hash = Sha256::digest(name.as_bytes());
-        let user_id = UserId(RandId::from_hash(&hash));
-        let rx = state.subscribe(user_id, name);
+        let user_id = hash_secret(&name);
+        state.add_bot(user_id, name.clone());
+        // Bots need replays to see their invites, so they don't take snapshots.
+        let protocol = Protocol {
+            version: PROTOCOL_VERSION,
+            capabilities: Vec::new(),
+        };
+        let rx = state.subscribe(user_id, name, protocol);
         let tx = move |user_id, game_id, action| state.apply_action(user_id, game_id, action);
-        let mut bot = Bot::new(user_id, rx, tx, strategy, |action| match action {
+        let sleep = |action| match action {
             Action::Slough { .. } | Action::Goat { .. } => Duration::from_millis(750),
             Action::PlayCard { .. } | Action::PlayTop => Duration::from_millis(1500),
             Action::PlayRun { .. } | Action::PickUp => Duration::from_secs(3),
             Action::Draw | Action::FinishTrick => Duration::from_millis(200),
             _ => Duration::ZERO,
-        });
+        };
+        let mut bot = Bot::new(user_id, rx, tx, policy, sleep, lifecycle);
         if let Err(e) = bot.run().await {
             log::error!("Bot {} failed: {}", user_id, e);
         }
@@ -165,22 +323,51 @@ async fn main() {
         }
     });
 
-    run_bot(state, "Alice (bot)".to_string(), AdaptSimulate);
-    run_bot(state, "Bob (bot)".to_string(), AdaptSimulate);
-    run_bot(state, "Carla (bot)".to_string(), AdaptSimulate);
-    run_bot(state, "Dimitri (bot)".to_string(), AdaptSimulate);
-    run_bot(state, "Eric (bot)".to_string(), AdaptSimulate);
-    run_bot(state, "Felicia (bot)".to_string(), AdaptSimulate);
-    run_bot(state, "George (bot)".to_string(), AdaptSimulate);
-    run_bot(state, "Hannah (bot)".to_string(), AdaptSimulate);
+    tokio::spawn(async move {
+        let mut ticker = time::interval(Duration::from_millis(500));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = tokio::task::spawn_blocking(|| state.update_goat_meters()).await {
+                log::error!("Failed to update goat meters: {}", e);
+            }
+        }
+    });
+
+    let lifecycle = Lifecycle {
+        noises: std::fs::read_dir("./assets/noises/").map_or(0, |dir| dir.count()),
+        ..Lifecycle::default()
+    };
+    for name in [
+        "Alice", "Bob", "Carla", "Dimitri", "Eric", "Felicia", "George", "Hannah",
+    ] {
+        run_bot(
+            state,
+            format!("{} (bot)", name),
+            AdaptSimulate,
+            lifecycle.clone(),
+        );
+    }
 
-    let app = root()
-        .or(assets())
-        .or(new_game(state))
-        .or(change_name(state))
-        .or(apply_action(state))
-        .or(subscribe(state))
-        .recover(handle_error)
-        .with(warp::log("request"));
+    // External bots are given as `Name=program args...`, separated by semicolons.
+    let external_bots = std::env::var("GOAT_EXTERNAL_BOTS").unwrap_or_default();
+    for bot in external_bots.split(';').filter(|bot| !bot.is_empty()) {
+        let Some((name, command)) = bot.split_once('=') else {
+            log::error!("External bot {:?} has no name", bot);
+            continue;
+        };
+        let mut args = command.split_whitespace();
+        let Some(program) = args.next() else {
+            log::error!("External bot {} has no command", name);
+            continue;
+        };
+        let mut command = Command::new(program);
+        command.args(args);
+        match External::spawn(&mut command, Duration::from_secs(10), AdaptSimulate) {
+            Ok(policy) => run_bot(state, format!("{} (bot)", name), policy, lifecycle.clone()),
+            Err(e) => log::error!("Failed to start external bot {}: {}", name, e),
+        }
+    }
+
+    let app = app(state).with(warp::log("request"));
     warp::serve(app).run(([127, 0, 0, 1], 9402)).await;
 }
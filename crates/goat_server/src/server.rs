@@ -1,5 +1,6 @@
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use parking_lot::{Mutex, RwLock};
@@ -7,18 +8,49 @@ use smallvec::SmallVec;
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::UnboundedReceiver;
 
-use goat_api::{Action, Event, GameId, GoatError, Response, ServerGame, ServerPhase, User, UserId};
+use goat_api::{
+    score_duplicate, Action, Capability, Cards, ClientGame, ClientPhase, ClientRummyHand, Event,
+    GameId, GoatError, Protocol, Response, RummyHand, RummyPhase, SeatScore, ServerGame,
+    ServerPhase, User, UserId,
+};
+use goat_bot::goat_chances;
 
-use crate::Subscriber;
+use crate::{Error, Subscriber};
+
+/// The most random games played out to update a game's goat meter.
+const GOAT_METER_SIMULATIONS: u64 = 2000;
+
+/// The longest time spent updating a game's goat meter.
+const GOAT_METER_TIME: Duration = Duration::from_millis(250);
+
+/// How many requests a bot can make at once with its API key.
+const API_KEY_BURST: f64 = 20.0;
+
+/// How many requests a second a bot can keep making with its API key.
+const API_KEY_RATE: f64 = 5.0;
 
 pub struct Server {
     games: RwLock<HashMap<GameId, Mutex<(ServerGame, Instant)>>>,
     users: Mutex<HashMap<UserId, ServerUser>>,
+    /// The tables of every duplicate set, by the id of each of its tables.
+    duplicates: RwLock<HashMap<GameId, Arc<[GameId]>>>,
+    /// The games in the rummy phase that have changed since their goat meter was last updated.
+    stale_meters: Mutex<HashSet<GameId>>,
+    api_keys: Mutex<HashMap<UserId, ApiKey>>,
 }
 
 struct ServerUser {
     name: String,
     subs: SmallVec<[Subscriber; 1]>,
+    bot: bool,
+}
+
+/// A bot's API key, known only by the user id it hashes to.
+struct ApiKey {
+    name: String,
+    /// How many more requests the bot can make right now.
+    tokens: f64,
+    refilled: Instant,
 }
 
 impl Default for Server {
@@ -26,6 +58,9 @@ impl Default for Server {
         Self {
             games: RwLock::new(HashMap::new()),
             users: Mutex::new(HashMap::new()),
+            duplicates: RwLock::new(HashMap::new()),
+            stale_meters: Mutex::new(HashSet::new()),
+            api_keys: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -33,24 +68,97 @@ impl Default for Server {
 impl Server {
     pub fn new_game(&self, seed: u64) -> GameId {
         let game_id = GameId(rand::random());
+        let game = ServerGame::with_seed(seed);
+        let events = game.events.clone();
         let mut games = self.games.write();
-        games.insert(
-            game_id,
-            Mutex::new((ServerGame::with_seed(seed), Instant::now())),
-        );
+        games.insert(game_id, Mutex::new((game, Instant::now())));
         let mut users = self.users.lock();
         broadcast(
             &mut users,
-            [Response::Replay {
-                game_id,
-                events: Vec::new(),
-            }]
-            .iter()
-            .cloned(),
+            [Response::Replay { game_id, events }].iter().cloned(),
         );
         game_id
     }
 
+    /// Creates a duplicate set of `tables` games that are all dealt from `seed`. Players can't add
+    /// entropy to them, since that would deal each table differently, and none of them reveal the
+    /// seed until they have all finished.
+    pub fn new_duplicate(&self, seed: u64, tables: usize) -> Vec<GameId> {
+        let game_ids: Vec<_> = (0..tables).map(|_| GameId(rand::random())).collect();
+        let set: Arc<[GameId]> = game_ids.clone().into();
+        let mut responses = Vec::with_capacity(tables + 1);
+        // The set is registered before any of its games can be seen, so that nobody can add
+        // entropy to one of them in between.
+        let mut games = self.games.write();
+        let mut duplicates = self.duplicates.write();
+        for &game_id in &game_ids {
+            let mut game = ServerGame::with_seed(seed);
+            game.withhold_seed = true;
+            let events = game.events.clone();
+            games.insert(game_id, Mutex::new((game, Instant::now())));
+            duplicates.insert(game_id, set.clone());
+            responses.push(Response::Replay { game_id, events });
+        }
+        drop(duplicates);
+        responses.push(Response::DuplicateSet {
+            game_ids: game_ids.clone(),
+        });
+        let mut users = self.users.lock();
+        broadcast(&mut users, responses.into_iter());
+        game_ids
+    }
+
+    /// Reveals the seed at every table of the duplicate set `game_id` is a table of, once they
+    /// have all finished.
+    fn reveal_duplicate_seed(&self, game_id: GameId) {
+        let set = match self.duplicates.read().get(&game_id) {
+            Some(set) => set.clone(),
+            None => return,
+        };
+        let games = self.games.read();
+        let mut tables: Vec<_> = set
+            .iter()
+            .filter_map(|game_id| Some((*game_id, games.get(game_id)?.lock())))
+            .collect();
+        if tables
+            .iter()
+            .any(|(_, table)| !matches!(table.0.phase, ServerPhase::Goat(_)))
+        {
+            return;
+        }
+        let mut users = self.users.lock();
+        for (game_id, table) in &mut tables {
+            let game = &mut table.0;
+            let index = game.events.len();
+            if game.reveal_seed() {
+                broadcast_events(
+                    *game_id,
+                    game,
+                    &mut users,
+                    game.events[index..].iter().cloned(),
+                );
+            }
+        }
+    }
+
+    /// The scores of the duplicate set `game_id` is a table of, once every table has finished.
+    pub fn duplicate_scores(&self, game_id: GameId) -> Result<Vec<SeatScore>, GoatError> {
+        let set = match self.duplicates.read().get(&game_id) {
+            Some(set) => set.clone(),
+            None => return Err(GoatError::InvalidGame { game_id }),
+        };
+        let games = self.games.read();
+        let tables = set
+            .iter()
+            .map(|game_id| match games.get(game_id) {
+                Some(game) => Ok(game.lock()),
+                None => Err(GoatError::InvalidGame { game_id: *game_id }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let tables: Vec<_> = tables.iter().map(|table| &table.0).collect();
+        score_duplicate(&tables)
+    }
+
     pub fn change_name(&self, user_id: UserId, name: String) {
         let mut users = self.users.lock();
         let result = match users.entry(user_id) {
@@ -58,7 +166,7 @@ impl Server {
                 let user = e.into_mut();
                 if user.name != name {
                     user.name = name.clone();
-                    Some(!user.subs.is_empty())
+                    Some((!user.subs.is_empty(), user.bot))
                 } else {
                     None
                 }
@@ -67,16 +175,17 @@ impl Server {
                 e.insert(ServerUser {
                     name: name.clone(),
                     subs: SmallVec::new(),
+                    bot: false,
                 });
-                Some(false)
+                Some((false, false))
             }
         };
-        if let Some(online) = result {
+        if let Some((online, bot)) = result {
             broadcast(
                 &mut users,
                 [Response::User {
                     user_id,
-                    user: User { name, online },
+                    user: User { name, online, bot },
                 }]
                 .iter()
                 .cloned(),
@@ -84,22 +193,91 @@ impl Server {
         }
     }
 
+    /// Marks `user_id` as a bot called `name`.
+    pub fn add_bot(&self, user_id: UserId, name: String) {
+        let mut users = self.users.lock();
+        let user = users.entry(user_id).or_insert_with(|| ServerUser {
+            name: String::new(),
+            subs: SmallVec::new(),
+            bot: false,
+        });
+        if user.bot && user.name == name {
+            return;
+        }
+        user.bot = true;
+        user.name = name.clone();
+        let online = !user.subs.is_empty();
+        broadcast(
+            &mut users,
+            [Response::User {
+                user_id,
+                user: User {
+                    name,
+                    online,
+                    bot: true,
+                },
+            }]
+            .iter()
+            .cloned(),
+        );
+    }
+
+    /// Lets a bot called `name` connect with the API key that hashes to `user_id`.
+    pub fn add_api_key(&self, user_id: UserId, name: String) {
+        self.api_keys.lock().insert(
+            user_id,
+            ApiKey {
+                name: name.clone(),
+                tokens: API_KEY_BURST,
+                refilled: Instant::now(),
+            },
+        );
+        self.add_bot(user_id, name);
+    }
+
+    /// Counts a request made with the API key that hashes to `user_id` and returns the name of
+    /// its bot, unless there is no such key or it has made too many requests lately.
+    pub fn use_api_key(&self, user_id: UserId) -> Result<String, Error> {
+        let mut api_keys = self.api_keys.lock();
+        let key = api_keys.get_mut(&user_id).ok_or(Error::UnknownApiKey)?;
+        let now = Instant::now();
+        let refill = now.duration_since(key.refilled).as_secs_f64() * API_KEY_RATE;
+        key.tokens = (key.tokens + refill).min(API_KEY_BURST);
+        key.refilled = now;
+        if key.tokens < 1.0 {
+            return Err(Error::RateLimited);
+        }
+        key.tokens -= 1.0;
+        Ok(key.name.clone())
+    }
+
     pub fn apply_action(
         &self,
         user_id: UserId,
         game_id: GameId,
         action: Action,
     ) -> Result<(), GoatError> {
+        if matches!(action, Action::AddEntropy { .. })
+            && self.duplicates.read().contains_key(&game_id)
+        {
+            return Err(GoatError::InvalidAction);
+        }
         let games = self.games.read();
         let game = match games.get(&game_id) {
             Some(game) => game,
             None => return Err(GoatError::InvalidGame { game_id }),
         };
-        let (game, last_updated) = &mut *game.lock();
+        let mut guard = game.lock();
+        let (game, last_updated) = &mut *guard;
         let index = game.events.len();
         game.apply(user_id, action)?;
         log::debug!("state {:?}", game);
         *last_updated = Instant::now();
+        if matches!(action, Action::PlayRun { .. } | Action::PickUp)
+            && matches!(game.phase, ServerPhase::Rummy(_))
+        {
+            self.stale_meters.lock().insert(game_id);
+        }
         let mut users = self.users.lock();
         broadcast_events(
             game_id,
@@ -107,12 +285,36 @@ impl Server {
             &mut users,
             game.events[index..].iter().cloned(),
         );
+        let finished = game.withhold_seed && matches!(game.phase, ServerPhase::Goat(_));
+        drop(users);
+        drop(guard);
+        drop(games);
+        if finished {
+            self.reveal_duplicate_seed(game_id);
+        }
         Ok(())
     }
 
-    pub fn subscribe(&self, user_id: UserId, name: String) -> UnboundedReceiver<Response> {
+    /// Subscribes `user_id`, whose client speaks `protocol`, to every game and user. Clients
+    /// that speak a version with a handshake are first told what the server will use. Games in
+    /// progress are sent as snapshots of their current state if the client understands them, and
+    /// as a replay of every event otherwise.
+    pub fn subscribe(
+        &self,
+        user_id: UserId,
+        name: String,
+        protocol: Protocol,
+    ) -> UnboundedReceiver<Response> {
+        let protocol = Protocol::current().negotiate(&protocol);
+        let snapshots = protocol.supports(Capability::Snapshots);
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut sub = Subscriber::new(tx);
+        let mut sub = Subscriber::new(tx, protocol.clone());
+        if protocol.version > 0 {
+            sub.send(Response::Hello {
+                version: protocol.version,
+                capabilities: protocol.capabilities,
+            });
+        }
 
         let mut users = self.users.lock();
         for (&user_id, user) in users.iter() {
@@ -121,21 +323,28 @@ impl Server {
                 user: User {
                     name: user.name.clone(),
                     online: !user.subs.is_empty(),
+                    bot: user.bot,
                 },
             });
         }
         let user = users.entry(user_id).or_insert_with(|| ServerUser {
             name: String::new(),
             subs: SmallVec::new(),
+            bot: self.api_keys.lock().contains_key(&user_id),
         });
         user.subs.push(sub.clone());
         if name != user.name || user.subs.len() == 1 {
             user.name = name.clone();
+            let bot = user.bot;
             broadcast(
                 &mut users,
                 [Response::User {
                     user_id,
-                    user: User { name, online: true },
+                    user: User {
+                        name,
+                        online: true,
+                        bot,
+                    },
                 }]
                 .iter()
                 .cloned(),
@@ -147,15 +356,82 @@ impl Server {
         for (game_id, game) in &*games {
             let (game, _) = &*game.lock();
             let player = game.player(user_id).ok();
-            sub.send(Response::Replay {
-                game_id: *game_id,
-                events: game.events.iter().map(|e| e.redact(player)).collect(),
+            sub.send(if snapshots {
+                Response::Snapshot {
+                    game_id: *game_id,
+                    game: Box::new(game.snapshot(player)),
+                }
+            } else {
+                Response::Replay {
+                    game_id: *game_id,
+                    events: game.events.iter().map(|e| e.redact(player)).collect(),
+                }
             });
         }
+        for (game_id, set) in &*self.duplicates.read() {
+            if set[0] == *game_id && games.contains_key(game_id) {
+                sub.send(Response::DuplicateSet {
+                    game_ids: set.to_vec(),
+                });
+            }
+        }
         sub.finish_replay();
         rx
     }
 
+    /// Tells every user with no cards left to play who is likely to be goat in each game that has
+    /// changed since the last call, guessing from hand sizes alone and dropping stale estimates.
+    pub fn update_goat_meters(&self) {
+        let game_ids: Vec<_> = self.stale_meters.lock().drain().collect();
+        for game_id in game_ids {
+            let (rummy, applied) = {
+                let games = self.games.read();
+                let Some(game) = games.get(&game_id) else {
+                    continue;
+                };
+                let (game, _) = &*game.lock();
+                let Some(rummy) = goat_meter_view(game) else {
+                    continue;
+                };
+                (rummy, game.events.len())
+            };
+            let start = Instant::now();
+            let Some(chances) = goat_chances(&rummy, |count| {
+                count < GOAT_METER_SIMULATIONS && start.elapsed() < GOAT_METER_TIME
+            }) else {
+                log::error!("Failed to estimate the goat in {}", game_id);
+                continue;
+            };
+            let response = Response::GoatMeter {
+                game_id,
+                goat_chances: chances
+                    .into_iter()
+                    .map(|chance| (chance * 100.0).round() as u8)
+                    .collect(),
+            };
+            let games = self.games.read();
+            let Some(game) = games.get(&game_id) else {
+                continue;
+            };
+            let (game, _) = &*game.lock();
+            let ServerPhase::Rummy(rummy) = &game.phase else {
+                continue;
+            };
+            if game.events.len() != applied {
+                continue;
+            }
+            let mut users = self.users.lock();
+            broadcast_to(
+                &mut users,
+                |user_id| match game.player(user_id) {
+                    Ok(player) => rummy.hands[player.idx()].is_empty(),
+                    Err(_) => true,
+                },
+                [response].iter().cloned(),
+            );
+        }
+    }
+
     pub fn ping_subscribers(&self) {
         let mut users = self.users.lock();
         broadcast(&mut users, [Response::Ping].iter().cloned());
@@ -188,6 +464,9 @@ impl Server {
             }
             !drop
         });
+        self.duplicates
+            .write()
+            .retain(|game_id, _| games.contains_key(game_id));
         let mut users = self.users.lock();
         if !drops.is_empty() {
             broadcast(
@@ -217,12 +496,40 @@ impl Server {
     }
 }
 
+/// The rummy phase of `game` as the goat meter sees it, with every hand turned into a count of its
+/// cards so that the meter gives nothing away, or `None` if the game isn't in the rummy phase.
+pub fn goat_meter_view(game: &ServerGame) -> Option<RummyPhase<ClientRummyHand, Cards>> {
+    let view = ClientGame::<(), Cards>::from_snapshot(game.snapshot(None));
+    let ClientPhase::Rummy(mut rummy) = view.phase else {
+        return None;
+    };
+    for hand in &mut rummy.hands {
+        *hand = ClientRummyHand {
+            known: Cards::NONE,
+            unknown: hand.len() as u16,
+        };
+    }
+    Some(rummy)
+}
+
 fn broadcast(
     users: &mut HashMap<UserId, ServerUser>,
     responses: impl Iterator<Item = Response> + Clone,
+) {
+    broadcast_to(users, |_| true, responses);
+}
+
+/// Like [`broadcast`], but only to the users `to` accepts.
+fn broadcast_to(
+    users: &mut HashMap<UserId, ServerUser>,
+    to: impl Fn(UserId) -> bool,
+    responses: impl Iterator<Item = Response> + Clone,
 ) {
     let mut disconnects = Vec::new();
-    for (&user_id, user) in users.iter_mut().filter(|(_, user)| !user.subs.is_empty()) {
+    for (&user_id, user) in users
+        .iter_mut()
+        .filter(|(user_id, user)| !user.subs.is_empty() && to(**user_id))
+    {
         let mut i = 0;
         while i < user.subs.len() {
             let sub = &mut user.subs[i];
@@ -238,6 +545,7 @@ fn broadcast(
                 user: User {
                     name: user.name.clone(),
                     online: false,
+                    bot: user.bot,
                 },
             });
         }
@@ -276,6 +584,7 @@ fn broadcast_events(
                 user: User {
                     name: user.name.clone(),
                     online: false,
+                    bot: user.bot,
                 },
             });
         }
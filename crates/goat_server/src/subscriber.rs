@@ -4,28 +4,37 @@ use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::UnboundedSender;
 
-use goat_api::{GameId, Response};
+use goat_api::{GameId, Protocol, Response};
 
 #[derive(Clone)]
 pub struct Subscriber {
     tx: UnboundedSender<Response>,
+    protocol: Protocol,
     replayed: Option<Arc<Mutex<HashSet<GameId>>>>,
 }
 
 impl Subscriber {
-    pub fn new(tx: UnboundedSender<Response>) -> Self {
+    pub fn new(tx: UnboundedSender<Response>, protocol: Protocol) -> Self {
         Self {
             tx,
+            protocol,
             replayed: Some(Arc::new(Mutex::new(HashSet::new()))),
         }
     }
 
     pub fn disconnected() -> Self {
         let (tx, _) = mpsc::unbounded_channel();
-        Self { tx, replayed: None }
+        Self {
+            tx,
+            protocol: Protocol::legacy(),
+            replayed: None,
+        }
     }
 
     pub fn send(&mut self, response: Response) -> bool {
+        let Some(response) = self.protocol.downgrade(response) else {
+            return !self.tx.is_closed();
+        };
         if let Some(replayed) = &mut self.replayed {
             match &response {
                 Response::Game { game_id, .. } => {
@@ -34,7 +43,7 @@ impl Subscriber {
                         return true;
                     }
                 }
-                Response::Replay { game_id, .. } => {
+                Response::Replay { game_id, .. } | Response::Snapshot { game_id, .. } => {
                     let mut replayed = replayed.lock().unwrap();
                     replayed.insert(*game_id);
                 }
@@ -1,24 +1,41 @@
 use std::collections::HashMap;
+use std::iter;
 use std::sync::Arc;
 use std::time::Duration;
 
 use log::LevelFilter;
 use rand::RngCore;
+use sha2::{Digest, Sha256};
 use tokio::time::timeout;
+use warp::Filter;
 
 use goat_api::{
-    Action, Card, Client, ClientGame, ClientPhase, Event, GoatError, Response, User, UserId,
+    seating, Action, Capability, Card, Cards, Client, ClientGame, ClientPhase, Event, GameId,
+    GoatError, Position, Protocol, RandId, Response, User, UserId, WarTrick, PROTOCOL_VERSION,
 };
-use goat_bot::{Bot, CoverSimple, DuckSimple, PlayTopSimple, Strategy};
+use goat_bot::{Bot, CoverSimple, DuckSimple, Lifecycle, PlayTopSimple, Policy};
+use goat_client::{Connection, Credentials};
 
-use crate::Server;
+use crate::{
+    app, apply_action, goat_meter_view, handle_error, hash_api_key, hash_secret, new_api_key,
+    new_duplicate, Server,
+};
+
+fn run_bot<P: Policy>(state: Arc<Server>, name: String, policy: P) -> UserId {
+    run_bot_with(state, name, policy, Lifecycle::default())
+}
 
-fn run_bot<S: Strategy>(state: Arc<Server>, name: String, strategy: S) -> UserId {
+fn run_bot_with<P: Policy>(
+    state: Arc<Server>,
+    name: String,
+    policy: P,
+    lifecycle: Lifecycle,
+) -> UserId {
     let user_id = UserId(rand::random());
-    let rx = state.subscribe(user_id, name);
+    let rx = state.subscribe(user_id, name, Protocol::legacy());
     tokio::spawn(async move {
         let tx = move |user_id, game_id, action| state.apply_action(user_id, game_id, action);
-        let mut bot = Bot::new(user_id, rx, tx, strategy, |_| Duration::ZERO);
+        let mut bot = Bot::new(user_id, rx, tx, policy, |_| Duration::ZERO, lifecycle);
         if let Err(e) = bot.run().await {
             log::error!("Bot {} failed: {}", user_id, e);
         }
@@ -82,14 +99,15 @@ macro_rules! pick_up {
 async fn test_play_top_deterministic() -> Result<(), GoatError> {
     let server = Arc::new(Server::default());
     let watcher = UserId(rand::random());
-    let mut rx = server.subscribe(watcher, "watcher".to_string());
+    let mut rx = server.subscribe(watcher, "watcher".to_string(), Protocol::legacy());
     expect!(
         rx,
         Response::User {
             user_id: watcher,
             user: User {
                 name: "watcher".to_string(),
-                online: true
+                online: true,
+                bot: false
             },
         }
     );
@@ -102,21 +120,24 @@ async fn test_play_top_deterministic() -> Result<(), GoatError> {
             user_id: cover,
             user: User {
                 name: "cover".to_string(),
-                online: true
+                online: true,
+                bot: false
             },
         },
         Response::User {
             user_id: duck,
             user: User {
                 name: "duck".to_string(),
-                online: true
+                online: true,
+                bot: false
             },
         },
         Response::User {
             user_id: top,
             user: User {
                 name: "top".to_string(),
-                online: true
+                online: true,
+                bot: false
             },
         }
     );
@@ -234,7 +255,7 @@ async fn test_bots() -> Result<(), GoatError> {
         .try_init();
     let server = Arc::new(Server::default());
     let watcher = UserId(rand::random());
-    let mut rx = server.subscribe(watcher, "watcher".to_string());
+    let mut rx = server.subscribe(watcher, "watcher".to_string(), Protocol::legacy());
     let mut client: Client<(), (), ()> = Client::new(());
     let cover = run_bot(server.clone(), "cover".to_string(), CoverSimple);
     let duck = run_bot(server.clone(), "duck".to_string(), DuckSimple);
@@ -266,3 +287,495 @@ async fn test_bots() -> Result<(), GoatError> {
     log::info!("Goats: {:?}", goat_count);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_bot_lifecycle() -> Result<(), GoatError> {
+    let server = Arc::new(Server::default());
+    let watcher = UserId(rand::random());
+    let mut rx = server.subscribe(watcher, "watcher".to_string(), Protocol::legacy());
+    let mut client: Client<(), (), ()> = Client::new(());
+    let lifecycle = Lifecycle {
+        auto_start: Some((3, 1)),
+        noises: 1,
+        ..Lifecycle::default()
+    };
+    let bots = [
+        run_bot_with(
+            server.clone(),
+            "a".to_string(),
+            PlayTopSimple,
+            lifecycle.clone(),
+        ),
+        run_bot_with(
+            server.clone(),
+            "b".to_string(),
+            PlayTopSimple,
+            lifecycle.clone(),
+        ),
+        run_bot_with(
+            server.clone(),
+            "c".to_string(),
+            PlayTopSimple,
+            lifecycle.clone(),
+        ),
+    ];
+    let game_id = server.new_game(rand::thread_rng().next_u64());
+    for user_id in bots {
+        server.apply_action(watcher, game_id, Action::Invite { user_id })?;
+    }
+    loop {
+        let response = timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        client.apply(response)?;
+        if let Some(ClientGame {
+            phase: ClientPhase::Goat(goat),
+            players,
+        }) = client.games.get(&game_id)
+        {
+            if goat.noise == Some(0) {
+                let mut players = players.clone();
+                players.sort_by_key(|id| id.to_string());
+                let mut bots = bots.to_vec();
+                bots.sort_by_key(|id| id.to_string());
+                assert_eq!(players, bots);
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_subscribe_snapshot() -> Result<(), GoatError> {
+    let server = Server::default();
+    let players = [(); 3].map(|_| UserId(rand::random()));
+    let game_id = server.new_game(rand::thread_rng().next_u64());
+    for user_id in players {
+        server.apply_action(user_id, game_id, Action::Join { user_id })?;
+    }
+    server.apply_action(players[0], game_id, Action::Start { num_decks: 1 })?;
+    for user_id in players {
+        server.apply_action(user_id, game_id, Action::Draw)?;
+        server.apply_action(user_id, game_id, Action::Draw)?;
+    }
+    server.apply_action(players[0], game_id, Action::PlayTop)?;
+
+    let mut replayed: Client<(), Option<WarTrick>, Cards> = Client::new(());
+    let mut snapshotted: Client<(), Option<WarTrick>, Cards> = Client::new(());
+    let mut replay_rx = server.subscribe(players[1], "replay".to_string(), Protocol::legacy());
+    let mut snapshot_rx = server.subscribe(players[1], "snapshot".to_string(), Protocol::current());
+    while let Ok(response) = replay_rx.try_recv() {
+        assert!(!matches!(response, Response::Snapshot { .. }));
+        replayed.apply(response)?;
+    }
+    while let Ok(response) = snapshot_rx.try_recv() {
+        assert!(!matches!(response, Response::Replay { .. }));
+        snapshotted.apply(response)?;
+    }
+    assert_eq!(snapshotted.games, replayed.games);
+
+    server.apply_action(players[2], game_id, Action::Draw)?;
+    while let Ok(response) = replay_rx.try_recv() {
+        replayed.apply(response)?;
+    }
+    while let Ok(response) = snapshot_rx.try_recv() {
+        snapshotted.apply(response)?;
+    }
+    assert_eq!(snapshotted.games, replayed.games);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subscribe_handshake() -> Result<(), GoatError> {
+    let server = Server::default();
+    let user_id = UserId(rand::random());
+    let mut legacy_rx = server.subscribe(user_id, "legacy".to_string(), Protocol::legacy());
+    assert!(!matches!(
+        legacy_rx.recv().await,
+        Some(Response::Hello { .. })
+    ));
+
+    let newer = Protocol {
+        version: PROTOCOL_VERSION + 1,
+        capabilities: vec![Capability::Unknown, Capability::Snapshots],
+    };
+    let mut newer_rx = server.subscribe(user_id, "newer".to_string(), newer);
+    expect!(
+        newer_rx,
+        Response::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: vec![Capability::Snapshots],
+        }
+    );
+
+    let without_snapshots = Protocol {
+        version: PROTOCOL_VERSION,
+        capabilities: Vec::new(),
+    };
+    let mut rx = server.subscribe(user_id, "plain".to_string(), without_snapshots);
+    expect!(
+        rx,
+        Response::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: Vec::new(),
+        }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_duplicate_tables() -> Result<(), GoatError> {
+    let server = Arc::new(Server::default());
+    let watcher = UserId(rand::random());
+    let bots = [
+        run_bot(server.clone(), "cover".to_string(), CoverSimple),
+        run_bot(server.clone(), "duck".to_string(), DuckSimple),
+        run_bot(server.clone(), "top".to_string(), PlayTopSimple),
+    ];
+    let tables = server.new_duplicate(rand::thread_rng().next_u64(), 3);
+    let mut rx = server.subscribe(watcher, "watcher".to_string(), Protocol::current());
+    let set = Response::DuplicateSet {
+        game_ids: tables.clone(),
+    };
+    assert!(iter::from_fn(|| rx.try_recv().ok()).any(|response| response == set));
+    for (table, game_id) in tables.iter().enumerate() {
+        for user_id in seating(&bots, table) {
+            server.apply_action(watcher, *game_id, Action::Join { user_id })?;
+        }
+        assert_eq!(
+            server.apply_action(
+                bots[0],
+                *game_id,
+                Action::AddEntropy {
+                    entropy: rand::random()
+                }
+            ),
+            Err(GoatError::InvalidAction)
+        );
+        server.apply_action(watcher, *game_id, Action::Start { num_decks: 1 })?;
+    }
+    let scores = timeout(Duration::from_secs(5), async {
+        loop {
+            match server.duplicate_scores(tables[1]) {
+                Err(GoatError::GameNotFinished) => tokio::task::yield_now().await,
+                scores => return scores,
+            }
+        }
+    })
+    .await
+    .unwrap()?;
+    assert_eq!(scores.len(), 9);
+    for seat in 0..3 {
+        let matchpoints: f64 = scores
+            .iter()
+            .filter(|score| score.seat.idx() == seat)
+            .map(|score| score.matchpoints)
+            .sum();
+        assert_eq!(matchpoints, 3.0);
+    }
+    // Every table reveals the seed once the last one has finished.
+    let mut revealed = Vec::new();
+    timeout(Duration::from_secs(5), async {
+        while revealed.len() < tables.len() {
+            if let Some(Response::Game {
+                game_id,
+                event: Event::RevealSeed { .. },
+            }) = rx.recv().await
+            {
+                revealed.push(game_id);
+            }
+        }
+    })
+    .await
+    .unwrap();
+    revealed.sort_by_key(|game_id| tables.iter().position(|table| table == game_id));
+    assert_eq!(revealed, tables);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_duplicate_seed_withheld() -> Result<(), GoatError> {
+    let server = Arc::new(Server::default());
+    let watcher = UserId(rand::random());
+    let mut rx = server.subscribe(watcher, "watcher".to_string(), Protocol::current());
+    let bots = [
+        run_bot(server.clone(), "cover".to_string(), CoverSimple),
+        run_bot(server.clone(), "duck".to_string(), DuckSimple),
+        run_bot(server.clone(), "top".to_string(), PlayTopSimple),
+    ];
+    let tables = server.new_duplicate(rand::thread_rng().next_u64(), 2);
+    // The second table gets stuck as soon as it is the turn of a player who never plays.
+    let idle = UserId(rand::random());
+    for (game_id, players) in tables.iter().zip([bots, [bots[0], bots[1], idle]]) {
+        for user_id in players {
+            server.apply_action(watcher, *game_id, Action::Join { user_id })?;
+        }
+        server.apply_action(watcher, *game_id, Action::Start { num_decks: 1 })?;
+    }
+    let mut game = ClientGame::<(), ()>::default();
+    timeout(Duration::from_secs(5), async {
+        while !matches!(game.phase, ClientPhase::Goat(_)) {
+            let events = match rx.recv().await.unwrap() {
+                Response::Replay { game_id, events } if game_id == tables[0] => events,
+                Response::Game { game_id, event } => {
+                    assert!(!matches!(event, Event::RevealSeed { .. }));
+                    match game_id == tables[0] {
+                        true => vec![event],
+                        false => continue,
+                    }
+                }
+                _ => continue,
+            };
+            for event in events {
+                game.apply(event).unwrap();
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert!(matches!(
+        server.duplicate_scores(tables[0]),
+        Err(GoatError::GameNotFinished)
+    ));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(
+        iter::from_fn(|| rx.try_recv().ok()).all(|response| !matches!(
+            response,
+            Response::Game {
+                event: Event::RevealSeed { .. },
+                ..
+            }
+        ))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_goat_meter() -> Result<(), GoatError> {
+    let server = Arc::new(Server::default());
+    let watcher = UserId(rand::random());
+    let protocol = Protocol {
+        version: PROTOCOL_VERSION,
+        capabilities: Vec::new(),
+    };
+    let mut rx = server.subscribe(watcher, "watcher".to_string(), protocol);
+    let game_id = server.new_game(rand::thread_rng().next_u64());
+    for _ in 0..3 {
+        let user_id = UserId(rand::random());
+        let bot_rx = server.subscribe(user_id, "bot".to_string(), Protocol::legacy());
+        let state = server.clone();
+        tokio::spawn(async move {
+            let tx = move |user_id, game_id, action| state.apply_action(user_id, game_id, action);
+            // Rummy moves are slow enough for the meter to catch up with them.
+            let sleep = |action| match action {
+                Action::PlayRun { .. } | Action::PickUp => Duration::from_millis(500),
+                _ => Duration::ZERO,
+            };
+            let mut bot = Bot::new(
+                user_id,
+                bot_rx,
+                tx,
+                CoverSimple,
+                sleep,
+                Lifecycle::default(),
+            );
+            bot.run().await
+        });
+        server.apply_action(watcher, game_id, Action::Join { user_id })?;
+    }
+    server.apply_action(watcher, game_id, Action::Start { num_decks: 1 })?;
+    let goat_chances = timeout(Duration::from_secs(30), async {
+        loop {
+            match rx.recv().await.unwrap() {
+                Response::Game {
+                    event: Event::PlayRun { .. } | Event::PickUp,
+                    ..
+                } => server.update_goat_meters(),
+                Response::GoatMeter {
+                    game_id: meter_game_id,
+                    goat_chances,
+                } => {
+                    assert_eq!(meter_game_id, game_id);
+                    return goat_chances;
+                }
+                _ => {}
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(goat_chances.len(), 3);
+    let total: u32 = goat_chances.iter().map(|chance| *chance as u32).sum();
+    assert!((98..=102).contains(&total), "{:?}", goat_chances);
+    Ok(())
+}
+
+#[test]
+fn test_goat_meter_hides_hands() {
+    let position: Position = "hands [32C] [4C AS] [] [5C KS]\ntrump 9S\nnext P0"
+        .parse()
+        .unwrap();
+    let players = (0..4).map(|_| UserId(rand::random())).collect();
+    let game = position.server_game(players, 0).unwrap();
+    let rummy = goat_meter_view(&game).unwrap();
+    let counts: Vec<_> = rummy.hands.iter().map(|hand| hand.unknown).collect();
+    assert_eq!(counts, [2, 2, 0, 2]);
+    assert!(rummy.hands.iter().all(|hand| hand.known.is_empty()));
+}
+
+#[tokio::test]
+async fn test_real_client() -> Result<(), goat_client::Error> {
+    let server: &Server = Box::leak(Box::default());
+    let (addr, serving) = warp::serve(app(server)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(serving);
+    let url = format!("http://{}", addr);
+
+    let credentials = Credentials::random("person".to_string());
+    let person = Arc::new(Connection::new(&url, credentials.clone()));
+    let (person_id, mut rx) = person.subscribe(Protocol::current()).await?;
+    let Credentials::Cookie { secret, .. } = credentials else {
+        panic!("people have cookies");
+    };
+    assert_eq!(person_id, hash_secret(&secret));
+
+    let key = person.new_api_key("robot").await?;
+    let bot = Arc::new(Connection::new(&url, Credentials::ApiKey(key.clone())));
+    let (bot_id, _bot_rx) = bot.subscribe(Protocol::current()).await?;
+    assert_eq!(bot_id, hash_api_key(&key));
+
+    let game_id = person.new_game().await?;
+    person
+        .apply_action(game_id, Action::Join { user_id: person_id })
+        .await?;
+    bot.apply_action(game_id, Action::Join { user_id: bot_id })
+        .await?;
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let joined = Response::Game {
+                game_id,
+                event: Event::Join { user_id: bot_id },
+            };
+            if rx.recv().await.unwrap() == joined {
+                break;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert!(matches!(
+        bot.apply_action(game_id, Action::Start { num_decks: 0 })
+            .await,
+        Err(goat_client::Error::Rules { .. })
+    ));
+
+    let broken = Connection::new(&url, Credentials::random("new\nline".to_string()));
+    assert!(matches!(
+        broken.new_game().await,
+        Err(goat_client::Error::Request { .. })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_error_responses() {
+    let server: &Server = Box::leak(Box::default());
+    let filter = apply_action(server).recover(handle_error);
+    let game_id = server.new_game(rand::thread_rng().next_u64());
+    let user_id = UserId(RandId::from_hash(&Sha256::digest(b"player")));
+    server
+        .apply_action(user_id, game_id, Action::Join { user_id })
+        .unwrap();
+    let request = |game_id: String, secret: Option<&str>| {
+        let request = warp::test::request()
+            .method("POST")
+            .path(&format!("/apply_action?game_id={}", game_id))
+            .json(&Action::PlayTop);
+        match secret {
+            Some(secret) => request.header("Cookie", format!("USER_SECRET={}", secret)),
+            None => request,
+        }
+    };
+    let other_game = GameId(rand::random()).to_string();
+    for (request, status, code) in [
+        (request(game_id.to_string(), None), 401, "unauthorized"),
+        (request(other_game, Some("player")), 404, "invalidGame"),
+        (
+            request(game_id.to_string(), Some("other")),
+            403,
+            "invalidPlayer",
+        ),
+        (
+            request(game_id.to_string(), Some("player")),
+            409,
+            "invalidAction",
+        ),
+        (request("x".to_string(), Some("player")), 400, "badRequest"),
+    ] {
+        let response = request.reply(&filter).await;
+        assert_eq!(response.status(), status, "{:?}", response.body());
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], code);
+        assert!(body["message"].is_string());
+    }
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/new_duplicate?tables=1")
+        .reply(&new_duplicate(server).recover(handle_error))
+        .await;
+    assert_eq!(response.status(), 400, "{:?}", response.body());
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["code"], "badRequest");
+}
+
+#[tokio::test]
+async fn test_api_keys() -> Result<(), GoatError> {
+    let server: &Server = Box::leak(Box::default());
+    let filter = new_api_key(server)
+        .or(apply_action(server))
+        .recover(handle_error);
+    let watcher = UserId(rand::random());
+    let mut rx = server.subscribe(watcher, "watcher".to_string(), Protocol::legacy());
+    let response = warp::test::request()
+        .method("POST")
+        .path("/new_api_key?name=robot")
+        .header("Cookie", "USER_SECRET=owner")
+        .reply(&filter)
+        .await;
+    assert_eq!(response.status(), 200);
+    let key: String = serde_json::from_slice(response.body()).unwrap();
+    let bot = hash_api_key(&key);
+    assert_ne!(hash_secret(&key), bot);
+    loop {
+        if let Some(Response::User { user_id, user }) = rx.recv().await {
+            if user_id == bot {
+                assert_eq!(user.name, "robot");
+                assert!(user.bot);
+                break;
+            }
+        }
+    }
+
+    let game_id = server.new_game(rand::thread_rng().next_u64());
+    let request = |key: &str| {
+        warp::test::request()
+            .method("POST")
+            .path(&format!("/apply_action?game_id={}", game_id))
+            .header("Authorization", format!("Bearer {}", key))
+            .json(&Action::Join { user_id: bot })
+    };
+    let response = request("wrong").reply(&filter).await;
+    assert_eq!(response.status(), 401);
+    let response = request(&key).reply(&filter).await;
+    assert_eq!(response.status(), 200, "{:?}", response.body());
+    // Keep joining until the key runs out of requests.
+    for _ in 0..30 {
+        request(&key).reply(&filter).await;
+    }
+    let response = request(&key).reply(&filter).await;
+    assert_eq!(response.status(), 429);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["code"], "rateLimited");
+    Ok(())
+}
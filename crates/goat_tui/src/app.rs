@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use goat_api::{
+    Action, Card, Cards, ClientPhase, ClientWarHand, GameId, PlayerIdx, RandId, Response, User,
+    UserId, MAX_DECKS, MAX_PLAYERS, MIN_PLAYERS,
+};
+use goat_bot::legal_actions;
+
+pub type Session = goat_client::Session<HashMap<UserId, User>, (), Cards>;
+pub type ClientGame = goat_api::ClientGame<(), Cards>;
+
+/// What the terminal should do after a key is pressed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Command {
+    Act(GameId, Action),
+    NewGame,
+    Quit,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum View {
+    Lobby,
+    Game(GameId),
+}
+
+pub struct App {
+    pub session: Session,
+    pub view: View,
+    /// The game picked in the lobby.
+    pub selected: usize,
+    /// The card of the player's hand under the cursor.
+    pub cursor: usize,
+    /// The low card of the run being picked in the rummy phase.
+    pub lo: Option<Card>,
+    /// The user picked to be invited to an unstarted game.
+    pub invitee: usize,
+    pub num_decks: u8,
+    /// The last thing that went wrong, or that the player should know.
+    pub status: String,
+}
+
+impl App {
+    pub fn new(session: Session) -> Self {
+        Self {
+            session,
+            view: View::Lobby,
+            selected: 0,
+            cursor: 0,
+            lo: None,
+            invitee: 0,
+            num_decks: 1,
+            status: String::new(),
+        }
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.session.user_id
+    }
+
+    pub fn name(&self, user_id: UserId) -> &str {
+        self.session
+            .client
+            .users
+            .get(&user_id)
+            .map_or("?", |user| &user.name)
+    }
+
+    /// Every game, in a stable order.
+    pub fn games(&self) -> Vec<(GameId, &ClientGame)> {
+        let mut games: Vec<_> = self
+            .session
+            .client
+            .games
+            .iter()
+            .map(|(game_id, game)| (*game_id, game))
+            .collect();
+        games.sort_by_key(|(game_id, _)| game_id.to_string());
+        games
+    }
+
+    /// The users who are online, besides the player, in a stable order.
+    pub fn others(&self) -> Vec<UserId> {
+        let mut users: Vec<_> = self
+            .session
+            .client
+            .users
+            .iter()
+            .filter(|(user_id, user)| **user_id != self.user_id() && user.online)
+            .map(|(user_id, _)| *user_id)
+            .collect();
+        users.sort_by_key(|user_id| (self.name(*user_id).to_string(), user_id.to_string()));
+        users
+    }
+
+    pub fn game(&self) -> Option<(GameId, &ClientGame)> {
+        match self.view {
+            View::Lobby => None,
+            View::Game(game_id) => Some((game_id, self.session.client.games.get(&game_id)?)),
+        }
+    }
+
+    pub fn seat(&self, game: &ClientGame) -> Option<PlayerIdx> {
+        let idx = game.players.iter().position(|id| *id == self.user_id())?;
+        Some(PlayerIdx(idx as u8))
+    }
+
+    /// The cards the player holds in `game`, in the order they are shown.
+    pub fn hand(&self, game: &ClientGame) -> Vec<Card> {
+        let Some(seat) = self.seat(game) else {
+            return Vec::new();
+        };
+        let mut cards: Vec<_> = match &game.phase {
+            ClientPhase::War(war) => match &war.hands[seat.idx()] {
+                ClientWarHand::Visible(hand) => hand.cards().collect(),
+                ClientWarHand::Hidden(_) => Vec::new(),
+            },
+            ClientPhase::Rummy(rummy) => rummy.hands[seat.idx()].known.cards().collect(),
+            _ => Vec::new(),
+        };
+        cards.sort();
+        cards
+    }
+
+    /// Every action the player could legally take in `game` right now.
+    pub fn legal(&self, game: &ClientGame) -> Vec<Action> {
+        let seat = self.seat(game);
+        match (&game.phase, seat) {
+            (ClientPhase::Unstarted, None) => vec![Action::Join {
+                user_id: self.user_id(),
+            }],
+            (ClientPhase::Unstarted, Some(player)) => {
+                let mut actions = vec![
+                    Action::Leave { player },
+                    Action::AddEntropy {
+                        entropy: rand::random::<RandId>(),
+                    },
+                ];
+                if (MIN_PLAYERS..=MAX_PLAYERS).contains(&game.players.len()) {
+                    actions.push(Action::Start {
+                        num_decks: self.num_decks,
+                    });
+                }
+                actions.extend(self.others().into_iter().filter_map(|user_id| {
+                    (!game.players.contains(&user_id)).then_some(Action::Invite { user_id })
+                }));
+                actions
+            }
+            (ClientPhase::War(_) | ClientPhase::Rummy(_), Some(seat)) => legal_actions(game, seat),
+            (ClientPhase::Goat(goat), Some(seat)) if goat.goat == seat && goat.noise.is_none() => {
+                vec![Action::Goat { noise: 0 }]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether some legal action uses `card`, so it can be highlighted.
+    pub fn is_playable(&self, game: &ClientGame, card: Card) -> bool {
+        self.legal(game).iter().any(|action| match *action {
+            Action::PlayCard { card: c } | Action::Slough { card: c } => c == card,
+            Action::PlayRun { lo, hi } => match self.lo {
+                Some(marked) => lo == marked && hi == card,
+                None => lo == card,
+            },
+            _ => false,
+        })
+    }
+
+    pub fn receive(&mut self, response: Response) {
+        if let Err(e) = self.session.apply(response) {
+            self.status = format!("Out of sync: {}", e);
+        }
+        if let View::Game(game_id) = self.view {
+            if !self.session.client.games.contains_key(&game_id) {
+                self.view = View::Lobby;
+            }
+        }
+        self.selected = self.selected.min(self.games().len().saturating_sub(1));
+        let hand = self.game().map_or(Vec::new(), |(_, game)| self.hand(game));
+        self.cursor = self.cursor.min(hand.len().saturating_sub(1));
+        if self.lo.is_some_and(|lo| !hand.contains(&lo)) {
+            self.lo = None;
+        }
+    }
+
+    pub fn key(&mut self, key: KeyEvent) -> Option<Command> {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Some(Command::Quit);
+        }
+        match self.view {
+            View::Lobby => self.lobby_key(key.code),
+            View::Game(game_id) => self.game_key(game_id, key.code),
+        }
+    }
+
+    fn lobby_key(&mut self, code: KeyCode) -> Option<Command> {
+        let games = self.games().len();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Command::Quit),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(games.saturating_sub(1))
+            }
+            KeyCode::Char('n') => return Some(Command::NewGame),
+            KeyCode::Enter => {
+                if let Some((game_id, _)) = self.games().get(self.selected) {
+                    self.view = View::Game(*game_id);
+                    self.cursor = 0;
+                    self.lo = None;
+                    self.invitee = 0;
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn game_key(&mut self, game_id: GameId, code: KeyCode) -> Option<Command> {
+        let game = self.session.client.games.get(&game_id)?;
+        let hand = self.hand(game);
+        let card = hand.get(self.cursor).copied();
+        let legal = self.legal(game);
+        let action = match (&game.phase, code) {
+            (_, KeyCode::Char('q')) => return Some(Command::Quit),
+            (_, KeyCode::Esc) => {
+                self.view = View::Lobby;
+                return None;
+            }
+            (_, KeyCode::Left | KeyCode::Char('h')) => {
+                self.cursor = self.cursor.saturating_sub(1);
+                return None;
+            }
+            (_, KeyCode::Right | KeyCode::Char('l')) if !hand.is_empty() => {
+                self.cursor = (self.cursor + 1).min(hand.len() - 1);
+                return None;
+            }
+            (ClientPhase::Unstarted, code) => {
+                let others = self.others().len();
+                match code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.invitee = self.invitee.saturating_sub(1);
+                        return None;
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.invitee = (self.invitee + 1).min(others.saturating_sub(1));
+                        return None;
+                    }
+                    KeyCode::Char('+') => {
+                        self.num_decks = (self.num_decks + 1).min(MAX_DECKS as u8);
+                        return None;
+                    }
+                    KeyCode::Char('-') => {
+                        self.num_decks = (self.num_decks - 1).max(1);
+                        return None;
+                    }
+                    KeyCode::Char('J') => Action::Join {
+                        user_id: self.user_id(),
+                    },
+                    KeyCode::Char('L') => Action::Leave {
+                        player: self.seat(game)?,
+                    },
+                    KeyCode::Char('e') => legal
+                        .iter()
+                        .copied()
+                        .find(|action| matches!(action, Action::AddEntropy { .. }))?,
+                    KeyCode::Char('i') => Action::Invite {
+                        user_id: *self.others().get(self.invitee)?,
+                    },
+                    KeyCode::Char('s') => Action::Start {
+                        num_decks: self.num_decks,
+                    },
+                    _ => return None,
+                }
+            }
+            (ClientPhase::War(_), code) => match code {
+                KeyCode::Enter | KeyCode::Char('p') => Action::PlayCard { card: card? },
+                KeyCode::Char('s') => Action::Slough { card: card? },
+                KeyCode::Char('t') => Action::PlayTop,
+                KeyCode::Char('d') => Action::Draw,
+                KeyCode::Char('f') => Action::FinishTrick,
+                _ => return None,
+            },
+            (ClientPhase::Rummy(_), code) => match code {
+                KeyCode::Char(' ') => {
+                    self.lo = if self.lo.is_some() { None } else { card };
+                    return None;
+                }
+                KeyCode::Enter | KeyCode::Char('p') => {
+                    let hi = card?;
+                    let lo = self.lo.unwrap_or(hi);
+                    Action::PlayRun {
+                        lo: lo.min(hi),
+                        hi: lo.max(hi),
+                    }
+                }
+                KeyCode::Char('u') => Action::PickUp,
+                _ => return None,
+            },
+            (ClientPhase::Goat(_), KeyCode::Char('g')) => Action::Goat { noise: 0 },
+            _ => return None,
+        };
+        if !legal.contains(&action) {
+            self.status = format!("Can't {} right now", describe(action));
+            return None;
+        }
+        self.status.clear();
+        self.lo = None;
+        Some(Command::Act(game_id, action))
+    }
+}
+
+/// What taking `action` does, in words.
+pub fn describe(action: Action) -> String {
+    match action {
+        Action::Join { .. } => "join".into(),
+        Action::Leave { .. } => "leave".into(),
+        Action::Invite { .. } => "invite".into(),
+        Action::AddEntropy { .. } => "add entropy".into(),
+        Action::Start { num_decks } => format!("start with {} decks", num_decks),
+        Action::PlayCard { card } => format!("play {}", card),
+        Action::PlayTop => "play from the top".into(),
+        Action::Slough { card } => format!("slough {}", card),
+        Action::Draw => "draw".into(),
+        Action::FinishTrick => "finish the trick".into(),
+        Action::PlayRun { lo, hi } if lo == hi => format!("play {}", lo),
+        Action::PlayRun { lo, hi } => format!("play {}-{}", lo, hi),
+        Action::PickUp => "pick up".into(),
+        Action::Goat { .. } => "make a goat noise".into(),
+    }
+}
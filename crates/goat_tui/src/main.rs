@@ -0,0 +1,111 @@
+//! Plays goat in a terminal.
+//!
+//! Usage: `goat_tui [url] [name]`
+//!
+//! Connects to the server at `url`, `http://127.0.0.1:9402` by default, as a person called
+//! `name`. The person is known by the secret in `GOAT_SECRET`, or a new one each time if it isn't
+//! set. With `GOAT_API_KEY` set, plays as the bot the key was made for instead.
+
+use std::io;
+use std::sync::Arc;
+use std::thread;
+
+use crossterm::event::{self, Event, KeyEventKind};
+use tokio::sync::mpsc;
+
+use goat_api::Protocol;
+use goat_client::{Connection, Credentials};
+
+use crate::app::{describe, App, Command, Session};
+
+mod app;
+mod ui;
+
+#[cfg(test)]
+mod test;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let url = args
+        .next()
+        .unwrap_or_else(|| "http://127.0.0.1:9402".into());
+    let credentials = match std::env::var("GOAT_API_KEY") {
+        Ok(key) => Credentials::ApiKey(key),
+        Err(_) => {
+            let name = args
+                .next()
+                .or_else(|| std::env::var("USER").ok())
+                .unwrap_or_else(|| "Anonymous".into());
+            match std::env::var("GOAT_SECRET") {
+                Ok(secret) => Credentials::Cookie { secret, name },
+                Err(_) => Credentials::random(name),
+            }
+        }
+    };
+    let connection = Arc::new(Connection::new(&url, credentials));
+    let (user_id, rx) = connection.subscribe(Protocol::current()).await?;
+
+    let mut terminal = ratatui::init();
+    let result = run(
+        &mut terminal,
+        App::new(Session::new(user_id)),
+        connection,
+        rx,
+    )
+    .await;
+    ratatui::restore();
+    result
+}
+
+async fn run(
+    terminal: &mut ratatui::DefaultTerminal,
+    mut app: App,
+    connection: Arc<Connection>,
+    mut rx: mpsc::UnboundedReceiver<goat_api::Response>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Reading the terminal blocks, so it gets a thread of its own.
+    let (keys_tx, mut keys) = mpsc::unbounded_channel();
+    thread::spawn(move || -> io::Result<()> {
+        loop {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && keys_tx.send(key).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    });
+    let (status_tx, mut statuses) = mpsc::unbounded_channel();
+    loop {
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+        tokio::select! {
+            response = rx.recv() => match response {
+                Some(response) => app.receive(response),
+                None => return Ok(()),
+            },
+            Some(key) = keys.recv() => match app.key(key) {
+                Some(Command::Quit) => return Ok(()),
+                Some(Command::NewGame) => {
+                    let connection = connection.clone();
+                    let status_tx = status_tx.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = connection.new_game().await {
+                            let _ = status_tx.send(format!("Failed to make a game: {}", e));
+                        }
+                    });
+                }
+                Some(Command::Act(game_id, action)) => {
+                    let connection = connection.clone();
+                    let status_tx = status_tx.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = connection.apply_action(game_id, action).await {
+                            let _ = status_tx.send(format!("Failed to {}: {}", describe(action), e));
+                        }
+                    });
+                }
+                None => {}
+            },
+            Some(status) = statuses.recv() => app.status = status,
+        }
+    }
+}
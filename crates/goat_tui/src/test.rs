@@ -0,0 +1,152 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+use goat_api::{
+    Action, Card, Cards, ClientPhase, Event, GameId, PlayerIdx, Position, RandId, Response, User,
+    UserId,
+};
+
+use crate::app::{App, Command, Session, View};
+use crate::ui;
+
+fn user_id(n: u8) -> UserId {
+    UserId(RandId::from_hash(&[n; 16]))
+}
+
+fn game_id() -> GameId {
+    GameId(RandId::from_hash(&[0xee; 16]))
+}
+
+/// An app for the first of three players, looking at `position` from their seat.
+fn app(position: &str) -> App {
+    let mut app = App::new(Session::new(user_id(0)));
+    let users: Vec<_> = (0..3).map(user_id).collect();
+    for (n, user_id) in users.iter().enumerate() {
+        app.receive(Response::User {
+            user_id: *user_id,
+            user: User {
+                name: format!("Player {}", n),
+                online: true,
+                bot: false,
+            },
+        });
+    }
+    let position: Position = position.parse().unwrap();
+    let game = position.client_game(users, Some(PlayerIdx(0))).unwrap();
+    app.session.client.games.insert(game_id(), game);
+    assert_eq!(press(&mut app, KeyCode::Enter), None);
+    assert_eq!(app.view, View::Game(game_id()));
+    app
+}
+
+fn press(app: &mut App, code: KeyCode) -> Option<Command> {
+    app.key(KeyEvent::from(code))
+}
+
+fn act(action: Action) -> Option<Command> {
+    Some(Command::Act(game_id(), action))
+}
+
+fn render(app: &App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+    terminal.draw(|frame| ui::draw(frame, app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    buffer
+        .content()
+        .chunks(buffer.area.width as usize)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>() + "\n")
+        .collect()
+}
+
+#[test]
+fn war_keys_take_legal_actions() {
+    let dealt: Cards = "9H 2C AS KD 7H 8C QS 2D".parse().unwrap();
+    let won = Cards::ONE_DECK - dealt;
+    let position = format!(
+        "deck 9H 2C AS KD\nhands [7H 8C] [QS] [2D]\nwon {} [] []\nleader P0",
+        won
+    );
+    let mut app = app(&position);
+    let game = app.game().unwrap().1;
+    assert!(app.is_playable(game, Card::EightClubs));
+    assert_eq!(press(&mut app, KeyCode::Right), None);
+    assert_eq!(
+        press(&mut app, KeyCode::Enter),
+        act(Action::PlayCard {
+            card: Card::SevenHearts
+        })
+    );
+    assert_eq!(press(&mut app, KeyCode::Char('t')), act(Action::PlayTop));
+    assert_eq!(press(&mut app, KeyCode::Char('d')), act(Action::Draw));
+    assert_eq!(press(&mut app, KeyCode::Char('f')), None);
+    assert_eq!(app.status, "Can't finish the trick right now");
+
+    let ClientPhase::War(war) = &mut app.session.client.games.get_mut(&game_id()).unwrap().phase
+    else {
+        panic!("not a war position");
+    };
+    war.play_from_top(Card::KingDiamonds);
+    let screen = render(&app);
+    assert!(screen.contains("Deck: 3 cards"), "{}", screen);
+    assert!(
+        screen.contains("Player 0 played from the top KD"),
+        "{}",
+        screen
+    );
+}
+
+#[test]
+fn rummy_keys_play_runs() {
+    let mut app = app("hands [2C 3C] [4C] [5H]\ntrump 9H\nnext P0");
+    assert_eq!(press(&mut app, KeyCode::Char('u')), None);
+    assert_eq!(press(&mut app, KeyCode::Char(' ')), None);
+    assert_eq!(app.lo, Some(Card::TwoClubs));
+    assert_eq!(press(&mut app, KeyCode::Right), None);
+    assert_eq!(
+        press(&mut app, KeyCode::Enter),
+        act(Action::PlayRun {
+            lo: Card::TwoClubs,
+            hi: Card::ThreeClubs
+        })
+    );
+    assert_eq!(app.lo, None);
+    let screen = render(&app);
+    assert!(screen.contains("Trump: 9H"), "{}", screen);
+    assert!(screen.contains("u pick up"), "{}", screen);
+}
+
+#[test]
+fn lobby_keys_make_and_join_games() {
+    let mut app = App::new(Session::new(user_id(0)));
+    assert_eq!(press(&mut app, KeyCode::Char('n')), Some(Command::NewGame));
+    app.receive(Response::Replay {
+        game_id: game_id(),
+        events: vec![Event::Join {
+            user_id: user_id(1),
+        }],
+    });
+    assert_eq!(press(&mut app, KeyCode::Enter), None);
+    assert_eq!(press(&mut app, KeyCode::Char('s')), None);
+    assert_eq!(
+        press(&mut app, KeyCode::Char('J')),
+        act(Action::Join {
+            user_id: user_id(0)
+        })
+    );
+    app.receive(Response::Game {
+        game_id: game_id(),
+        event: Event::Join {
+            user_id: user_id(0),
+        },
+    });
+    assert_eq!(
+        press(&mut app, KeyCode::Char('L')),
+        act(Action::Leave {
+            player: PlayerIdx(1)
+        })
+    );
+    assert_eq!(press(&mut app, KeyCode::Esc), None);
+    assert_eq!(app.view, View::Lobby);
+    assert_eq!(press(&mut app, KeyCode::Char('q')), Some(Command::Quit));
+}
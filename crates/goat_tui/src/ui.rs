@@ -0,0 +1,261 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+
+use goat_api::{Action, Card, ClientPhase, Deck, RummyHand, Suit, WarHand, WarPlayKind};
+
+use crate::app::{App, ClientGame};
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [title, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let mut heading = vec![
+        "goat".bold(),
+        format!(" as {}", app.name(app.user_id())).into(),
+    ];
+    if !app.status.is_empty() {
+        heading.push(format!("  {}", app.status).yellow());
+    }
+    frame.render_widget(Line::from(heading), title);
+    match app.game() {
+        None => draw_lobby(frame, app, body, footer),
+        Some((_, game)) => draw_game(frame, app, game, body, footer),
+    }
+}
+
+fn draw_lobby(frame: &mut Frame, app: &App, body: Rect, footer: Rect) {
+    let items: Vec<ListItem> = app
+        .games()
+        .into_iter()
+        .map(|(game_id, game)| {
+            let players: Vec<_> = game.players.iter().map(|id| app.name(*id)).collect();
+            let mine = app.seat(game).is_some();
+            let line = Line::from(vec![
+                format!("{} ", game_id).dark_gray(),
+                format!("{:<9}", phase_name(game)).into(),
+                players.join(", ").into(),
+            ]);
+            ListItem::new(if mine { line.bold() } else { line })
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title("Games"))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, body, &mut state);
+    let hints = Line::from(vec![
+        hint("↑↓ pick", true),
+        hint("⏎ open", !app.games().is_empty()),
+        hint("n new game", true),
+        hint("q quit", true),
+    ]);
+    frame.render_widget(hints, footer);
+}
+
+fn phase_name(game: &ClientGame) -> &'static str {
+    match game.phase {
+        ClientPhase::Unstarted => "unstarted",
+        ClientPhase::War(_) => "war",
+        ClientPhase::Rummy(_) => "rummy",
+        ClientPhase::Goat(_) => "goat",
+    }
+}
+
+fn draw_game(frame: &mut Frame, app: &App, game: &ClientGame, body: Rect, footer: Rect) {
+    let [top, hand] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(body);
+    let [players, table] =
+        Layout::horizontal([Constraint::Length(36), Constraint::Min(0)]).areas(top);
+    frame.render_widget(
+        Paragraph::new(player_lines(app, game)).block(Block::bordered().title("Players")),
+        players,
+    );
+    frame.render_widget(
+        Paragraph::new(table_lines(app, game))
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(phase_name(game))),
+        table,
+    );
+    frame.render_widget(
+        Paragraph::new(hand_line(app, game)).block(Block::bordered().title("Your hand")),
+        hand,
+    );
+    frame.render_widget(hints(app, game), footer);
+}
+
+fn player_lines<'a>(app: &'a App, game: &ClientGame) -> Vec<Line<'a>> {
+    let next = match &game.phase {
+        ClientPhase::War(war) => war.trick.next_player(),
+        ClientPhase::Rummy(rummy) => Some(rummy.next),
+        _ => None,
+    };
+    game.players
+        .iter()
+        .enumerate()
+        .map(|(idx, user_id)| {
+            let mut spans = vec![
+                format!("P{} ", idx).dark_gray(),
+                Span::raw(app.name(*user_id)),
+            ];
+            if *user_id == app.user_id() {
+                spans.push(" (you)".dark_gray());
+            }
+            let counts = match &game.phase {
+                ClientPhase::War(war) => {
+                    format!("  hand {} won {}", war.hands[idx].len(), war.won[idx].len())
+                }
+                ClientPhase::Rummy(rummy) => format!("  hand {}", rummy.hands[idx].len()),
+                _ => String::new(),
+            };
+            spans.push(counts.into());
+            if next.is_some_and(|next| next.idx() == idx) {
+                spans.push(" ◀".cyan().bold());
+            }
+            if let ClientPhase::Goat(goat) = &game.phase {
+                if goat.goat.idx() == idx {
+                    spans.push(" 🐐 goat".red().bold());
+                }
+            }
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn table_lines<'a>(app: &'a App, game: &ClientGame) -> Vec<Line<'a>> {
+    let name = |idx: usize| game.players.get(idx).map_or("?", |id| app.name(*id));
+    let mut lines = Vec::new();
+    match &game.phase {
+        ClientPhase::Unstarted => {
+            lines.push(Line::from(format!("Decks: {}", app.num_decks)));
+            lines.push(Line::from("Invite:"));
+            for (idx, user_id) in app.others().into_iter().enumerate() {
+                let mut line = Line::from(format!("  {}", app.name(user_id)));
+                if game.players.contains(&user_id) {
+                    line = line.dark_gray();
+                }
+                if idx == app.invitee {
+                    line = line.reversed();
+                }
+                lines.push(line);
+            }
+        }
+        ClientPhase::War(war) => {
+            lines.push(Line::from(format!(
+                "Deck: {} cards",
+                war.deck.cards_remaining()
+            )));
+            let rank = war.trick.rank().map_or("-".into(), |rank| rank.to_string());
+            lines.push(Line::from(format!("Trick rank: {}", rank)));
+            for play in war.trick.plays() {
+                let kind = match play.kind() {
+                    WarPlayKind::PlayHand => "played",
+                    WarPlayKind::PlayTop => "played from the top",
+                    WarPlayKind::Slough => "sloughed",
+                };
+                lines.push(Line::from(vec![
+                    format!("  {} {} ", name(play.player().idx()), kind).into(),
+                    card_span(play.card),
+                ]));
+            }
+            if let Some(winner) = war.trick.winner() {
+                lines.push(Line::from(format!("{} wins the trick", name(winner.idx()))).bold());
+            }
+        }
+        ClientPhase::Rummy(rummy) => {
+            lines.push(Line::from(vec!["Trump: ".into(), card_span(rummy.trump)]));
+            lines.push(Line::from("Trick:"));
+            for (lo, hi) in rummy.trick.plays() {
+                let mut spans = vec![Span::raw("  "), card_span(*lo)];
+                if lo != hi {
+                    spans.push("-".into());
+                    spans.push(card_span(*hi));
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+        ClientPhase::Goat(goat) => {
+            lines.push(Line::from(format!("{} is the goat", name(goat.goat.idx()))).bold());
+            if goat.noise.is_some() {
+                lines.push(Line::from("Baaa!"));
+            }
+        }
+    }
+    lines
+}
+
+fn card_span(card: Card) -> Span<'static> {
+    let span = Span::raw(card.to_string());
+    match card.suit() {
+        Suit::Diamonds | Suit::Hearts => span.red(),
+        _ => span,
+    }
+}
+
+fn hand_line<'a>(app: &App, game: &ClientGame) -> Line<'a> {
+    let hand = app.hand(game);
+    let mut spans = Vec::new();
+    for (idx, card) in hand.into_iter().enumerate() {
+        let mut span = card_span(card);
+        span = if app.is_playable(game, card) {
+            span.bold().green()
+        } else {
+            span.dim()
+        };
+        if app.lo == Some(card) {
+            span = span.underlined();
+        }
+        if idx == app.cursor {
+            span = span.reversed();
+        }
+        spans.push(span);
+        spans.push(" ".into());
+    }
+    Line::from(spans)
+}
+
+fn hints(app: &App, game: &ClientGame) -> Line<'static> {
+    let legal = app.legal(game);
+    let any = |f: fn(&Action) -> bool| legal.iter().any(f);
+    let mut spans = match &game.phase {
+        ClientPhase::Unstarted => vec![
+            hint("J join", any(|a| matches!(a, Action::Join { .. }))),
+            hint("L leave", any(|a| matches!(a, Action::Leave { .. }))),
+            hint("e entropy", any(|a| matches!(a, Action::AddEntropy { .. }))),
+            hint("↑↓ i invite", any(|a| matches!(a, Action::Invite { .. }))),
+            hint("+- decks", true),
+            hint("s start", any(|a| matches!(a, Action::Start { .. }))),
+        ],
+        ClientPhase::War(_) => vec![
+            hint("←→ card", true),
+            hint("⏎ play", any(|a| matches!(a, Action::PlayCard { .. }))),
+            hint("s slough", any(|a| matches!(a, Action::Slough { .. }))),
+            hint("t top", legal.contains(&Action::PlayTop)),
+            hint("d draw", legal.contains(&Action::Draw)),
+            hint("f finish", legal.contains(&Action::FinishTrick)),
+        ],
+        ClientPhase::Rummy(_) => vec![
+            hint("←→ card", true),
+            hint("space mark", any(|a| matches!(a, Action::PlayRun { .. }))),
+            hint("⏎ play run", any(|a| matches!(a, Action::PlayRun { .. }))),
+            hint("u pick up", legal.contains(&Action::PickUp)),
+        ],
+        ClientPhase::Goat(_) => vec![hint("g goat noise", !legal.is_empty())],
+    };
+    spans.push(hint("esc lobby", true));
+    spans.push(hint("q quit", true));
+    Line::from(spans)
+}
+
+fn hint(label: &str, legal: bool) -> Span<'static> {
+    let style = if legal {
+        Style::new().fg(Color::Green)
+    } else {
+        Style::new().fg(Color::DarkGray)
+    };
+    Span::styled(format!("{}  ", label), style)
+}
@@ -5,7 +5,7 @@ use wasm_bindgen::prelude::*;
 
 use goat_api::{Response, User, UserId, WarTrick};
 
-use crate::{OneAction, Wrapper};
+use crate::{GameView, OneAction};
 
 #[wasm_bindgen]
 pub struct Client {
@@ -22,7 +22,10 @@ impl Client {
         }
     }
 
-    pub fn apply(&mut self, response: JsValue) -> Result<(), JsValue> {
+    pub fn apply(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "Response")] response: JsValue,
+    ) -> Result<(), JsValue> {
         let response: Response = serde_wasm_bindgen::from_value(response)?;
         self.client
             .apply(response)
@@ -30,15 +33,19 @@ impl Client {
         Ok(())
     }
 
-    pub fn game(&self, game_id: JsValue) -> Result<JsValue, JsValue> {
+    #[wasm_bindgen(unchecked_return_type = "GameView")]
+    pub fn game(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "GameId")] game_id: JsValue,
+    ) -> Result<JsValue, JsValue> {
         let game_id = serde_wasm_bindgen::from_value(game_id)?;
         match self.client.games.get(&game_id) {
-            Some(game) => Ok(serde_wasm_bindgen::to_value(&Wrapper(game))?),
+            Some(game) => Ok(serde_wasm_bindgen::to_value(&GameView::new(game))?),
             None => Err(JsValue::from(format!("Unknown game {}", game_id))),
         }
     }
 
-    #[wasm_bindgen(js_name = userIds)]
+    #[wasm_bindgen(js_name = userIds, unchecked_return_type = "UserId[]")]
     pub fn user_ids(&self) -> Result<Array, JsValue> {
         Ok(self
             .client
@@ -48,7 +55,11 @@ impl Client {
             .collect::<Result<_, _>>()?)
     }
 
-    pub fn user(&self, user_id: JsValue) -> Result<JsValue, JsValue> {
+    #[wasm_bindgen(unchecked_return_type = "User")]
+    pub fn user(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "UserId")] user_id: JsValue,
+    ) -> Result<JsValue, JsValue> {
         let user_id = serde_wasm_bindgen::from_value(user_id)?;
         match self.client.users.get(&user_id) {
             Some(user) => Ok(serde_wasm_bindgen::to_value(user)?),
@@ -2,12 +2,24 @@ use wasm_bindgen::prelude::*;
 
 pub use client::*;
 pub use game::*;
+pub use local_game::*;
 pub use one_action::*;
 
 mod client;
 mod game;
+mod local_game;
 mod one_action;
 
+#[cfg(test)]
+mod test;
+
+// The bindings name the types generated into the frontend's `src/protocol` instead of `any`.
+#[wasm_bindgen(typescript_custom_section)]
+const PROTOCOL_TYPES: &str = r#"
+import type { Action, GameId, Response, User, UserId } from "protocol/goat_api";
+import type { GameView } from "protocol/goat_wasm";
+"#;
+
 #[wasm_bindgen(start)]
 pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
@@ -0,0 +1,167 @@
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use goat_api::{Action, Cards, ClientGame, GameId, GoatError, PlayerIdx, RandId, Response, User};
+use goat_bot::{
+    AdaptSimple, AdaptSimulateN, Arena, CoverSimple, DuckSimple, PlayTopSimple, Policy,
+};
+
+/// The number of random games the simulating bot plays out per rummy decision. There's no clock
+/// in the browser, so this stands in for the server's time limit.
+const SIMULATIONS: u64 = 500;
+
+/// A game played entirely in the browser, between the player in seat 0 and bots in every other
+/// seat. Events are handed out as the same responses a server would send, so they can be applied
+/// to a [`crate::Client`].
+#[wasm_bindgen]
+pub struct LocalGame {
+    game_id: GameId,
+    arena: Arena,
+    policies: Box<[Box<dyn Policy>]>,
+}
+
+struct Human;
+
+impl Policy for Human {
+    fn action(&self, _: &ClientGame<(), Cards>, _: PlayerIdx) -> Option<Action> {
+        None
+    }
+}
+
+#[wasm_bindgen]
+impl LocalGame {
+    /// Starts a game with `num_players` seats, all but the first of them played by `bot`, which
+    /// is one of `playTop`, `duck`, `cover`, `simple` or `simulate`. The game is shuffled with
+    /// `seed`, or randomly if none is given.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        num_players: usize,
+        num_decks: u8,
+        bot: &str,
+        seed: Option<u64>,
+    ) -> Result<LocalGame, JsValue> {
+        let seed = match seed {
+            Some(seed) => seed,
+            None => {
+                let mut bytes = [0; 8];
+                getrandom::getrandom(&mut bytes).map_err(|e| JsValue::from(e.to_string()))?;
+                u64::from_le_bytes(bytes)
+            }
+        };
+        let arena = Arena::new(seed, num_players, num_decks)
+            .map_err(|e| JsValue::from(format!("Failed to start game: {}", e)))?;
+        let mut policies: Vec<Box<dyn Policy>> = vec![Box::new(Human)];
+        for _ in 1..num_players {
+            policies.push(match bot {
+                "playTop" => Box::new(PlayTopSimple),
+                "duck" => Box::new(DuckSimple),
+                "cover" => Box::new(CoverSimple),
+                "simple" => Box::new(AdaptSimple),
+                "simulate" => Box::new(AdaptSimulateN(SIMULATIONS)),
+                _ => return Err(JsValue::from(format!("Unknown bot {}", bot))),
+            });
+        }
+        let mut hash = [0; 16];
+        hash[..8].copy_from_slice(&seed.to_le_bytes());
+        Ok(Self {
+            game_id: GameId(RandId::from_hash(&hash)),
+            arena,
+            policies: policies.into_boxed_slice(),
+        })
+    }
+
+    #[wasm_bindgen(js_name = gameId, unchecked_return_type = "GameId")]
+    pub fn game_id(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.game_id)?)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.arena.game.seed
+    }
+
+    #[wasm_bindgen(js_name = numEvents)]
+    pub fn num_events(&self) -> usize {
+        self.arena.game.events.len()
+    }
+
+    /// A user response for every seat, naming the player and the bots.
+    #[wasm_bindgen(unchecked_return_type = "Response[]")]
+    pub fn users(&self) -> Result<Array, JsValue> {
+        Ok((0..self.policies.len())
+            .map(|idx| {
+                let name = if idx == 0 {
+                    "You".to_string()
+                } else {
+                    format!("Bot {}", idx)
+                };
+                serde_wasm_bindgen::to_value(&Response::User {
+                    user_id: self.arena.user(PlayerIdx(idx as u8)),
+                    user: User {
+                        name,
+                        online: true,
+                        bot: idx != 0,
+                    },
+                })
+            })
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// The events from `since` onwards, redacted for `seat`, or for a spectator if no seat is
+    /// given. Starting from zero gives a single replay of the whole game, which a client needs
+    /// before it accepts individual events.
+    #[wasm_bindgen(unchecked_return_type = "Response[]")]
+    pub fn responses(&self, seat: Option<u8>, since: usize) -> Result<Array, JsValue> {
+        Ok(self
+            .redacted_responses(seat.map(PlayerIdx), since)
+            .iter()
+            .map(serde_wasm_bindgen::to_value)
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Applies `action` on behalf of the player in seat 0.
+    pub fn apply(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "Action")] action: JsValue,
+    ) -> Result<(), JsValue> {
+        let action: Action = serde_wasm_bindgen::from_value(action)?;
+        self.apply_action(action)
+            .map_err(|e| JsValue::from(format!("Failed to apply action: {}", e)))
+    }
+
+    /// Lets the first bot that wants to act take one action. Returns whether any bot acted, so
+    /// the caller can keep stepping, with whatever delay it likes, until the player has to move.
+    pub fn step(&mut self) -> Result<bool, JsValue> {
+        self.step_bots()
+            .map_err(|e| JsValue::from(format!("Failed to apply bot action: {}", e)))
+    }
+}
+
+/// The same calls without JavaScript values, for Rust callers.
+impl LocalGame {
+    pub fn redacted_responses(&self, seat: Option<PlayerIdx>, since: usize) -> Vec<Response> {
+        let events = self.arena.game.events.get(since..).unwrap_or_default();
+        let redacted = events.iter().map(|event| event.redact(seat));
+        if since == 0 {
+            vec![Response::Replay {
+                game_id: self.game_id,
+                events: redacted.collect(),
+            }]
+        } else {
+            redacted
+                .map(|event| Response::Game {
+                    game_id: self.game_id,
+                    event,
+                })
+                .collect()
+        }
+    }
+
+    pub fn apply_action(&mut self, action: Action) -> Result<(), GoatError> {
+        self.arena.apply(PlayerIdx(0), action)
+    }
+
+    pub fn step_bots(&mut self) -> Result<bool, GoatError> {
+        let policies: Vec<&dyn Policy> = self.policies.iter().map(|p| &**p).collect();
+        Ok(self.arena.step(&policies, |_, _, _| {})?.is_some())
+    }
+}
@@ -1,12 +1,18 @@
-use serde::{Serialize, Serializer};
+use schemars::JsonSchema;
+use serde::Serialize;
+use ts_rs::TS;
 
 use goat_api::{Card, PlayerIdx, RummyHistory};
 
+/// The last thing each player did in the rummy phase.
+#[derive(JsonSchema, Serialize, TS)]
+#[serde(transparent)]
+#[ts(as = "Vec<LastAction>")]
 pub struct OneAction {
     history: Box<[LastAction]>,
 }
 
-#[derive(Clone, Copy, Serialize)]
+#[derive(Clone, Copy, JsonSchema, Serialize, TS)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 pub enum LastAction {
@@ -79,12 +85,3 @@ impl RummyHistory for OneAction {
         self.history[player.idx()] = LastAction::PickUp { lo, hi };
     }
 }
-
-impl Serialize for OneAction {
-    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        self.history.serialize(ser)
-    }
-}
@@ -0,0 +1,32 @@
+use goat_api::{check_generated, json_schema, Cards, Client, ClientPhase, PlayerIdx};
+use goat_bot::legal_actions;
+
+use crate::{typescript, GameView, LocalGame};
+
+#[test]
+fn game_view_is_up_to_date() {
+    check_generated("frontend/src/protocol/goat_wasm.ts", &typescript().module());
+    let json = serde_json::to_string_pretty(&json_schema::<GameView>()).unwrap() + "\n";
+    check_generated("schema/game_view.schema.json", &json);
+}
+
+#[test]
+fn local_game_plays_to_the_end() {
+    let mut game = LocalGame::new(3, 1, "simple", Some(5)).unwrap();
+    let mut client = Client::<(), (), Cards>::new(());
+    let mut seen = 0;
+    loop {
+        while game.step_bots().unwrap() {}
+        for response in game.redacted_responses(Some(PlayerIdx(0)), seen) {
+            client.apply(response).unwrap();
+        }
+        seen = game.num_events();
+        let view = client.games.values().next().unwrap();
+        if let ClientPhase::Goat(_) = view.phase {
+            break;
+        }
+        // Taking the first legal action every time is enough to finish the game.
+        let action = legal_actions(view, PlayerIdx(0))[0];
+        game.apply_action(action).unwrap();
+    }
+}
@@ -4,6 +4,10 @@
  */
 import init, { Client } from "generated/goat_wasm";
 
+/** The messages and views the simulator takes and returns, generated from the Rust types. */
+export type * from "protocol/goat_api";
+export type * from "protocol/goat_wasm";
+
 /** Initialize and create a game simulator. */
 export async function simulator(): Promise<Client> {
     await init();
@@ -0,0 +1,75 @@
+// Generated from the Rust types, do not edit. Regenerate with
+// `GOAT_UPDATE_PROTOCOL=1 cargo test`.
+
+export type Action = { "type": "join", userId: UserId, } | { "type": "leave", player: PlayerIdx, } | { "type": "invite", userId: UserId, } | { "type": "addEntropy", entropy: RandId, } | { "type": "start", numDecks: number, } | { "type": "playCard", card: Card, } | { "type": "playTop" } | { "type": "slough", card: Card, } | { "type": "draw" } | { "type": "finishTrick" } | { "type": "playRun", lo: Card, hi: Card, } | { "type": "pickUp" } | { "type": "goat", noise: number, };
+
+export type Response = { "type": "hello", version: number, capabilities: Array<Capability>, } | { "type": "ping" } | { "type": "replay", gameId: GameId, events: Array<Event>, } | { "type": "game", gameId: GameId, event: Event, } | { "type": "snapshot", gameId: GameId, game: ClientGame<WarTrick | null, Cards>, } | { "type": "goatMeter", gameId: GameId, goatChances: Array<number>, } | { "type": "duplicateSet", gameIds: Array<GameId>, } | { "type": "forgetGame", gameId: GameId, } | { "type": "user", userId: UserId, user: User, } | { "type": "forgetUser", userId: UserId, };
+
+export type Event = { "type": "commit", commitment: Commitment, } | { "type": "join", userId: UserId, } | { "type": "leave", player: PlayerIdx, } | { "type": "invite", userId: UserId, } | { "type": "addEntropy", entropy: RandId, } | { "type": "start", numDecks: number, } | { "type": "playCard", card: Card, } | { "type": "playTop", card: Card, } | { "type": "slough", player: PlayerIdx, card: Card, } | { "type": "draw", player: PlayerIdx, card: Card, } | { "type": "finishTrick", player: PlayerIdx, } | { "type": "revealTrump", trump: Card, } | { "type": "offerDreck", player: PlayerIdx, dreck: Cards, } | { "type": "receiveDreck", player: PlayerIdx, dreck: Cards, } | { "type": "playRun", lo: Card, hi: Card, } | { "type": "pickUp" } | { "type": "goat", noise: number, } | { "type": "revealSeed", seed: Seed, nonce: Nonce, } | { "type": "redactedDraw", player: PlayerIdx, } | { "type": "redactedOfferDreck", player: PlayerIdx, dreck: number, } | { "type": "redactedReceiveDreck", player: PlayerIdx, dreck: number, };
+
+export type User = { name: string, online: boolean, 
+/**
+ * Whether the user is a bot, so that it can be marked as one.
+ */
+bot: boolean, };
+
+export type Capability = "snapshots";
+
+export type SeatScore = { table: number, seat: PlayerIdx, userId: UserId, place: number, 
+/**
+ * One for every table where the seat placed worse, and a half for every table where it
+ * placed the same.
+ */
+matchpoints: number, };
+
+export type GoatError = { "code": "cannotDrawFromEmptyDeck" } | { "code": "cannotDrawMoreThanThreeCards" } | { "code": "cannotFinishIncompleteTrick" } | { "code": "cannotPickUpFromEmptyTrick" } | { "code": "cannotPlayFromEmptyDeck" } | { "code": "cannotPlayOnFinishedTrick" } | { "code": "cannotPlayRange", lo: Card, } | { "code": "cannotSloughOnEndedTrick" } | { "code": "gameNotFinished" } | { "code": "illegalSlough", card: Card, } | { "code": "invalidAction" } | { "code": "invalidCard", card: string, } | { "code": "invalidHex", hex: string, } | { "code": "invalidGame", gameId: GameId, } | { "code": "invalidId", id: string, } | { "code": "invalidNotation", text: string, } | { "code": "invalidNumberOfDecks" } | { "code": "invalidNumberOfPlayers" } | { "code": "invalidPlayer", userId: UserId, } | { "code": "invalidRange", lo: Card, hi: Card, } | { "code": "invalidState", what: string, } | { "code": "mustMatchRank", rank: Rank, } | { "code": "noFreeShows" } | { "code": "notYourCard", card: Card, } | { "code": "notYourTurn", player: PlayerIdx, } | { "code": "noSuchPlayer", player: PlayerIdx, };
+
+export type GameId = RandId;
+
+export type UserId = RandId;
+
+export type PlayerIdx = number;
+
+export type RandId = string;
+
+export type Card = string;
+
+export type Cards = Array<string>;
+
+export type Rank = string;
+
+export type ClientGame<PrevTrick, History> = { phase: ClientPhase<PrevTrick, History>, players: Array<UserId>, };
+
+export type ClientPhase<PrevTrick, History> = { "type": "unstarted" } | { "type": "war" } & WarPhase<ClientDeck, ClientWarHand, PrevTrick> | { "type": "rummy" } & RummyPhase<ClientRummyHand, History> | { "type": "goat" } & GoatPhase;
+
+export type WarPhase<Deck, Hand, Trick> = { deck: Deck, hands: Array<Hand>, won: Array<Cards>, trick: WarTrick, prevTrick: Trick, };
+
+export type ClientDeck = number;
+
+export type ClientWarHand = { "type": "visible", cards: ServerWarHand, } | { "type": "hidden", length: number, };
+
+export type ServerWarHand = Array<string>;
+
+export type WarTrick = { next: number, rank: Rank, players: Array<PlayerIdx>, winners: Array<PlayerIdx>, plays: Array<WarPlay>, notEnded: Array<PlayerIdx>, };
+
+export type WarPlay = { player: PlayerIdx, kind: WarPlayKind, card: Card, };
+
+export type WarPlayKind = "playHand" | "playTop" | "slough";
+
+export type RummyPhase<Hand, History> = { hands: Array<Hand>, trick: RummyTrick, next: PlayerIdx, trump: Card, 
+/**
+ * How many times each player has picked up since a trick was last killed or a hand emptied.
+ */
+pickUps: Array<number>, history: History, };
+
+export type ClientRummyHand = { known: Cards, unknown: number, };
+
+export type RummyTrick = { plays: Array<[Card, Card]>, numPlayers: number, };
+
+export type GoatPhase = { goat: PlayerIdx, noise?: number | null, };
+
+export type Seed = string;
+
+export type Nonce = string;
+
+export type Commitment = string;
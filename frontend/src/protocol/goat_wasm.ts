@@ -0,0 +1,23 @@
+// Generated from the Rust types, do not edit. Regenerate with
+// `GOAT_UPDATE_PROTOCOL=1 cargo test`.
+import type { Card, ClientDeck, GoatPhase, PlayerIdx, Rank, RummyTrick, UserId, WarPlayKind } from "./goat_api";
+
+export type GameView = { phase: PhaseView, players: Array<UserId>, };
+
+export type PhaseView = { "type": "unstarted" } | { "type": "war", deck: ClientDeck, hands: Array<WarHandView>, won: Array<number>, finished: boolean, currTrick: WarTrickView, prevTrick?: WarTrickView | null, } | { "type": "rummy", hands: Array<RummyHandView>, trick: RummyTrick, next: PlayerIdx, trump: Card, history: OneAction, } | { "type": "goat" } & GoatPhase;
+
+export type WarHandView = { "type": "visible", cards: Array<WarCard>, } | { "type": "hidden", length: number, };
+
+export type WarCard = { card: Card, playable: boolean, sloughable: boolean, };
+
+export type WarTrickView = { next?: PlayerIdx | null, rank?: Rank | null, plays: Array<WarPlayView>, winner?: PlayerIdx | null, ended: Array<boolean>, };
+
+export type WarPlayView = { card: Card, player: PlayerIdx, kind: WarPlayKind, lead: boolean, };
+
+export type RummyHandView = { cards: Array<RummyCard>, length: number, };
+
+export type RummyCard = { card: Card, runMin: Card, canPlay: boolean, };
+
+export type OneAction = Array<{ "type": "lead", lo: Card, hi: Card, } | { "type": "play", lo: Card, hi: Card, } | { "type": "kill", lo: Card, hi: Card, } | { "type": "killAndLead", killLo: Card, killHi: Card, leadLo: Card, leadHi: Card, } | { "type": "pickUp", lo: Card, hi: Card, } | { "type": "none" }>;
+
+export type LastAction = { "type": "lead", lo: Card, hi: Card, } | { "type": "play", lo: Card, hi: Card, } | { "type": "kill", lo: Card, hi: Card, } | { "type": "killAndLead", killLo: Card, killHi: Card, leadLo: Card, leadHi: Card, } | { "type": "pickUp", lo: Card, hi: Card, } | { "type": "none" };
@@ -0,0 +1,11 @@
+#![no_main]
+
+use goat_api::Action;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(action) = serde_json::from_slice::<Action>(data) {
+        let json = serde_json::to_vec(&action).unwrap();
+        assert_eq!(serde_json::from_slice::<Action>(&json).unwrap(), action);
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use goat_api::RandId;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    if let Ok(id) = data.parse::<RandId>() {
+        assert_eq!(id.to_string(), data);
+    }
+    if let Ok(id) = serde_json::from_str::<RandId>(data) {
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(serde_json::from_str::<RandId>(&json).unwrap(), id);
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use goat_api::Response;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(response) = serde_json::from_slice::<Response>(data) {
+        let json = serde_json::to_vec(&response).unwrap();
+        assert_eq!(serde_json::from_slice::<Response>(&json).unwrap(), response);
+    }
+});
@@ -0,0 +1,68 @@
+#![no_main]
+
+use arbitrary::{Result, Unstructured};
+use goat_api::{Action, Card, InvariantChecker, PlayerIdx, RandId, Rank, ServerGame, Suit, UserId};
+use libfuzzer_sys::fuzz_target;
+
+/// The users that may send actions. There are a few more than can take part in a game, so that
+/// some actions come from users who aren't playing.
+const USERS: u8 = 20;
+
+fn user(u: &mut Unstructured) -> Result<UserId> {
+    let idx = u.int_in_range(0..=USERS - 1)?;
+    Ok(UserId(RandId::from_hash(&[idx; 16])))
+}
+
+fn card(u: &mut Unstructured) -> Result<Card> {
+    let rank = Rank::from(u.int_in_range(0..=12)?);
+    let suit = Suit::from(u.int_in_range(0..=3)?);
+    Ok(Card::new(rank, suit))
+}
+
+fn action(u: &mut Unstructured) -> Result<Action> {
+    Ok(match u.int_in_range(0..=12)? {
+        0 => Action::Join { user_id: user(u)? },
+        1 => Action::Leave {
+            player: PlayerIdx(u.arbitrary()?),
+        },
+        2 => Action::Invite { user_id: user(u)? },
+        3 => Action::Start {
+            num_decks: u.arbitrary()?,
+        },
+        4 => Action::PlayCard { card: card(u)? },
+        5 => Action::PlayTop,
+        6 => Action::Slough { card: card(u)? },
+        7 => Action::Draw,
+        8 => Action::FinishTrick,
+        9 => Action::PlayRun {
+            lo: card(u)?,
+            hi: card(u)?,
+        },
+        10 => Action::PickUp,
+        11 => Action::AddEntropy {
+            entropy: RandId::from_hash(&u.arbitrary::<[u8; 16]>()?),
+        },
+        _ => Action::Goat {
+            noise: u.arbitrary()?,
+        },
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut u = Unstructured::new(data);
+    let Ok(seed) = u.arbitrary() else {
+        return;
+    };
+    let mut game = ServerGame::with_seed(seed);
+    let mut checker = InvariantChecker::new();
+    // Once the input runs out, `Unstructured` keeps producing default values, so stop there.
+    while !u.is_empty() {
+        let (Ok(user_id), Ok(action)) = (user(&mut u), action(&mut u)) else {
+            return;
+        };
+        let _ = game.apply(user_id, action);
+        if let Err(e) = checker.check(&game) {
+            panic!("{} after {:?} from {}", e, action, user_id);
+        }
+    }
+});